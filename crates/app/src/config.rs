@@ -2,18 +2,29 @@ use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use inkstone_core::domain::search::QueryOperator;
+use inkstone_infra::search::{FieldBoosts, SnippetOptions};
+use serde::Deserialize;
 use thiserror::Error;
 
+const DEFAULT_FEED_SOURCE: &str = "blog";
+
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     pub http_addr: SocketAddr,
     pub index_dir: PathBuf,
-    pub feed_url: String,
+    pub feed_sources: Vec<FeedSource>,
     pub poll_interval: Duration,
     pub douban_poll_interval: Duration,
     pub comments_sync_interval: Duration,
     pub request_timeout: Duration,
     pub max_search_limit: usize,
+    pub search_default_operator: QueryOperator,
+    pub search_boosts: FieldBoosts,
+    pub search_snippets: SnippetOptions,
+    pub search_synonyms_path: Option<PathBuf>,
+    pub search_user_dict_path: Option<PathBuf>,
+    pub search_stop_words_path: Option<PathBuf>,
     pub database_url: Option<String>,
     pub douban_max_pages: usize,
     pub douban_uid: String,
@@ -23,6 +34,7 @@ pub struct AppConfig {
     pub stats_secret: Option<String>,
     pub search_hash_secret: Option<String>,
     pub valid_paths_url: String,
+    pub search_popularity_interval: Duration,
     pub kudos_flush_interval: Duration,
     pub github_webhook_secret: Option<String>,
     pub github_discussion_webhook_secret: Option<String>,
@@ -38,7 +50,26 @@ pub struct AppConfig {
     pub admin_token_secret: Option<String>,
 }
 
+// One feed merged into the search index. Relative entry links resolve against
+// `base_url`, or the feed's own origin when it is unset.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FeedSource {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default = "default_source_weight")]
+    pub weight: f32,
+}
+
+fn default_source_weight() -> f32 {
+    1.0
+}
+
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum ConfigError {
     #[error("invalid socket address: {0}")]
     InvalidSocket(String),
@@ -67,11 +98,64 @@ impl AppConfig {
                 feed_url,
             ));
         }
+        let feed_sources = match read_optional_string("INKSTONE_FEED_SOURCES")? {
+            Some(raw) => parse_feed_sources(&raw)?,
+            None => vec![FeedSource {
+                name: DEFAULT_FEED_SOURCE.to_string(),
+                url: feed_url,
+                base_url: None,
+                category: None,
+                weight: default_source_weight(),
+            }],
+        };
         let poll_interval_secs = read_u64("INKSTONE_POLL_INTERVAL_SECS", 300)?;
         let douban_poll_interval_secs =
             read_u64("INKSTONE_DOUBAN_POLL_INTERVAL_SECS", poll_interval_secs)?;
         let request_timeout_secs = read_u64("INKSTONE_REQUEST_TIMEOUT_SECS", 15)?;
         let max_search_limit = read_usize("INKSTONE_MAX_SEARCH_LIMIT", 50)?;
+        let search_default_operator_raw =
+            read_string("INKSTONE_SEARCH_DEFAULT_OPERATOR", "and")?;
+        let search_default_operator = parse_query_operator(&search_default_operator_raw)
+            .ok_or_else(|| {
+                ConfigError::InvalidValue(
+                    "INKSTONE_SEARCH_DEFAULT_OPERATOR",
+                    search_default_operator_raw.clone(),
+                )
+            })?;
+        let default_boosts = FieldBoosts::default();
+        let search_boosts = FieldBoosts {
+            title: read_boost("INKSTONE_SEARCH_BOOST_TITLE", default_boosts.title)?,
+            subtitle: read_boost("INKSTONE_SEARCH_BOOST_SUBTITLE", default_boosts.subtitle)?,
+            content: read_boost("INKSTONE_SEARCH_BOOST_CONTENT", default_boosts.content)?,
+            tags: read_boost("INKSTONE_SEARCH_BOOST_TAGS", default_boosts.tags)?,
+            category: read_boost("INKSTONE_SEARCH_BOOST_CATEGORY", default_boosts.category)?,
+        };
+        let default_snippets = SnippetOptions::default();
+        let search_snippets = SnippetOptions {
+            max_chars: read_usize("INKSTONE_SEARCH_SNIPPET_CHARS", default_snippets.max_chars)?,
+            fragments: read_usize(
+                "INKSTONE_SEARCH_SNIPPET_FRAGMENTS",
+                default_snippets.fragments,
+            )?,
+        };
+        if search_snippets.max_chars == 0 {
+            return Err(ConfigError::InvalidValue(
+                "INKSTONE_SEARCH_SNIPPET_CHARS",
+                "0".to_string(),
+            ));
+        }
+        if search_snippets.fragments == 0 {
+            return Err(ConfigError::InvalidValue(
+                "INKSTONE_SEARCH_SNIPPET_FRAGMENTS",
+                "0".to_string(),
+            ));
+        }
+        let search_synonyms_path =
+            read_optional_string("INKSTONE_SEARCH_SYNONYMS_PATH")?.map(PathBuf::from);
+        let search_user_dict_path =
+            read_optional_string("INKSTONE_SEARCH_USER_DICT_PATH")?.map(PathBuf::from);
+        let search_stop_words_path =
+            read_optional_string("INKSTONE_SEARCH_STOP_WORDS_PATH")?.map(PathBuf::from);
         let database_url = read_optional_string("INKSTONE_DATABASE_URL")?;
         let comments_sync_secs = read_u64("INKSTONE_COMMENTS_SYNC_SECS", 432000)?;
         let douban_max_pages = read_usize("INKSTONE_DOUBAN_MAX_PAGES", 1)?;
@@ -88,6 +172,7 @@ impl AppConfig {
             "INKSTONE_VALID_PATHS_URL",
             "https://velite-refactor.blog-8fo.pages.dev/valid_paths.txt",
         )?;
+        let search_popularity_secs = read_u64("INKSTONE_SEARCH_POPULARITY_REFRESH_SECS", 3600)?;
         let kudos_flush_secs = read_u64("INKSTONE_KUDOS_FLUSH_SECS", 60)?;
         let github_webhook_secret = read_optional_string("INKSTONE_GITHUB_WEBHOOK_SECRET")?;
         let github_discussion_webhook_secret =
@@ -108,12 +193,18 @@ impl AppConfig {
         Ok(Self {
             http_addr,
             index_dir,
-            feed_url,
+            feed_sources,
             poll_interval: Duration::from_secs(poll_interval_secs),
             douban_poll_interval: Duration::from_secs(douban_poll_interval_secs),
             comments_sync_interval: Duration::from_secs(comments_sync_secs),
             request_timeout: Duration::from_secs(request_timeout_secs),
             max_search_limit,
+            search_default_operator,
+            search_boosts,
+            search_snippets,
+            search_synonyms_path,
+            search_user_dict_path,
+            search_stop_words_path,
             database_url,
             douban_max_pages,
             douban_uid,
@@ -123,6 +214,7 @@ impl AppConfig {
             stats_secret,
             search_hash_secret,
             valid_paths_url,
+            search_popularity_interval: Duration::from_secs(search_popularity_secs),
             kudos_flush_interval: Duration::from_secs(kudos_flush_secs),
             github_webhook_secret,
             github_discussion_webhook_secret,
@@ -140,6 +232,66 @@ impl AppConfig {
     }
 }
 
+fn parse_query_operator(value: &str) -> Option<QueryOperator> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "and" => Some(QueryOperator::And),
+        "or" => Some(QueryOperator::Or),
+        _ => None,
+    }
+}
+
+// A JSON array of sources; names must be unique since documents are tracked
+// and filtered by them.
+fn parse_feed_sources(raw: &str) -> Result<Vec<FeedSource>, ConfigError> {
+    let invalid = |reason: String| ConfigError::InvalidValue("INKSTONE_FEED_SOURCES", reason);
+    let sources: Vec<FeedSource> =
+        serde_json::from_str(raw).map_err(|err| invalid(err.to_string()))?;
+    if sources.is_empty() {
+        return Err(invalid("no sources".to_string()));
+    }
+    let mut names = std::collections::HashSet::new();
+    let mut normalized = Vec::with_capacity(sources.len());
+    for source in sources {
+        let name = source.name.trim().to_string();
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+        if !valid_name {
+            return Err(invalid(format!("invalid source name: {name:?}")));
+        }
+        if !names.insert(name.clone()) {
+            return Err(invalid(format!("duplicate source name: {name}")));
+        }
+        let url = source.url.trim().to_string();
+        if url.is_empty() {
+            return Err(invalid(format!("missing url for source {name}")));
+        }
+        if !source.weight.is_finite() || source.weight <= 0.0 {
+            return Err(invalid(format!("invalid weight for source {name}")));
+        }
+        let trimmed = |value: Option<String>| {
+            value
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let base_url =
+            trimmed(source.base_url).map(|value| value.trim_end_matches('/').to_string());
+        // Local feeds have no origin, so their post ids would collide across sources.
+        if base_url.is_none() && url.starts_with("file://") {
+            return Err(invalid(format!("missing base_url for local source {name}")));
+        }
+        normalized.push(FeedSource {
+            name,
+            url,
+            base_url,
+            category: trimmed(source.category),
+            weight: source.weight,
+        });
+    }
+    Ok(normalized)
+}
+
 pub fn load_dotenv() -> Result<(), std::io::Error> {
     let path = Path::new(".env");
     if !path.exists() {
@@ -184,6 +336,16 @@ fn read_usize(key: &'static str, default: usize) -> Result<usize, ConfigError> {
         .map_err(|_| ConfigError::InvalidNumber(key, raw))
 }
 
+fn read_boost(key: &'static str, default: f32) -> Result<f32, ConfigError> {
+    let Some(raw) = read_raw(key)? else {
+        return Ok(default);
+    };
+    match raw.parse::<f32>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => Ok(value),
+        _ => Err(ConfigError::InvalidNumber(key, raw)),
+    }
+}
+
 fn read_optional_string(key: &'static str) -> Result<Option<String>, ConfigError> {
     read_raw(key)
 }
@@ -280,7 +442,7 @@ fn unescape_double_quoted(value: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_dotenv_line, read_string, read_u64, ConfigError};
+    use super::{parse_dotenv_line, parse_feed_sources, read_string, read_u64, ConfigError};
     use std::path::PathBuf;
     use std::sync::Mutex;
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -426,4 +588,36 @@ mod tests {
         let err = read_string("INKSTONE_TEST_MISSING", "default").unwrap_err();
         assert!(matches!(err, ConfigError::InvalidFile("INKSTONE_TEST_MISSING", _)));
     }
+
+    #[test]
+    fn parse_feed_sources_applies_defaults_and_rejects_duplicates() {
+        let sources = parse_feed_sources(
+            r#"[
+                {"name": "blog", "url": "https://example.com/search-index.json"},
+                {"name": "notes", "url": "file:///srv/notes",
+                 "base_url": "https://notes.example.com/", "category": " wiki ", "weight": 0.5}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].weight, 1.0);
+        assert_eq!(sources[0].base_url, None);
+        assert_eq!(sources[1].base_url.as_deref(), Some("https://notes.example.com"));
+        assert_eq!(sources[1].category.as_deref(), Some("wiki"));
+        assert_eq!(sources[1].weight, 0.5);
+
+        for raw in [
+            "[]",
+            r#"[{"name": "a", "url": "x"}, {"name": "a", "url": "y"}]"#,
+            r#"[{"name": "a b", "url": "x"}]"#,
+            r#"[{"name": "a", "url": " "}]"#,
+            r#"[{"name": "a", "url": "x", "weight": 0}]"#,
+            r#"[{"name": "a", "url": "file:///srv/notes"}]"#,
+        ] {
+            assert!(
+                matches!(parse_feed_sources(raw), Err(ConfigError::InvalidValue(_, _))),
+                "{raw}"
+            );
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use axum::body::Body;
 use axum::extract::State;
 use axum::http::header::{AUTHORIZATION, COOKIE, SET_COOKIE};
-use axum::http::Request;
+use axum::http::{HeaderMap, Request};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
@@ -48,8 +48,8 @@ pub async fn require_admin(
         .filter(|value| !value.is_empty())
         .ok_or(AdminAuthError::MissingConfig)?;
 
-    let token = extract_bearer_token(&request)
-        .or_else(|| extract_cookie(&request, ADMIN_COOKIE_NAME));
+    let token = extract_bearer_token(request.headers())
+        .or_else(|| extract_cookie(request.headers(), ADMIN_COOKIE_NAME));
     let token = token.ok_or(AdminAuthError::MissingToken)?;
     if !verify_token(secret, &token) {
         return Err(AdminAuthError::InvalidToken);
@@ -57,6 +57,21 @@ pub async fn require_admin(
     Ok(next.run(request).await)
 }
 
+// For public routes that unlock extra output for admins instead of rejecting everyone else.
+pub fn has_admin_token(state: &AppState, headers: &HeaderMap) -> bool {
+    let Some(secret) = state
+        .config
+        .admin_token_secret
+        .as_deref()
+        .filter(|value| !value.is_empty())
+    else {
+        return false;
+    };
+    extract_bearer_token(headers)
+        .or_else(|| extract_cookie(headers, ADMIN_COOKIE_NAME))
+        .is_some_and(|token| verify_token(secret, &token))
+}
+
 pub fn issue_token(secret: &str, max_age_secs: i64) -> Result<String, AdminAuthError> {
     let exp = Utc::now().timestamp().saturating_add(max_age_secs);
     let payload = AdminTokenPayload { exp };
@@ -112,8 +127,8 @@ fn sign_token(secret: &str, payload_b64: &str) -> String {
     URL_SAFE_NO_PAD.encode(raw)
 }
 
-fn extract_bearer_token<B>(request: &Request<B>) -> Option<String> {
-    let header = request.headers().get(AUTHORIZATION)?.to_str().ok()?;
+fn extract_bearer_token(headers: &HeaderMap) -> Option<String> {
+    let header = headers.get(AUTHORIZATION)?.to_str().ok()?;
     let header = header.trim();
     let value = header.strip_prefix("Bearer ")?;
     let value = value.trim();
@@ -124,8 +139,8 @@ fn extract_bearer_token<B>(request: &Request<B>) -> Option<String> {
     }
 }
 
-fn extract_cookie<B>(request: &Request<B>, name: &str) -> Option<String> {
-    let header = request.headers().get(COOKIE)?.to_str().ok()?;
+fn extract_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    let header = headers.get(COOKIE)?.to_str().ok()?;
     for part in header.split(';') {
         let trimmed = part.trim();
         let mut iter = trimmed.splitn(2, '=');
@@ -139,22 +154,21 @@ fn extract_cookie<B>(request: &Request<B>, name: &str) -> Option<String> {
 }
 
 pub fn is_https(headers: &axum::http::HeaderMap) -> bool {
-    if let Some(value) = headers.get("x-forwarded-proto") {
-        if let Ok(value) = value.to_str() {
-            if value.split(',').any(|part| part.trim().eq_ignore_ascii_case("https")) {
-                return true;
-            }
-        }
+    if let Some(value) = headers.get("x-forwarded-proto")
+        && let Ok(value) = value.to_str()
+        && value.split(',').any(|part| part.trim().eq_ignore_ascii_case("https"))
+    {
+        return true;
     }
-    if let Some(value) = headers.get("forwarded") {
-        if let Ok(value) = value.to_str() {
-            for part in value.split(';') {
-                let part = part.trim();
-                if let Some(proto) = part.strip_prefix("proto=") {
-                    if proto.trim().eq_ignore_ascii_case("https") {
-                        return true;
-                    }
-                }
+    if let Some(value) = headers.get("forwarded")
+        && let Ok(value) = value.to_str()
+    {
+        for part in value.split(';') {
+            let part = part.trim();
+            if let Some(proto) = part.strip_prefix("proto=")
+                && proto.trim().eq_ignore_ascii_case("https")
+            {
+                return true;
             }
         }
     }
@@ -182,7 +196,9 @@ pub fn attach_cookie(mut response: Response, cookie_value: String) -> Response {
 
 #[cfg(test)]
 mod tests {
-    use super::{build_cookie_value, issue_token, verify_token};
+    use super::{build_cookie_value, extract_bearer_token, issue_token, verify_token};
+    use axum::http::header::AUTHORIZATION;
+    use axum::http::HeaderMap;
 
     #[test]
     fn issue_token_round_trip() {
@@ -197,4 +213,12 @@ mod tests {
         assert!(cookie.contains("Path=/v2/admin"));
         assert!(cookie.contains("Max-Age=60"));
     }
+
+    #[test]
+    fn extract_bearer_token_reads_authorization_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(extract_bearer_token(&headers), None);
+        headers.insert(AUTHORIZATION, "Bearer  token ".parse().unwrap());
+        assert_eq!(extract_bearer_token(&headers).as_deref(), Some("token"));
+    }
 }
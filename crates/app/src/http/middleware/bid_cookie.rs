@@ -84,10 +84,10 @@ pub async fn ensure_bid_cookie(
     });
 
     let mut response = next.run(request).await;
-    if let Some(cookie_value) = set_cookie {
-        if let Ok(value) = cookie_value.parse() {
-            response.headers_mut().append(SET_COOKIE, value);
-        }
+    if let Some(cookie_value) = set_cookie
+        && let Ok(value) = cookie_value.parse()
+    {
+        response.headers_mut().append(SET_COOKIE, value);
     }
     Ok(response)
 }
@@ -170,9 +170,8 @@ fn encode_token(bytes: &[u8]) -> String {
 
 fn build_cookie_value(token: &str, sig: &str) -> String {
     format!(
-        "{name}={value}; Path=/; Secure; HttpOnly; SameSite=Lax; Max-Age={max_age}",
+        "{name}={token}.{sig}; Path=/; Secure; HttpOnly; SameSite=Lax; Max-Age={max_age}",
         name = COOKIE_NAME,
-        value = format!("{token}.{sig}"),
         max_age = COOKIE_MAX_AGE_SECS
     )
 }
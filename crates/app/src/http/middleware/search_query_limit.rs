@@ -25,7 +25,7 @@ pub async fn enforce_search_query_length(req: Request, next: Next) -> Response {
 }
 
 fn is_query_too_long(query: Option<&str>) -> bool {
-    query.map_or(false, |value| value.len() > MAX_SEARCH_QUERY_LEN)
+    query.is_some_and(|value| value.len() > MAX_SEARCH_QUERY_LEN)
 }
 
 #[cfg(test)]
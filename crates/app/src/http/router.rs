@@ -7,7 +7,10 @@ use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 
 use crate::http::middleware::{admin_auth, bid_cookie, search_query_limit};
 use crate::state::AppState;
-use crate::http::routes::{admin, analytics, comments, douban, health, kudos, search, webhook};
+use crate::http::routes::{
+    admin, analytics, comments, douban, health, kudos, search, search_related, search_suggest,
+    webhook,
+};
 
 pub fn build(state: AppState) -> Router {
     let cors = build_cors(&state);
@@ -18,6 +21,13 @@ pub fn build(state: AppState) -> Router {
             get(search::search)
                 .layer(middleware::from_fn(search_query_limit::enforce_search_query_length)),
         )
+        .route("/v2/search/click", post(search::post_click))
+        .route("/v2/search/related", get(search_related::related))
+        .route(
+            "/v2/search/suggest",
+            get(search_suggest::suggest)
+                .layer(middleware::from_fn(search_query_limit::enforce_search_query_length)),
+        )
         .route("/v2/douban/marks", get(douban::marks_this_year))
         .route("/v2/comments", get(comments::get_comments))
         .route("/v2/kudos", get(kudos::get_kudos).put(kudos::put_kudos))
@@ -82,6 +92,10 @@ pub fn build(state: AppState) -> Router {
             "/v2/admin/search/status",
             get(admin::search_reindex::get_search_status),
         )
+        .route(
+            "/v2/admin/search/synonyms/reload",
+            post(admin::search_synonyms::post_synonyms_reload),
+        )
         .route("/webhook/github/content", post(webhook::github_webhook))
         .route(
             "/webhook/github/discussions",
@@ -209,7 +209,7 @@ fn map_overview(
             total: recent_total,
             items: recent_items
                 .into_iter()
-                .map(|item| map_recent_entry(item))
+                .map(map_recent_entry)
                 .collect(),
         },
     }
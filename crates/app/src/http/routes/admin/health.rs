@@ -22,6 +22,7 @@ pub struct AdminJobsStatus {
     pub douban_crawl: AdminJobStatus,
     pub comments_sync: AdminJobStatus,
     pub kudos_flush: AdminJobStatus,
+    pub search_popularity: AdminJobStatus,
 }
 
 #[derive(Debug, Serialize)]
@@ -126,6 +127,10 @@ fn map_jobs(snapshot: &AdminHealthState) -> AdminJobsStatus {
             last_run_at: format_timestamp(snapshot.kudos_flush_last_run),
             last_success_at: format_timestamp(snapshot.kudos_flush_last_success),
         },
+        search_popularity: AdminJobStatus {
+            last_run_at: format_timestamp(snapshot.search_popularity_last_run),
+            last_success_at: format_timestamp(snapshot.search_popularity_last_success),
+        },
     }
 }
 
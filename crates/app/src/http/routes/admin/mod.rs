@@ -7,3 +7,4 @@ pub mod kudos;
 pub mod pulse;
 pub mod search_reindex;
 pub mod search_stats;
+pub mod search_synonyms;
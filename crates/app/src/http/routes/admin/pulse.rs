@@ -395,7 +395,7 @@ fn normalize_host_value(value: &str) -> Result<String, PulseAdminError> {
         .or_else(|| trimmed.strip_prefix("http://"))
         .unwrap_or(trimmed);
     let host_list = without_scheme.split(',').next().unwrap_or(without_scheme);
-    let host_port = host_list.split('@').last().unwrap_or(host_list);
+    let host_port = host_list.split('@').next_back().unwrap_or(host_list);
     let host = host_port.split(':').next().unwrap_or(host_port).trim();
     if host.is_empty() || host.chars().any(|ch| ch.is_whitespace()) {
         return Err(PulseAdminError::InvalidSite);
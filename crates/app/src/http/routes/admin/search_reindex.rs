@@ -29,8 +29,14 @@ pub struct SearchIndexActionResponse {
 #[derive(Debug, Serialize)]
 pub struct SearchIndexStatusResponse {
     index_dir: String,
+    active_dir: String,
     doc_count: u64,
+    section_count: u64,
     segment_count: usize,
+    tokenizer_fingerprint: String,
+    tokenizer_stale: bool,
+    last_run_at: Option<String>,
+    last_deleted: Option<usize>,
 }
 
 #[derive(Debug, Serialize)]
@@ -39,6 +45,8 @@ pub struct SearchJobStats {
     indexed: usize,
     skipped: usize,
     failed: usize,
+    deleted: usize,
+    unchanged: bool,
 }
 
 pub async fn post_search_reindex(
@@ -64,11 +72,24 @@ pub async fn post_search_refresh(
 pub async fn get_search_status(
     State(state): State<AppState>,
 ) -> Result<Json<SearchIndexStatusResponse>, SearchAdminError> {
-    let stats = state.search.stats();
+    let search_index = state.search.read().await.clone();
+    let stats = search_index.stats().map_err(JobError::from)?;
+    let health = {
+        let guard = state.admin_health.lock().await;
+        guard.clone()
+    };
     Ok(Json(SearchIndexStatusResponse {
         index_dir: state.config.index_dir.display().to_string(),
+        active_dir: search_index.dir().display().to_string(),
         doc_count: stats.num_docs,
+        section_count: stats.num_sections,
         segment_count: stats.num_segments,
+        tokenizer_fingerprint: state.search_tokenizer.fingerprint().to_string(),
+        tokenizer_stale: search_index.tokenizer_stale(),
+        last_run_at: health
+            .feed_index_last_run
+            .map(|timestamp| timestamp.to_rfc3339()),
+        last_deleted: health.feed_index_last_deleted,
     }))
 }
 
@@ -87,5 +108,7 @@ fn map_job_stats(stats: feed_index::JobStats) -> SearchJobStats {
         indexed: stats.indexed,
         skipped: stats.skipped,
         failed: stats.failed,
+        deleted: stats.deleted,
+        unchanged: stats.unchanged,
     }
 }
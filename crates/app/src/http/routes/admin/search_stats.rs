@@ -8,14 +8,18 @@ use thiserror::Error;
 
 use crate::state::AppState;
 use inkstone_infra::db::{
-    fetch_filter_usage, fetch_keyword_usage, fetch_search_daily, fetch_search_summary,
-    fetch_top_categories, fetch_top_queries, fetch_top_tags, SearchDailyRow, SearchDimCountRow,
-    SearchEventsRepoError, SearchFilterUsage, SearchSummaryRow, SearchTopQueryRow,
+    fetch_abandoned_queries, fetch_click_summary, fetch_filter_usage, fetch_keyword_usage,
+    fetch_query_click_through, fetch_search_daily, fetch_search_summary, fetch_top_categories,
+    fetch_top_queries, fetch_top_tags, fetch_zero_result_queries, SearchClickSummaryRow,
+    SearchDailyRow, SearchDimCountRow, SearchEventsRepoError, SearchFilterUsage,
+    SearchQueryClicksRow, SearchSummaryRow, SearchTopQueryRow, SearchZeroResultRow,
 };
 
 const DEFAULT_RANGE_DAYS: i64 = 30;
 const DEFAULT_LIMIT: i64 = 20;
 const MAX_LIMIT: i64 = 200;
+// Queries need this many searches with results before their abandonment rate is ranked.
+const ABANDONMENT_MIN_SEARCHES: i64 = 3;
 
 #[derive(Debug, Deserialize)]
 pub struct SearchStatsQuery {
@@ -51,6 +55,10 @@ pub struct SearchStatsResponse {
     top_categories: Vec<SearchDimEntry>,
     filter_usage: SearchFilterUsageEntry,
     keyword_usage: Vec<SearchKeywordEntry>,
+    zero_result_queries: Vec<SearchZeroResultEntry>,
+    clicks: SearchClickSummary,
+    query_ctr: Vec<SearchQueryCtrEntry>,
+    abandoned_queries: Vec<SearchAbandonedEntry>,
 }
 
 #[derive(Debug, Serialize)]
@@ -106,6 +114,42 @@ pub struct SearchKeywordEntry {
     count: i64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct SearchClickSummary {
+    searches: i64,
+    clicked_searches: i64,
+    clicks: i64,
+    ctr: f64,
+    mean_click_rank: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchQueryCtrEntry {
+    query: String,
+    searches: i64,
+    clicked_searches: i64,
+    clicks: i64,
+    ctr: f64,
+    mean_click_rank: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchAbandonedEntry {
+    query: String,
+    searches_with_results: i64,
+    abandoned: i64,
+    abandonment_rate: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchZeroResultEntry {
+    query: String,
+    suggestion: Option<String>,
+    count: i64,
+    resolved: i64,
+    resolved_rate: f64,
+}
+
 pub async fn get_search_stats(
     State(state): State<AppState>,
     Query(query): Query<SearchStatsQuery>,
@@ -122,6 +166,10 @@ pub async fn get_search_stats(
         top_categories,
         filter_usage,
         keyword_usage,
+        zero_result_queries,
+        clicks,
+        query_ctr,
+        abandoned_queries,
     ) = tokio::try_join!(
         fetch_search_summary(&pool, from, to),
         fetch_search_daily(&pool, from, to),
@@ -130,6 +178,10 @@ pub async fn get_search_stats(
         fetch_top_categories(&pool, from, to, limit),
         fetch_filter_usage(&pool, from, to),
         fetch_keyword_usage(&pool, from, to, limit),
+        fetch_zero_result_queries(&pool, from, to, limit),
+        fetch_click_summary(&pool, from, to),
+        fetch_query_click_through(&pool, from, to, limit),
+        fetch_abandoned_queries(&pool, from, to, ABANDONMENT_MIN_SEARCHES, limit),
     )?;
 
     Ok(Json(SearchStatsResponse {
@@ -150,6 +202,10 @@ pub async fn get_search_stats(
                 count: entry.count,
             })
             .collect(),
+        zero_result_queries: zero_result_queries.into_iter().map(map_zero_result).collect(),
+        clicks: map_click_summary(clicks),
+        query_ctr: query_ctr.into_iter().map(map_query_ctr).collect(),
+        abandoned_queries: abandoned_queries.into_iter().map(map_abandoned).collect(),
     }))
 }
 
@@ -199,6 +255,58 @@ fn map_top_query(entry: SearchTopQueryRow) -> SearchTopQueryEntry {
     }
 }
 
+fn map_zero_result(entry: SearchZeroResultRow) -> SearchZeroResultEntry {
+    SearchZeroResultEntry {
+        resolved_rate: ratio(entry.resolved, entry.count),
+        query: entry.query_norm,
+        suggestion: entry.suggestion,
+        count: entry.count,
+        resolved: entry.resolved,
+    }
+}
+
+fn map_click_summary(entry: SearchClickSummaryRow) -> SearchClickSummary {
+    SearchClickSummary {
+        searches: entry.searches,
+        clicked_searches: entry.clicked_searches,
+        clicks: entry.clicks,
+        ctr: ratio(entry.clicked_searches, entry.searches),
+        mean_click_rank: round_rank(entry.mean_rank),
+    }
+}
+
+fn map_query_ctr(entry: SearchQueryClicksRow) -> SearchQueryCtrEntry {
+    SearchQueryCtrEntry {
+        ctr: ratio(entry.clicked_searches, entry.searches),
+        query: entry.query_norm,
+        searches: entry.searches,
+        clicked_searches: entry.clicked_searches,
+        clicks: entry.clicks,
+        mean_click_rank: round_rank(entry.mean_rank),
+    }
+}
+
+fn map_abandoned(entry: SearchQueryClicksRow) -> SearchAbandonedEntry {
+    SearchAbandonedEntry {
+        abandonment_rate: ratio(entry.abandoned, entry.with_results),
+        query: entry.query_norm,
+        searches_with_results: entry.with_results,
+        abandoned: entry.abandoned,
+    }
+}
+
+fn ratio(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+fn round_rank(value: Option<f64>) -> Option<f64> {
+    value.map(|value| (value * 100.0).round() / 100.0)
+}
+
 fn map_dim_entry(entry: SearchDimCountRow) -> SearchDimEntry {
     SearchDimEntry {
         value: entry.value,
@@ -259,13 +367,50 @@ impl IntoResponse for SearchStatsError {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_date, parse_range};
+    use super::{map_abandoned, map_query_ctr, map_zero_result, parse_date, parse_range};
+    use inkstone_infra::db::{SearchQueryClicksRow, SearchZeroResultRow};
 
     #[test]
     fn parse_date_rejects_invalid() {
         assert!(parse_date("2025-13-01").is_err());
     }
 
+    #[test]
+    fn zero_result_rate_counts_resolved_suggestions() {
+        let entry = map_zero_result(SearchZeroResultRow {
+            query_norm: "tantvy".to_string(),
+            suggestion: Some("tantivy".to_string()),
+            count: 4,
+            resolved: 3,
+        });
+        assert_eq!(entry.resolved_rate, 0.75);
+        let entry = map_zero_result(SearchZeroResultRow {
+            query_norm: "zzz".to_string(),
+            suggestion: None,
+            count: 2,
+            resolved: 0,
+        });
+        assert_eq!(entry.resolved_rate, 0.0);
+    }
+
+    #[test]
+    fn click_through_rates_use_their_own_denominators() {
+        let row = SearchQueryClicksRow {
+            query_norm: "rust".to_string(),
+            searches: 10,
+            with_results: 8,
+            clicked_searches: 4,
+            abandoned: 4,
+            clicks: 6,
+            mean_rank: Some(1.666_666),
+        };
+        let ctr = map_query_ctr(row.clone());
+        assert_eq!(ctr.ctr, 0.4);
+        assert_eq!(ctr.mean_click_rank, Some(1.67));
+        let abandoned = map_abandoned(row);
+        assert_eq!(abandoned.abandonment_rate, 0.5);
+    }
+
     #[test]
     fn parse_range_defaults() {
         let (from, to) = parse_range(None, None).unwrap();
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Serialize;
+use thiserror::Error;
+use tracing::info;
+
+use crate::state::AppState;
+use inkstone_infra::search::{SynonymError, SynonymMap};
+
+#[derive(Debug, Error)]
+pub enum SynonymsAdminError {
+    #[error("INKSTONE_SEARCH_SYNONYMS_PATH not configured")]
+    NotConfigured,
+    #[error("{0}")]
+    Synonyms(#[from] SynonymError),
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SynonymsReloadResponse {
+    path: String,
+    groups: usize,
+}
+
+pub async fn post_synonyms_reload(
+    State(state): State<AppState>,
+) -> Result<Json<SynonymsReloadResponse>, SynonymsAdminError> {
+    let path = state
+        .config
+        .search_synonyms_path
+        .as_deref()
+        .ok_or(SynonymsAdminError::NotConfigured)?;
+    let synonyms = SynonymMap::load(path)?;
+    let groups = synonyms.len();
+    *state.search_synonyms.write().await = Arc::new(synonyms);
+    info!(path = %path.display(), groups, "search synonyms reloaded");
+    Ok(Json(SynonymsReloadResponse {
+        path: path.display().to_string(),
+        groups,
+    }))
+}
+
+impl IntoResponse for SynonymsAdminError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            SynonymsAdminError::NotConfigured => StatusCode::SERVICE_UNAVAILABLE,
+            SynonymsAdminError::Synonyms(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = Json(ErrorBody {
+            error: self.to_string(),
+        });
+        (status, body).into_response()
+    }
+}
@@ -220,7 +220,7 @@ fn normalize_host_value(value: &str) -> Result<String, PulseApiError> {
         .or_else(|| trimmed.strip_prefix("http://"))
         .unwrap_or(trimmed);
     let host_list = without_scheme.split(',').next().unwrap_or(without_scheme);
-    let host_port = host_list.split('@').last().unwrap_or(host_list);
+    let host_port = host_list.split('@').next_back().unwrap_or(host_list);
     let host = host_port.split(':').next().unwrap_or(host_port).trim();
     if host.is_empty() || host.chars().any(|ch| ch.is_whitespace()) {
         return Err(PulseApiError::InvalidSite);
@@ -303,7 +303,7 @@ fn parse_ref_host(referer: &str) -> Option<String> {
         .or_else(|| trimmed.strip_prefix("http://"))
         .unwrap_or(trimmed);
     let host_port = without_scheme.split('/').next().unwrap_or(without_scheme);
-    let host_port = host_port.split('@').last().unwrap_or(host_port);
+    let host_port = host_port.split('@').next_back().unwrap_or(host_port);
     let host = host_port.split(':').next().unwrap_or(host_port).trim();
     if host.is_empty() {
         None
@@ -332,10 +332,10 @@ fn derive_entry_source(
 }
 
 fn extract_country(headers: &HeaderMap) -> Option<String> {
-    if let Some(value) = header_value(headers, "cf-ipcountry") {
-        if !value.eq_ignore_ascii_case("xx") {
-            return Some(value.to_string());
-        }
+    if let Some(value) = header_value(headers, "cf-ipcountry")
+        && !value.eq_ignore_ascii_case("xx")
+    {
+        return Some(value.to_string());
     }
     if let Some(value) = header_value(headers, "x-forwarded-for") {
         let first = value.split(',').next().unwrap_or(value).trim();
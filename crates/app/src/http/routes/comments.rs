@@ -103,11 +103,11 @@ fn build_comment_tree(records: &[inkstone_infra::db::CommentRecord]) -> Vec<Comm
     let mut children: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
     let mut roots = Vec::new();
     for (idx, node) in nodes.iter().enumerate() {
-        if let Some(parent_id) = node.parent_id.as_ref() {
-            if let Some(parent_idx) = index.get(parent_id) {
-                children[*parent_idx].push(idx);
-                continue;
-            }
+        if let Some(parent_id) = node.parent_id.as_ref()
+            && let Some(parent_idx) = index.get(parent_id)
+        {
+            children[*parent_idx].push(idx);
+            continue;
         }
         roots.push(idx);
     }
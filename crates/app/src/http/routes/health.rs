@@ -117,15 +117,19 @@ mod tests {
     use super::health;
     use axum::extract::State;
     use chrono::Duration;
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
     use std::sync::Arc;
     use tokio::sync::{Mutex, RwLock};
 
-    use crate::config::AppConfig;
+    use crate::config::{AppConfig, FeedSource};
     use crate::kudos_cache::KudosCache;
     use crate::state::{AdminHealthState, AppState, ContentRefreshBackoff};
+    use inkstone_core::domain::search::QueryOperator;
     use inkstone_infra::db::connect_lazy;
-    use inkstone_infra::search::SearchIndex;
+    use inkstone_infra::search::{
+        FieldBoosts, PopularityScores, QueryHistory, SearchIndex, SearchTokenizer, SnippetOptions,
+        SynonymMap,
+    };
 
     fn build_state(db_configured: bool) -> AppState {
         let suffix = std::time::SystemTime::now()
@@ -143,12 +147,24 @@ mod tests {
         let config = AppConfig {
             http_addr: "127.0.0.1:8080".parse().unwrap(),
             index_dir,
-            feed_url: "https://example.com/index.json".to_string(),
+            feed_sources: vec![FeedSource {
+                name: "blog".to_string(),
+                url: "https://example.com/index.json".to_string(),
+                base_url: None,
+                category: None,
+                weight: 1.0,
+            }],
             poll_interval: Duration::seconds(300).to_std().unwrap(),
             douban_poll_interval: Duration::seconds(300).to_std().unwrap(),
             comments_sync_interval: Duration::seconds(300).to_std().unwrap(),
             request_timeout: Duration::seconds(15).to_std().unwrap(),
             max_search_limit: 50,
+            search_default_operator: QueryOperator::And,
+            search_boosts: FieldBoosts::default(),
+            search_snippets: SnippetOptions::default(),
+            search_synonyms_path: None,
+            search_user_dict_path: None,
+            search_stop_words_path: None,
             database_url: None,
             douban_max_pages: 1,
             douban_uid: "93562087".to_string(),
@@ -156,7 +172,9 @@ mod tests {
             douban_user_agent: "ua".to_string(),
             cookie_secret: Some("cookie".to_string()),
             stats_secret: Some("stats".to_string()),
+            search_hash_secret: None,
             valid_paths_url: "https://example.com/paths.txt".to_string(),
+            search_popularity_interval: Duration::seconds(3600).to_std().unwrap(),
             kudos_flush_interval: Duration::seconds(60).to_std().unwrap(),
             github_webhook_secret: None,
             github_discussion_webhook_secret: None,
@@ -173,12 +191,18 @@ mod tests {
         };
         AppState {
             config: Arc::new(config),
-            search: Arc::new(search),
+            search: Arc::new(RwLock::new(Arc::new(search))),
+            search_popularity: Arc::new(RwLock::new(Arc::new(PopularityScores::new()))),
+            search_query_history: Arc::new(RwLock::new(Arc::new(QueryHistory::new()))),
+            search_synonyms: Arc::new(RwLock::new(Arc::new(SynonymMap::default()))),
+            search_tokenizer: Arc::new(SearchTokenizer::default()),
+            feed_index_runs: Arc::new(Mutex::new(())),
             http_client: reqwest::Client::new(),
             db,
             valid_paths: Arc::new(RwLock::new(HashSet::new())),
             kudos_cache: Arc::new(RwLock::new(KudosCache::default())),
             content_refresh_backoff: Arc::new(Mutex::new(ContentRefreshBackoff::default())),
+            fetch_validators: Arc::new(Mutex::new(HashMap::new())),
             admin_health: Arc::new(Mutex::new(AdminHealthState::default())),
         }
     }
@@ -5,4 +5,6 @@ pub mod douban;
 pub mod health;
 pub mod kudos;
 pub mod search;
+pub mod search_related;
+pub mod search_suggest;
 pub mod webhook;
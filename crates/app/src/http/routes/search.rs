@@ -1,5 +1,8 @@
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Instant;
 
+use axum::body::Bytes;
 use axum::extract::{Query, State};
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
@@ -13,14 +16,29 @@ use sha2::Sha256;
 use thiserror::Error;
 use tracing::{info, warn};
 
+use crate::http::middleware::admin_auth::has_admin_token;
 use crate::state::AppState;
-use inkstone_core::domain::search::{SearchHit, SearchQuery, SearchResult};
+use inkstone_core::domain::search::{
+    FacetField, HighlightFragment, KeywordExpr, SearchFacets, SearchHit, SearchQuery,
+    SearchResult,
+};
 use inkstone_core::types::time_range::TimeRange;
-use inkstone_infra::db::{fetch_recent_search_query, insert_search_event, SearchEvent};
-use inkstone_infra::search::{parse_query, QueryParseError, SearchIndexError, SearchSort};
+use inkstone_infra::db::{
+    fetch_recent_search_query, has_recent_search, insert_search_event, SearchEvent,
+    SearchEventsRepoError,
+};
+use inkstone_infra::search::{
+    closest_query, parse_query_with_operator, HighlightMode, QueryParseError, SearchCursor,
+    SearchIndex, SearchIndexError, SearchOptions, SearchSort, SearchTokenizer, SourceWeights,
+    SynonymMap,
+};
 
 const MAX_QUERY_LEN: usize = 256;
 const SEARCH_EVENT_DEDUP_SECS: i64 = 30 * 60;
+// Queries with at most this many hits get a "did you mean" suggestion.
+const SUGGESTION_MAX_TOTAL: usize = 2;
+const MAX_CLICK_URL_LEN: usize = 2048;
+const MAX_CLICK_RANK: i32 = 1000;
 
 #[derive(Debug, Deserialize)]
 pub struct SearchParams {
@@ -28,19 +46,28 @@ pub struct SearchParams {
     pub limit: Option<usize>,
     pub offset: Option<usize>,
     pub sort: Option<SearchSortParam>,
+    pub facets: Option<String>,
+    pub explain: Option<bool>,
+    pub cursor: Option<String>,
+    pub highlight: Option<HighlightParam>,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Deserialize)]
+pub struct SearchClickRequest {
+    pub query: Option<String>,
+    pub url: Option<String>,
+    pub rank: Option<i32>,
+    pub sort: Option<SearchSortParam>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum SearchSortParam {
+    #[default]
     Relevance,
     Latest,
-}
-
-impl Default for SearchSortParam {
-    fn default() -> Self {
-        Self::Relevance
-    }
+    Smart,
+    Popular,
 }
 
 impl SearchSortParam {
@@ -48,6 +75,8 @@ impl SearchSortParam {
         match self {
             SearchSortParam::Relevance => SearchSort::Relevance,
             SearchSortParam::Latest => SearchSort::Latest,
+            SearchSortParam::Smart => SearchSort::Smart,
+            SearchSortParam::Popular => SearchSort::Popular,
         }
     }
 
@@ -55,6 +84,25 @@ impl SearchSortParam {
         match self {
             SearchSortParam::Relevance => "relevance",
             SearchSortParam::Latest => "latest",
+            SearchSortParam::Smart => "smart",
+            SearchSortParam::Popular => "popular",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightParam {
+    #[default]
+    Html,
+    Ranges,
+}
+
+impl HighlightParam {
+    fn as_mode(self) -> HighlightMode {
+        match self {
+            HighlightParam::Html => HighlightMode::Html,
+            HighlightParam::Ranges => HighlightMode::Ranges,
         }
     }
 }
@@ -64,6 +112,13 @@ pub struct SearchResponse {
     pub total: usize,
     pub hits: Vec<SearchHitResponse>,
     pub elapsed_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<SearchFacets>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    pub fuzzy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -73,6 +128,12 @@ pub struct SearchHitResponse {
     pub matched: MatchedFields,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct QuerySuggestion {
+    text: String,
+    query_norm: String,
+}
+
 #[derive(Debug, Serialize, PartialEq, Eq)]
 pub struct MatchedFields {
     pub title: bool,
@@ -80,6 +141,8 @@ pub struct MatchedFields {
     pub content: bool,
     pub tags: Vec<String>,
     pub category: bool,
+    pub terms: Vec<String>,
+    pub synonyms: Vec<String>,
 }
 
 #[derive(Debug, Error)]
@@ -88,10 +151,36 @@ pub enum SearchApiError {
     Query(#[from] QueryParseError),
     #[error("query too long (max {0} chars)")]
     QueryTooLong(usize),
+    #[error("unknown facet: {0}")]
+    InvalidFacet(String),
+    #[error("explain requires an admin token")]
+    ExplainUnauthorized,
+    #[error("cursor and offset cannot be combined")]
+    CursorWithOffset,
     #[error("search failure: {0}")]
     Search(#[from] SearchIndexError),
 }
 
+#[derive(Debug, Error)]
+pub enum SearchClickError {
+    #[error("invalid payload")]
+    InvalidPayload,
+    #[error("query is required")]
+    MissingQuery,
+    #[error("invalid query: {0}")]
+    Query(#[from] QueryParseError),
+    #[error("query too long (max {0} chars)")]
+    QueryTooLong(usize),
+    #[error("url is invalid")]
+    InvalidUrl,
+    #[error("rank must be between 1 and {0}")]
+    InvalidRank(i32),
+    #[error("db not configured")]
+    DbUnavailable,
+    #[error("db error: {0}")]
+    Db(#[from] SearchEventsRepoError),
+}
+
 #[derive(Debug, Serialize)]
 struct ErrorBody {
     error: String,
@@ -117,13 +206,20 @@ pub async fn search(
         .limit
         .unwrap_or(8)
         .min(state.config.max_search_limit);
+    let cursor = parse_cursor(params.cursor.as_deref(), params.offset)?;
     let offset = params.offset.unwrap_or(0);
     let sort = params.sort.unwrap_or_default();
+    let facets = parse_facets(params.facets.as_deref())?;
+    let explain = params.explain.unwrap_or(false);
+    if explain && !has_admin_token(&state, &headers) {
+        return Err(SearchApiError::ExplainUnauthorized);
+    }
     let kind = "search";
     let search_user_hash =
         build_search_user_hash(state.config.search_hash_secret.as_deref(), &headers);
 
-    let query = match parse_query(&query_text) {
+    let query = match parse_query_with_operator(&query_text, state.config.search_default_operator)
+    {
         Ok(query) => query,
         Err(err) => {
             warn!(
@@ -135,7 +231,28 @@ pub async fn search(
             return Err(err.into());
         }
     };
-    let result: SearchResult = match state.search.search(&query, limit, offset, sort.as_sort()) {
+    let search_index = state.search.read().await.clone();
+    let synonyms = state.search_synonyms.read().await.clone();
+    let popularity = if sort == SearchSortParam::Popular {
+        Some(state.search_popularity.read().await.clone())
+    } else {
+        None
+    };
+    let options = SearchOptions {
+        limit,
+        offset,
+        sort: sort.as_sort(),
+        facets,
+        popularity,
+        source_weights: source_weights(&state),
+        boosts: state.config.search_boosts,
+        explain,
+        cursor,
+        highlight: params.highlight.unwrap_or_default().as_mode(),
+        snippets: state.config.search_snippets,
+        synonyms: Some(synonyms.clone()),
+    };
+    let result: SearchResult = match search_index.search(&query, &options) {
         Ok(result) => result,
         Err(err) => {
             warn!(
@@ -148,9 +265,27 @@ pub async fn search(
         }
     };
 
+    // Suggestions are extra work on top of the search and stay out of its timing.
     let elapsed_ms = started_at.elapsed().as_millis();
-    if let Some(pool) = state.db.as_ref() {
-        let event = build_search_event(
+    // Only first pages of weak results are worth a second look at the spelling.
+    let exact_total = if result.fuzzy { 0 } else { result.total };
+    let suggestion = if !explain
+        && options.cursor.is_none()
+        && offset == 0
+        && exact_total <= SUGGESTION_MAX_TOTAL
+        && query.keyword_expr().is_some()
+        && has_plain_word(&query_text)
+    {
+        suggest_query(&state, &search_index, &query_text, &query, &options, exact_total).await
+    } else {
+        None
+    };
+
+    // Score tuning queries from the admin UI stay out of search analytics.
+    if !explain
+        && let Some(pool) = state.db.as_ref()
+    {
+        let mut event = build_search_event(
             &query_text,
             &query,
             sort,
@@ -159,6 +294,7 @@ pub async fn search(
             elapsed_ms,
             search_user_hash.clone(),
         );
+        event.suggestion = suggestion.as_ref().map(|value| value.query_norm.clone());
         let should_insert = if kind == "search" {
             if let Some(hash) = search_user_hash.as_deref() {
                 match fetch_recent_search_query(pool, hash, SEARCH_EVENT_DEDUP_SECS).await {
@@ -175,10 +311,10 @@ pub async fn search(
         } else {
             true
         };
-        if should_insert {
-            if let Err(err) = insert_search_event(pool, &event).await {
-                warn!(error = %err, "failed to store search event");
-            }
+        if should_insert
+            && let Err(err) = insert_search_event(pool, &event).await
+        {
+            warn!(error = %err, "failed to store search event");
         }
     }
     info!(
@@ -194,17 +330,217 @@ pub async fn search(
         .hits
         .into_iter()
         .map(|hit| SearchHitResponse {
-            matched: build_matched(&hit, &query),
+            matched: build_matched(&hit, &query, &synonyms, &state.search_tokenizer),
             hit,
         })
         .collect();
     Ok(Json(SearchResponse {
         total: result.total,
         hits,
-        elapsed_ms: elapsed_ms,
+        elapsed_ms,
+        facets: result.facets,
+        next_cursor: result.next_cursor,
+        fuzzy: result.fuzzy,
+        suggestion: suggestion.map(|value| value.text),
     }))
 }
 
+// Tries the query with misspelled words swapped for indexed ones, then the
+// closest earlier query that found something, and keeps the first candidate
+// that actually finds more than the original.
+async fn suggest_query(
+    state: &AppState,
+    search_index: &Arc<SearchIndex>,
+    query_text: &str,
+    query: &SearchQuery,
+    options: &SearchOptions,
+    total: usize,
+) -> Option<QuerySuggestion> {
+    let query_norm = normalize_query(query_text, query);
+    let history = state.search_query_history.read().await.clone();
+    let operator = state.config.search_default_operator;
+    let search_index = search_index.clone();
+    let query_text = query_text.to_string();
+    let options = options.clone();
+    // Term dictionary scans and candidate counts are blocking index reads.
+    let suggestion = tokio::task::spawn_blocking(move || {
+        let mut candidates = Vec::new();
+        if let Some(text) = rewrite_misspelled(&search_index, &query_text) {
+            candidates.push(text);
+        }
+        if let Some(text) = closest_query(&query_norm, &history) {
+            candidates.push(text.to_string());
+        }
+        for text in candidates {
+            let Ok(candidate) = parse_query_with_operator(&text, operator) else {
+                continue;
+            };
+            match search_index.count(&candidate, &options) {
+                Ok(count) if count > total => {
+                    return Some(QuerySuggestion {
+                        query_norm: normalize_query(&text, &candidate),
+                        text,
+                    });
+                }
+                Ok(_) => {}
+                Err(err) => warn!(error = %err, "failed to check search suggestion"),
+            }
+        }
+        None
+    })
+    .await;
+    suggestion.unwrap_or_else(|err| {
+        warn!(error = %err, "search suggestion task failed");
+        None
+    })
+}
+
+// Spelling suggestions only cover Latin-script words; queries without one
+// skip them.
+fn has_plain_word(query_text: &str) -> bool {
+    query_text.split_whitespace().any(is_plain_word)
+}
+
+fn is_plain_word(word: &str) -> bool {
+    word.chars().any(|ch| ch.is_ascii_alphabetic())
+        && word.chars().all(|ch| ch.is_ascii_alphanumeric())
+}
+
+// Swaps plain words the index has never seen for their closest indexed spelling,
+// leaving qualifiers, phrases and exclusions as typed.
+fn rewrite_misspelled(search_index: &SearchIndex, query_text: &str) -> Option<String> {
+    let mut changed = false;
+    let words = query_text
+        .split_whitespace()
+        .map(|word| {
+            if !is_plain_word(word) {
+                return word.to_string();
+            }
+            match search_index.spelling_neighbour(word) {
+                Ok(Some(neighbour)) => {
+                    changed = true;
+                    neighbour
+                }
+                Ok(None) => word.to_string(),
+                Err(err) => {
+                    warn!(error = %err, "failed to look up spelling neighbour");
+                    word.to_string()
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    changed.then(|| words.join(" "))
+}
+
+// Records a click on a search hit. Clicks are only kept when they can be tied to
+// a search the same visitor ran for that query within the dedup window.
+pub async fn post_click(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, SearchClickError> {
+    if body.is_empty() {
+        return Err(SearchClickError::InvalidPayload);
+    }
+    let payload: SearchClickRequest =
+        serde_json::from_slice(&body).map_err(|_| SearchClickError::InvalidPayload)?;
+    let query_text = payload
+        .query
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or(SearchClickError::MissingQuery)?;
+    if query_text.chars().count() > MAX_QUERY_LEN {
+        return Err(SearchClickError::QueryTooLong(MAX_QUERY_LEN));
+    }
+    let url = normalize_click_url(payload.url.as_deref())?;
+    let rank = payload
+        .rank
+        .filter(|rank| (1..=MAX_CLICK_RANK).contains(rank))
+        .ok_or(SearchClickError::InvalidRank(MAX_CLICK_RANK))?;
+    let query = parse_query_with_operator(query_text, state.config.search_default_operator)?;
+    let pool = state.db.as_ref().ok_or(SearchClickError::DbUnavailable)?;
+    let Some(search_user_hash) =
+        build_search_user_hash(state.config.search_hash_secret.as_deref(), &headers)
+    else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+
+    let mut event = build_search_event(
+        query_text,
+        &query,
+        payload.sort.unwrap_or_default(),
+        "click",
+        0,
+        0,
+        Some(search_user_hash.clone()),
+    );
+    if !has_recent_search(pool, &search_user_hash, &event.query_norm, SEARCH_EVENT_DEDUP_SECS)
+        .await?
+    {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    event.clicked_url = Some(url);
+    event.click_rank = Some(rank);
+    insert_search_event(pool, &event).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn normalize_click_url(value: Option<&str>) -> Result<String, SearchClickError> {
+    let url = value.map(str::trim).unwrap_or_default();
+    if url.is_empty()
+        || url.len() > MAX_CLICK_URL_LEN
+        || url.chars().any(char::is_control)
+        || !(url.starts_with("https://") || url.starts_with("http://"))
+    {
+        return Err(SearchClickError::InvalidUrl);
+    }
+    Ok(url.to_string())
+}
+
+fn parse_cursor(
+    value: Option<&str>,
+    offset: Option<usize>,
+) -> Result<Option<SearchCursor>, SearchApiError> {
+    let Some(value) = value.map(str::trim).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+    if offset.is_some_and(|offset| offset > 0) {
+        return Err(SearchApiError::CursorWithOffset);
+    }
+    SearchCursor::decode(value)
+        .map(Some)
+        .ok_or(SearchApiError::Search(SearchIndexError::InvalidCursor("cannot be decoded")))
+}
+
+// Only sources with a non-default weight need to reach the scorer.
+fn source_weights(state: &AppState) -> Option<Arc<SourceWeights>> {
+    let weights = state
+        .config
+        .feed_sources
+        .iter()
+        .filter(|source| source.weight != 1.0)
+        .map(|source| (source.name.clone(), source.weight))
+        .collect::<SourceWeights>();
+    (!weights.is_empty()).then(|| Arc::new(weights))
+}
+
+fn parse_facets(value: Option<&str>) -> Result<Vec<FacetField>, SearchApiError> {
+    let mut facets = Vec::new();
+    for name in value.unwrap_or_default().split(',') {
+        let facet = match name.trim() {
+            "" => continue,
+            "tags" => FacetField::Tags,
+            "category" => FacetField::Category,
+            other => return Err(SearchApiError::InvalidFacet(other.to_string())),
+        };
+        if !facets.contains(&facet) {
+            facets.push(facet);
+        }
+    }
+    Ok(facets)
+}
+
 fn build_search_event(
     query_text: &str,
     query: &SearchQuery,
@@ -214,17 +550,27 @@ fn build_search_event(
     elapsed_ms: u128,
     search_user_hash: Option<String>,
 ) -> SearchEvent {
-    let raw = query_text.trim().to_string();
-    let keyword_count = query.keywords.len() as i32;
-    let mut keywords = query
-        .keywords
-        .iter()
-        .map(|value| normalize_token(value))
-        .filter(|value| !value.is_empty())
-        .collect::<Vec<_>>();
-    keywords.sort();
-    keywords.dedup();
+    let tags = normalized_tags(query);
+    SearchEvent {
+        query_raw: query_text.trim().to_string(),
+        query_norm: normalize_query(query_text, query),
+        keyword_count: (query.keywords().len() + query.phrases().len()) as i32,
+        tags,
+        category: normalized_category(query),
+        range_start: query.range.as_ref().and_then(|range| range.start),
+        range_end: query.range.as_ref().and_then(|range| range.end),
+        sort: sort.as_str().to_string(),
+        kind: kind.to_string(),
+        search_user_hash,
+        result_total: clamp_i32(total as i64),
+        elapsed_ms: clamp_i32(elapsed_ms as i64),
+        suggestion: None,
+        clicked_url: None,
+        click_rank: None,
+    }
+}
 
+fn normalized_tags(query: &SearchQuery) -> Vec<String> {
     let mut tags = query
         .tags
         .iter()
@@ -233,46 +579,137 @@ fn build_search_event(
         .collect::<Vec<_>>();
     tags.sort();
     tags.dedup();
+    tags
+}
 
-    let category = query
+fn normalized_category(query: &SearchQuery) -> Option<String> {
+    query
         .category
         .as_ref()
         .map(|value| normalize_token(value))
-        .filter(|value| !value.is_empty());
+        .filter(|value| !value.is_empty())
+}
+
+fn normalize_query(query_text: &str, query: &SearchQuery) -> String {
+    let mut keywords = query
+        .keywords()
+        .into_iter()
+        .map(normalize_token)
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>();
+    keywords.sort();
+    keywords.dedup();
+
+    let mut phrases = query
+        .phrases()
+        .into_iter()
+        .map(normalize_token)
+        .filter(|value| !value.is_empty())
+        .map(|value| format!("\"{value}\""))
+        .collect::<Vec<_>>();
+    phrases.sort();
+    phrases.dedup();
+
+    let tags = normalized_tags(query);
+    let category = normalized_category(query);
+
+    let mut excluded = query
+        .excluded_keywords
+        .iter()
+        .map(|value| normalize_token(value))
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            if value.contains(' ') {
+                format!("-\"{value}\"")
+            } else {
+                format!("-{value}")
+            }
+        })
+        .chain(
+            query
+                .excluded_categories
+                .iter()
+                .map(|value| normalize_token(value))
+                .filter(|value| !value.is_empty())
+                .map(|value| format!("-category:{value}")),
+        )
+        .chain(
+            query
+                .excluded_sources
+                .iter()
+                .map(|value| format!("-source:{value}")),
+        )
+        .collect::<Vec<_>>();
+    let mut excluded_tags = query
+        .excluded_tags
+        .iter()
+        .map(|value| normalize_token(value))
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>();
+    excluded_tags.sort();
+    excluded_tags.dedup();
+    if !excluded_tags.is_empty() {
+        excluded.push(format!("-tags:{}", excluded_tags.join(",")));
+    }
+    excluded.sort();
+    excluded.dedup();
 
     let mut parts = Vec::new();
-    if !keywords.is_empty() {
-        parts.push(keywords.join(" "));
+    match query.expr.as_ref() {
+        Some(expr) if expr.has_or() => parts.push(format_keyword_expr(expr, false)),
+        _ => {
+            if !keywords.is_empty() {
+                parts.push(keywords.join(" "));
+            }
+            if !phrases.is_empty() {
+                parts.push(phrases.join(" "));
+            }
+        }
     }
     if let Some(category_value) = category.as_ref() {
         parts.push(format!("category:{category_value}"));
     }
+    if let Some(source) = query.source.as_ref() {
+        parts.push(format!("source:{source}"));
+    }
     if !tags.is_empty() {
         parts.push(format!("tags:{}", tags.join(",")));
     }
     if let Some(range) = query.range.as_ref() {
-        parts.push(format!("range:{}", format_range(range)));
+        parts.push(format!("{}:{}", range.field.as_str(), format_range(range)));
+    }
+    if !excluded.is_empty() {
+        parts.push(excluded.join(" "));
     }
 
-    let normalized = if parts.is_empty() {
-        raw.clone()
+    if parts.is_empty() {
+        query_text.trim().to_string()
     } else {
         parts.join(" ")
-    };
+    }
+}
 
-    SearchEvent {
-        query_raw: raw,
-        query_norm: normalized,
-        keyword_count,
-        tags,
-        category,
-        range_start: query.range.as_ref().and_then(|range| range.start),
-        range_end: query.range.as_ref().and_then(|range| range.end),
-        sort: sort.as_str().to_string(),
-        kind: kind.to_string(),
-        search_user_hash,
-        result_total: clamp_i32(total as i64),
-        elapsed_ms: clamp_i32(elapsed_ms as i64),
+fn format_keyword_expr(expr: &KeywordExpr, grouped: bool) -> String {
+    match expr {
+        KeywordExpr::Keyword(value) => normalize_token(value),
+        KeywordExpr::Phrase(value) => format!("\"{}\"", normalize_token(value)),
+        KeywordExpr::And(items) => items
+            .iter()
+            .map(|item| format_keyword_expr(item, true))
+            .collect::<Vec<_>>()
+            .join(" "),
+        KeywordExpr::Or(items) => {
+            let joined = items
+                .iter()
+                .map(|item| format_keyword_expr(item, false))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            if grouped {
+                format!("({joined})")
+            } else {
+                joined
+            }
+        }
     }
 }
 
@@ -280,7 +717,11 @@ fn normalize_token(value: &str) -> String {
     value.trim().to_ascii_lowercase()
 }
 
+// Relative windows keep their form so the same query reads the same every day.
 fn format_range(range: &TimeRange) -> String {
+    if let Some(relative) = range.relative.as_ref() {
+        return relative.clone();
+    }
     let start = range
         .start
         .map(|value| value.format("%Y-%m-%d").to_string())
@@ -345,30 +786,49 @@ fn enforce_query_length(query_text: &str) -> Result<(), SearchApiError> {
     Ok(())
 }
 
-fn build_matched(hit: &SearchHit, query: &SearchQuery) -> MatchedFields {
-    let title = hit.title.contains("<b>");
-    let subtitle = hit
-        .subtitle
-        .as_deref()
-        .map(|value| value.contains("<b>"))
-        .unwrap_or(false);
-    let content = hit
-        .content
-        .as_deref()
-        .map(|value| value.contains("<b>"))
-        .unwrap_or(false);
+fn build_matched(
+    hit: &SearchHit,
+    query: &SearchQuery,
+    synonyms: &SynonymMap,
+    tokenizer: &SearchTokenizer,
+) -> MatchedFields {
+    let (title, subtitle, content) = match hit.highlights.as_ref() {
+        Some(highlights) => (
+            !highlights.title.is_empty(),
+            !highlights.subtitle.is_empty(),
+            !highlights.content.is_empty(),
+        ),
+        None => (
+            hit.title.contains("<b>"),
+            hit.subtitle
+                .as_deref()
+                .map(|value| value.contains("<b>"))
+                .unwrap_or(false),
+            hit.content
+                .as_deref()
+                .map(|value| value.contains("<b>"))
+                .unwrap_or(false),
+        ),
+    };
+
+    let keyword_candidates = query
+        .keywords()
+        .into_iter()
+        .chain(query.phrases())
+        .flat_map(|keyword| std::iter::once(keyword).chain(synonyms.expand(keyword)))
+        .collect::<Vec<_>>();
 
     let mut tag_matches = Vec::new();
     if !hit.tags.is_empty() {
         let mut candidates: Vec<&str> = Vec::new();
         candidates.extend(query.tags.iter().map(|tag| tag.as_str()));
-        candidates.extend(query.keywords.iter().map(|keyword| keyword.as_str()));
+        candidates.extend(keyword_candidates.iter().copied());
         if !candidates.is_empty() {
             for hit_tag in &hit.tags {
-                if candidates.iter().any(|candidate| hit_tag == candidate) {
-                    if !tag_matches.contains(hit_tag) {
-                        tag_matches.push(hit_tag.clone());
-                    }
+                if candidates.iter().any(|candidate| hit_tag == candidate)
+                    && !tag_matches.contains(hit_tag)
+                {
+                    tag_matches.push(hit_tag.clone());
                 }
             }
         }
@@ -378,9 +838,39 @@ fn build_matched(hit: &SearchHit, query: &SearchQuery) -> MatchedFields {
     if let Some(query_category) = query.category.as_ref() {
         category = hit.category.as_deref() == Some(query_category.as_str());
     }
-    if !category {
-        if let Some(hit_category) = hit.category.as_deref() {
-            category = query.keywords.iter().any(|keyword| keyword == hit_category);
+    if !category && let Some(hit_category) = hit.category.as_deref() {
+        category = keyword_candidates.contains(&hit_category);
+    }
+
+    let mut terms = Vec::new();
+    let mut matched_synonyms = Vec::new();
+    if let Some(expr) = query.keyword_expr() {
+        let highlighted = highlighted_spans(hit)
+            .iter()
+            .flat_map(|span| tokenizer.terms(span))
+            .collect::<HashSet<_>>();
+        // A term matched when every word of it was highlighted, or it names a tag or category.
+        let found_in_hit = |term: &str| {
+            let words = tokenizer.terms(term);
+            (!words.is_empty() && words.iter().all(|word| highlighted.contains(word)))
+                || hit.tags.iter().any(|tag| tag == term)
+                || hit.category.as_deref() == Some(term)
+        };
+        for term in expr.terms() {
+            let found_synonyms = synonyms
+                .expand(term)
+                .into_iter()
+                .filter(|synonym| found_in_hit(synonym))
+                .collect::<Vec<_>>();
+            let found = found_in_hit(term) || !found_synonyms.is_empty();
+            if found && !terms.iter().any(|value| value == term) {
+                terms.push(term.to_string());
+            }
+            for synonym in found_synonyms {
+                if !matched_synonyms.iter().any(|value| value == synonym) {
+                    matched_synonyms.push(synonym.to_string());
+                }
+            }
         }
     }
 
@@ -390,14 +880,75 @@ fn build_matched(hit: &SearchHit, query: &SearchQuery) -> MatchedFields {
         content,
         tags: tag_matches,
         category,
+        terms,
+        synonyms: matched_synonyms,
+    }
+}
+
+fn highlighted_spans(hit: &SearchHit) -> Vec<String> {
+    match hit.highlights.as_ref() {
+        Some(highlights) => highlights
+            .title
+            .iter()
+            .chain(&highlights.subtitle)
+            .chain(&highlights.content)
+            .flat_map(fragment_spans)
+            .collect(),
+        None => [
+            Some(hit.title.as_str()),
+            hit.subtitle.as_deref(),
+            hit.content.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .flat_map(bold_spans)
+        .collect(),
     }
 }
 
+// Ranges are UTF-16 offsets; touching ranges are joined so split words read whole.
+fn fragment_spans(fragment: &HighlightFragment) -> Vec<String> {
+    let units = fragment.text.encode_utf16().collect::<Vec<_>>();
+    let mut merged: Vec<[usize; 2]> = Vec::new();
+    for &[start, end] in &fragment.ranges {
+        let (start, end) = (start.min(units.len()), end.min(units.len()));
+        match merged.last_mut() {
+            Some(last) if start <= last[1] => last[1] = last[1].max(end),
+            _ => merged.push([start, end]),
+        }
+    }
+    merged
+        .into_iter()
+        .filter(|[start, end]| start < end)
+        .map(|[start, end]| String::from_utf16_lossy(&units[start..end]))
+        .collect()
+}
+
+fn bold_spans(html: &str) -> Vec<String> {
+    html.replace("</b><b>", "")
+        .split("<b>")
+        .skip(1)
+        .filter_map(|part| part.split_once("</b>").map(|(span, _)| span.to_string()))
+        .collect()
+}
+
 impl IntoResponse for SearchApiError {
     fn into_response(self) -> axum::response::Response {
         let (status, message) = match &self {
             SearchApiError::Query(err) => (StatusCode::BAD_REQUEST, err.to_string()),
-            SearchApiError::QueryTooLong(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            SearchApiError::QueryTooLong(_)
+            | SearchApiError::InvalidFacet(_)
+            | SearchApiError::CursorWithOffset => (StatusCode::BAD_REQUEST, self.to_string()),
+            SearchApiError::Search(
+                err @ (SearchIndexError::InvalidCursor(_) | SearchIndexError::CursorExpired),
+            ) => (StatusCode::BAD_REQUEST, err.to_string()),
+            SearchApiError::ExplainUnauthorized => (StatusCode::UNAUTHORIZED, self.to_string()),
+            SearchApiError::Search(
+                SearchIndexError::FieldNotFast(_) | SearchIndexError::OutdatedSchema(_),
+            ) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "search index predates this feature; rebuild it with --rebuild".to_string(),
+            ),
             SearchApiError::Search(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
         };
         let body = Json(ErrorBody { error: message });
@@ -405,16 +956,63 @@ impl IntoResponse for SearchApiError {
     }
 }
 
+impl IntoResponse for SearchClickError {
+    fn into_response(self) -> axum::response::Response {
+        let (status, message) = match &self {
+            SearchClickError::Query(err) => (StatusCode::BAD_REQUEST, err.to_string()),
+            SearchClickError::InvalidPayload
+            | SearchClickError::MissingQuery
+            | SearchClickError::QueryTooLong(_)
+            | SearchClickError::InvalidUrl
+            | SearchClickError::InvalidRank(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            SearchClickError::DbUnavailable => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
+            SearchClickError::Db(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
+        };
+        let body = Json(ErrorBody { error: message });
+        (status, body).into_response()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        build_matched, enforce_query_length, MatchedFields, SearchApiError, SearchSortParam,
-        MAX_QUERY_LEN,
+        build_matched, build_search_event, enforce_query_length, has_plain_word,
+        normalize_click_url, normalize_query, parse_cursor, parse_facets, rewrite_misspelled,
+        MatchedFields, SearchApiError, SearchClickError, SearchSortParam, MAX_QUERY_LEN,
+        SEARCH_EVENT_DEDUP_SECS,
     };
+    use crate::test_support::{doc, temp_dir};
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
     use chrono::{TimeZone, Utc};
-    use inkstone_core::domain::search::{SearchHit, SearchQuery};
+    use inkstone_core::domain::search::{
+        FacetField, HighlightFragment, KeywordExpr, SearchHighlights, SearchHit, SearchQuery,
+    };
+    use inkstone_infra::search::{
+        parse_query, CursorKey, SearchCursor, SearchIndex, SearchIndexError, SearchTokenizer,
+        SynonymMap,
+    };
     use serde_json::json;
 
+    fn hit(title: &str, content: Option<&str>) -> SearchHit {
+        let timestamp = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        SearchHit {
+            id: "id".to_string(),
+            title: title.to_string(),
+            subtitle: None,
+            content: content.map(str::to_string),
+            url: "https://example.com".to_string(),
+            tags: Vec::new(),
+            category: None,
+            source: "blog".to_string(),
+            published_at: timestamp,
+            updated_at: timestamp,
+            highlights: None,
+            explanation: None,
+            sections: Vec::new(),
+        }
+    }
+
     #[test]
     fn query_length_rejects_long_text() {
         let query = "a".repeat(MAX_QUERY_LEN + 1);
@@ -434,24 +1032,75 @@ mod tests {
         assert!(matches!(sort, SearchSortParam::Latest));
     }
 
+    #[test]
+    fn sort_param_parses_blended_modes() {
+        let sort: SearchSortParam = serde_json::from_value(json!("smart")).unwrap();
+        assert!(matches!(sort, SearchSortParam::Smart));
+        let sort: SearchSortParam = serde_json::from_value(json!("popular")).unwrap();
+        assert!(matches!(sort, SearchSortParam::Popular));
+    }
+
+    #[test]
+    fn facets_param_parses_known_fields() {
+        let facets = parse_facets(Some("tags, category,tags")).unwrap();
+        assert_eq!(facets, vec![FacetField::Tags, FacetField::Category]);
+        assert!(parse_facets(None).unwrap().is_empty());
+        let err = parse_facets(Some("author")).unwrap_err();
+        assert!(matches!(err, SearchApiError::InvalidFacet(_)));
+    }
+
+    #[test]
+    fn cursor_param_decodes_and_rejects_offset() {
+        let cursor = SearchCursor {
+            index_id: 1,
+            generation: 2,
+            key: CursorKey::Updated(1_700_000_000),
+            segment_ord: 0,
+            doc_id: 4,
+            id: "post-4".to_string(),
+        };
+        let encoded = cursor.encode();
+        assert_eq!(parse_cursor(Some(&encoded), None).unwrap(), Some(cursor.clone()));
+        assert_eq!(parse_cursor(Some(&encoded), Some(0)).unwrap(), Some(cursor));
+        assert!(parse_cursor(Some(" "), Some(8)).unwrap().is_none());
+        assert!(matches!(
+            parse_cursor(Some(&encoded), Some(8)).unwrap_err(),
+            SearchApiError::CursorWithOffset
+        ));
+        assert!(matches!(
+            parse_cursor(Some("garbage"), None).unwrap_err(),
+            SearchApiError::Search(SearchIndexError::InvalidCursor(_))
+        ));
+    }
+
+    #[test]
+    fn outdated_index_errors_ask_for_a_rebuild() {
+        for err in [
+            SearchIndexError::FieldNotFast("tags"),
+            SearchIndexError::OutdatedSchema(0),
+        ] {
+            let response = SearchApiError::Search(err).into_response();
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        }
+    }
+
     #[test]
     fn matched_fields_detects_highlight_and_keyword_tags() {
         let hit = SearchHit {
-            id: "id".to_string(),
-            title: "<b>实验室</b> 笔记".to_string(),
             subtitle: Some("副标题".to_string()),
-            content: Some("正文内容".to_string()),
-            url: "https://example.com".to_string(),
             tags: vec!["实验室".to_string()],
-            category: None,
-            published_at: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
-            updated_at: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+            ..hit("<b>实验室</b> 笔记", Some("正文内容"))
         };
         let query = SearchQuery {
-            keywords: vec!["实验室".to_string()],
+            expr: Some(KeywordExpr::Keyword("实验室".to_string())),
             ..Default::default()
         };
-        let matched = build_matched(&hit, &query);
+        let matched = build_matched(
+            &hit,
+            &query,
+            &SynonymMap::default(),
+            &SearchTokenizer::default(),
+        );
         assert_eq!(
             matched,
             MatchedFields {
@@ -459,7 +1108,9 @@ mod tests {
                 subtitle: false,
                 content: false,
                 tags: vec!["实验室".to_string()],
-                category: false
+                category: false,
+                terms: vec!["实验室".to_string()],
+                synonyms: Vec::new(),
             }
         );
     }
@@ -467,21 +1118,19 @@ mod tests {
     #[test]
     fn matched_fields_detects_explicit_category_filter() {
         let hit = SearchHit {
-            id: "id".to_string(),
-            title: "无关".to_string(),
-            subtitle: None,
-            content: None,
-            url: "https://example.com".to_string(),
-            tags: vec![],
             category: Some("实验室".to_string()),
-            published_at: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
-            updated_at: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+            ..hit("无关", None)
         };
         let query = SearchQuery {
             category: Some("实验室".to_string()),
             ..Default::default()
         };
-        let matched = build_matched(&hit, &query);
+        let matched = build_matched(
+            &hit,
+            &query,
+            &SynonymMap::default(),
+            &SearchTokenizer::default(),
+        );
         assert_eq!(
             matched,
             MatchedFields {
@@ -489,11 +1138,156 @@ mod tests {
                 subtitle: false,
                 content: false,
                 tags: Vec::new(),
-                category: true
+                category: true,
+                terms: Vec::new(),
+                synonyms: Vec::new(),
             }
         );
     }
 
+    #[test]
+    fn matched_fields_use_highlight_ranges() {
+        let hit = SearchHit {
+            highlights: Some(SearchHighlights {
+                content: vec![HighlightFragment {
+                    text: "trusty async tokio runtime".to_string(),
+                    ranges: vec![[13, 18]],
+                }],
+                ..Default::default()
+            }),
+            ..hit("Rust <b> notes", Some("trusty async tokio runtime"))
+        };
+        let query = parse_query("tokio OR rust").unwrap();
+        let matched = build_matched(
+            &hit,
+            &query,
+            &SynonymMap::default(),
+            &SearchTokenizer::default(),
+        );
+        assert!(!matched.title);
+        assert!(matched.content);
+        assert_eq!(matched.terms, vec!["tokio".to_string()]);
+    }
+
+    #[test]
+    fn matched_fields_report_synonym_matches() {
+        let hit = SearchHit {
+            tags: vec!["JavaScript".to_string()],
+            ..hit("<b>Kubernetes</b> operators", None)
+        };
+        let synonyms = SynonymMap::parse("k8s, Kubernetes\nJS, JavaScript\n");
+        let query = parse_query("k8s js").unwrap();
+        let matched = build_matched(&hit, &query, &synonyms, &SearchTokenizer::default());
+        assert!(matched.title);
+        assert_eq!(matched.tags, vec!["JavaScript".to_string()]);
+        assert_eq!(matched.terms, vec!["k8s".to_string(), "js".to_string()]);
+        assert_eq!(
+            matched.synonyms,
+            vec!["Kubernetes".to_string(), "JavaScript".to_string()]
+        );
+    }
+
+    #[test]
+    fn search_event_normalizes_exclusions() {
+        let query = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("Tokio".to_string())),
+            excluded_keywords: vec!["Actix".to_string(), "web framework".to_string()],
+            excluded_tags: vec!["wasm".to_string(), "WASM".to_string()],
+            excluded_categories: vec!["随笔".to_string()],
+            ..Default::default()
+        };
+        let event = build_search_event(
+            "Tokio -Actix",
+            &query,
+            SearchSortParam::Relevance,
+            "search",
+            0,
+            0,
+            None,
+        );
+        assert_eq!(
+            event.query_norm,
+            "tokio -\"web framework\" -actix -category:随笔 -tags:wasm"
+        );
+    }
+
+    #[test]
+    fn matched_terms_follow_or_branches() {
+        let hit = hit("<b>Tantivy</b> notes", Some("building an index"));
+        let query = parse_query("tantivy OR lucene").unwrap();
+        let matched = build_matched(
+            &hit,
+            &query,
+            &SynonymMap::default(),
+            &SearchTokenizer::default(),
+        );
+        assert_eq!(matched.terms, vec!["tantivy".to_string()]);
+    }
+
+    #[test]
+    fn search_event_keeps_or_structure() {
+        let query = parse_query("Rust (tantivy OR lucene)").unwrap();
+        let event = build_search_event(
+            "Rust (tantivy OR lucene)",
+            &query,
+            SearchSortParam::Relevance,
+            "search",
+            0,
+            0,
+            None,
+        );
+        assert_eq!(event.query_norm, "rust (tantivy OR lucene)");
+    }
+
+    #[test]
+    fn search_event_keeps_date_field() {
+        let query = parse_query("Rust published:2023-05").unwrap();
+        let event = build_search_event(
+            "Rust published:2023-05",
+            &query,
+            SearchSortParam::Relevance,
+            "search",
+            0,
+            0,
+            None,
+        );
+        assert_eq!(event.query_norm, "rust published:2023-05-01~2023-05-31");
+
+        let query = parse_query("Rust range:last-90d").unwrap();
+        assert_eq!(normalize_query("Rust range:last-90d", &query), "rust range:last-90d");
+    }
+
+    #[test]
+    fn misspelled_words_are_rewritten_and_qualifiers_kept() {
+        let dir = temp_dir("inkstone-search-suggest");
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        index
+            .upsert_documents(&[doc("doc-1", "Tantivy internals", "segments and postings")])
+            .unwrap();
+
+        assert_eq!(
+            rewrite_misspelled(&index, "tantvy segmnets tags:Rust").as_deref(),
+            Some("tantivy segments tags:Rust")
+        );
+        assert_eq!(rewrite_misspelled(&index, "tantivy postings"), None);
+        assert!(has_plain_word("数据库 tantvy"));
+        assert!(!has_plain_word("数据库 2024 tags:Rust"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn click_url_must_be_http() {
+        assert_eq!(
+            normalize_click_url(Some(" https://example.com/posts/a ")).unwrap(),
+            "https://example.com/posts/a"
+        );
+        for value in [None, Some(""), Some("javascript:alert(1)"), Some("https://a\nb")] {
+            assert!(matches!(
+                normalize_click_url(value),
+                Err(SearchClickError::InvalidUrl)
+            ));
+        }
+    }
 
     #[test]
     fn search_event_dedup_window_is_half_hour() {
@@ -0,0 +1,95 @@
+use std::time::Instant;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::state::AppState;
+use inkstone_core::domain::search::SearchHit;
+use inkstone_infra::search::{DocumentRef, SearchIndexError};
+
+const DEFAULT_RELATED_LIMIT: usize = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct RelatedParams {
+    pub url: Option<String>,
+    pub id: Option<String>,
+    pub limit: Option<usize>,
+    pub recency: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelatedResponse {
+    pub hits: Vec<SearchHit>,
+    pub elapsed_ms: u128,
+}
+
+#[derive(Debug, Error)]
+pub enum RelatedApiError {
+    #[error("url or id is required")]
+    MissingTarget,
+    #[error("document not found")]
+    NotFound,
+    #[error("search failure: {0}")]
+    Search(#[from] SearchIndexError),
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+pub async fn related(
+    State(state): State<AppState>,
+    Query(params): Query<RelatedParams>,
+) -> Result<Json<RelatedResponse>, RelatedApiError> {
+    let started_at = Instant::now();
+    let url = non_empty(params.url.as_deref());
+    let id = non_empty(params.id.as_deref());
+    let target = match (url, id) {
+        (Some(url), _) => DocumentRef::Url(url),
+        (None, Some(id)) => DocumentRef::Id(id),
+        (None, None) => return Err(RelatedApiError::MissingTarget),
+    };
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_RELATED_LIMIT)
+        .min(state.config.max_search_limit);
+    let recency = params.recency.unwrap_or(false);
+    let search_index = state.search.read().await.clone();
+    let hits = search_index
+        .related(target, limit, recency)?
+        .ok_or(RelatedApiError::NotFound)?;
+    let elapsed_ms = started_at.elapsed().as_millis();
+    info!(
+        target = ?target,
+        limit,
+        total = hits.len(),
+        elapsed_ms = elapsed_ms,
+        "related search completed"
+    );
+    Ok(Json(RelatedResponse { hits, elapsed_ms }))
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+impl IntoResponse for RelatedApiError {
+    fn into_response(self) -> axum::response::Response {
+        warn!(error = %self, "related search error");
+        let status = match self {
+            RelatedApiError::MissingTarget => StatusCode::BAD_REQUEST,
+            RelatedApiError::NotFound => StatusCode::NOT_FOUND,
+            RelatedApiError::Search(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = Json(ErrorBody {
+            error: self.to_string(),
+        });
+        (status, body).into_response()
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::Instant;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::state::AppState;
+use inkstone_core::domain::search::SearchSuggestions;
+use inkstone_infra::search::SearchIndexError;
+
+const MAX_PREFIX_LEN: usize = 64;
+const DEFAULT_SUGGEST_LIMIT: usize = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestParams {
+    pub q: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SuggestResponse {
+    #[serde(flatten)]
+    pub suggestions: SearchSuggestions,
+    pub elapsed_ms: u128,
+}
+
+#[derive(Debug, Error)]
+pub enum SuggestApiError {
+    #[error("q is required")]
+    MissingPrefix,
+    #[error("q too long (max {0} chars)")]
+    PrefixTooLong(usize),
+    #[error("control characters are not allowed")]
+    ControlCharacter,
+    #[error("search failure: {0}")]
+    Search(#[from] SearchIndexError),
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+pub async fn suggest(
+    State(state): State<AppState>,
+    Query(params): Query<SuggestParams>,
+) -> Result<Json<SuggestResponse>, SuggestApiError> {
+    let started_at = Instant::now();
+    let prefix = normalize_prefix(params.q.as_deref())?;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_SUGGEST_LIMIT)
+        .min(state.config.max_search_limit);
+    let search_index = state.search.read().await.clone();
+    let suggestions = search_index.suggest(&prefix, limit)?;
+    let elapsed_ms = started_at.elapsed().as_millis();
+    info!(
+        prefix = %prefix,
+        limit,
+        titles = suggestions.titles.len(),
+        elapsed_ms = elapsed_ms,
+        "search suggest completed"
+    );
+    Ok(Json(SuggestResponse {
+        suggestions,
+        elapsed_ms,
+    }))
+}
+
+fn normalize_prefix(value: Option<&str>) -> Result<String, SuggestApiError> {
+    let raw = value.unwrap_or_default();
+    if raw.chars().any(|ch| ch.is_control()) {
+        return Err(SuggestApiError::ControlCharacter);
+    }
+    let prefix = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    if prefix.is_empty() {
+        return Err(SuggestApiError::MissingPrefix);
+    }
+    if prefix.chars().count() > MAX_PREFIX_LEN {
+        return Err(SuggestApiError::PrefixTooLong(MAX_PREFIX_LEN));
+    }
+    Ok(prefix)
+}
+
+impl IntoResponse for SuggestApiError {
+    fn into_response(self) -> axum::response::Response {
+        warn!(error = %self, "search suggest error");
+        let status = match self {
+            SuggestApiError::MissingPrefix
+            | SuggestApiError::PrefixTooLong(_)
+            | SuggestApiError::ControlCharacter => StatusCode::BAD_REQUEST,
+            SuggestApiError::Search(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = Json(ErrorBody {
+            error: self.to_string(),
+        });
+        (status, body).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_prefix, SuggestApiError, MAX_PREFIX_LEN};
+
+    #[test]
+    fn normalize_prefix_collapses_whitespace() {
+        assert_eq!(normalize_prefix(Some("  rust   as ")).unwrap(), "rust as");
+    }
+
+    #[test]
+    fn normalize_prefix_rejects_empty_and_long_input() {
+        assert!(matches!(
+            normalize_prefix(Some("  ")).unwrap_err(),
+            SuggestApiError::MissingPrefix
+        ));
+        let long = "a".repeat(MAX_PREFIX_LEN + 1);
+        assert!(matches!(
+            normalize_prefix(Some(&long)).unwrap_err(),
+            SuggestApiError::PrefixTooLong(_)
+        ));
+    }
+}
@@ -14,18 +14,24 @@ pub enum JobError {
     Feed(String),
     #[error("search index error: {0}")]
     Search(#[from] inkstone_infra::search::SearchIndexError),
+    #[error("search index validation failed: {0}")]
+    IndexValidation(String),
     #[error("db error: {0}")]
     Db(#[from] inkstone_infra::db::DoubanRepoError),
     #[error("kudos db error: {0}")]
     KudosDb(#[from] inkstone_infra::db::KudosRepoError),
     #[error("comments db error: {0}")]
     CommentsDb(#[from] inkstone_infra::db::CommentsRepoError),
+    #[error("search events db error: {0}")]
+    SearchEventsDb(#[from] inkstone_infra::db::SearchEventsRepoError),
     #[error("github error: {0}")]
     Github(#[from] inkstone_infra::github::GithubError),
     #[error("comments error: {0}")]
     Comments(String),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("watch error: {0}")]
+    Watch(#[from] notify::Error),
 }
 
 pub async fn start(state: AppState, rebuild: bool) -> Result<(), JobError> {
@@ -101,6 +107,16 @@ pub async fn start(state: AppState, rebuild: bool) -> Result<(), JobError> {
         None
     };
 
+    let watch_targets = tasks::feed_watch::watch_targets(&state.config);
+    let watch_job = (!watch_targets.is_empty())
+        .then(|| tasks::feed_watch::run(state.clone(), watch_targets));
+    let watch_job = async move {
+        match watch_job {
+            Some(job) => job.await,
+            None => Ok(()),
+        }
+    };
+
     if state.db.is_some() && kudos_interval.as_secs() > 0 {
         let kudos_state = state.clone();
         let kudos_job = scheduler::run_interval("kudos_cache_flush", kudos_interval, move || {
@@ -114,19 +130,19 @@ pub async fn start(state: AppState, rebuild: bool) -> Result<(), JobError> {
         });
         match comments_job {
             Some(comments_job) => {
-                tokio::try_join!(refresh_job, douban_job, kudos_job, comments_job)?;
+                tokio::try_join!(refresh_job, douban_job, kudos_job, comments_job, watch_job)?;
             }
             None => {
-                tokio::try_join!(refresh_job, douban_job, kudos_job)?;
+                tokio::try_join!(refresh_job, douban_job, kudos_job, watch_job)?;
             }
         }
     } else {
         match comments_job {
             Some(comments_job) => {
-                tokio::try_join!(refresh_job, douban_job, comments_job)?;
+                tokio::try_join!(refresh_job, douban_job, comments_job, watch_job)?;
             }
             None => {
-                tokio::try_join!(refresh_job, douban_job)?;
+                tokio::try_join!(refresh_job, douban_job, watch_job)?;
             }
         }
     }
@@ -5,7 +5,7 @@ use tracing::{info, warn};
 
 use crate::config::AppConfig;
 use crate::jobs::JobError;
-use crate::jobs::tasks::feed_index::{SearchIndexEntry, parse_search_index_entries};
+use crate::jobs::tasks::feed_index::{SearchIndexEntry, fetch_feed_entries};
 use crate::state::AppState;
 use inkstone_core::types::slug::Slug;
 use inkstone_infra::db::{
@@ -334,15 +334,7 @@ fn flatten_comments(info: &DiscussionInfo) -> Vec<CommentRecord> {
 }
 
 async fn fetch_posts(state: &AppState) -> Result<Vec<PostRef>, JobError> {
-    let response = state
-        .http_client
-        .get(&state.config.feed_url)
-        .send()
-        .await?
-        .error_for_status()?;
-    let body = response.bytes().await?;
-    let entries =
-        parse_search_index_entries(&body).map_err(|err| JobError::Comments(err.to_string()))?;
+    let entries = fetch_feed_entries(state).await?;
     let mut posts = Vec::new();
     for entry in entries {
         if let Some(post) = post_from_index_entry(&entry) {
@@ -422,7 +414,7 @@ fn path_from_url(url: &str) -> Option<String> {
     } else {
         return None;
     };
-    let path = path.split(|ch| ch == '?' || ch == '#').next()?.trim();
+    let path = path.split(['?', '#']).next()?.trim();
     if path.is_empty() {
         None
     } else {
@@ -736,6 +728,7 @@ mod tests {
             category: None,
             tags: vec![],
             content: "Hello<!--more-->World".to_string(),
+            sections: None,
         };
 
         let summary = super::summary_from_entry(&entry).unwrap();
@@ -753,6 +746,7 @@ mod tests {
             category: None,
             tags: vec![],
             content: "a".repeat(250),
+            sections: None,
         };
 
         let summary = super::summary_from_entry(&entry).unwrap();
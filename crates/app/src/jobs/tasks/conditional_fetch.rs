@@ -0,0 +1,190 @@
+use axum::body::Bytes;
+use reqwest::header::{
+    CONTENT_TYPE, ETAG, HeaderMap, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::jobs::JobError;
+use crate::state::AppState;
+use inkstone_infra::db::{load_fetch_validators, upsert_fetch_validators, FetchValidatorsRecord};
+
+pub(crate) enum FetchOutcome {
+    Unchanged,
+    Changed(FetchedBody),
+}
+
+pub(crate) struct FetchedBody {
+    pub url: String,
+    pub content_type: Option<String>,
+    pub body: Bytes,
+    validators: FetchValidatorsRecord,
+}
+
+enum Response {
+    NotModified,
+    Body(FetchedBody),
+}
+
+// With `conditional` set, the validators from the last committed fetch are sent and
+// a 304 or a body hashing the same as last time is reported as unchanged.
+pub(crate) async fn fetch(
+    state: &AppState,
+    url: &str,
+    conditional: bool,
+) -> Result<FetchOutcome, JobError> {
+    let previous = if conditional {
+        stored_validators(state, url).await
+    } else {
+        None
+    };
+    match fetch_with(&state.http_client, url, previous.as_ref()).await? {
+        Response::NotModified => {
+            debug!(url, "fetch not modified");
+            Ok(FetchOutcome::Unchanged)
+        }
+        Response::Body(fetched) => match previous {
+            Some(previous) if previous.body_hash == fetched.validators.body_hash => {
+                debug!(url, "fetched body unchanged");
+                if previous != fetched.validators {
+                    commit(state, fetched).await;
+                }
+                Ok(FetchOutcome::Unchanged)
+            }
+            _ => Ok(FetchOutcome::Changed(fetched)),
+        },
+    }
+}
+
+// Called once the body has been applied, so a failed run is retried in full.
+pub(crate) async fn commit(state: &AppState, fetched: FetchedBody) {
+    if let Some(pool) = state.db.as_ref()
+        && let Err(err) = upsert_fetch_validators(pool, &fetched.url, &fetched.validators).await
+    {
+        warn!(url = fetched.url, error = %err, "failed to store fetch validators");
+    }
+    let mut guard = state.fetch_validators.lock().await;
+    guard.insert(fetched.url, fetched.validators);
+}
+
+async fn fetch_with(
+    client: &Client,
+    url: &str,
+    previous: Option<&FetchValidatorsRecord>,
+) -> Result<Response, JobError> {
+    let mut request = client.get(url);
+    if let Some(previous) = previous {
+        if let Some(etag) = &previous.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &previous.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = request.send().await?;
+    if previous.is_some() && response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(Response::NotModified);
+    }
+    let response = response.error_for_status()?;
+    let headers = response.headers();
+    let content_type = header_value(headers, CONTENT_TYPE);
+    let etag = header_value(headers, ETAG);
+    let last_modified = header_value(headers, LAST_MODIFIED);
+    let body = response.bytes().await?;
+    let body_hash = hex::encode(Sha256::digest(&body));
+    Ok(Response::Body(FetchedBody {
+        url: url.to_string(),
+        content_type,
+        body,
+        validators: FetchValidatorsRecord {
+            etag,
+            last_modified,
+            body_hash,
+        },
+    }))
+}
+
+async fn stored_validators(state: &AppState, url: &str) -> Option<FetchValidatorsRecord> {
+    if let Some(validators) = state.fetch_validators.lock().await.get(url) {
+        return Some(validators.clone());
+    }
+    let pool = state.db.as_ref()?;
+    match load_fetch_validators(pool, url).await {
+        Ok(Some(validators)) => {
+            let mut guard = state.fetch_validators.lock().await;
+            guard.insert(url.to_string(), validators.clone());
+            Some(validators)
+        }
+        Ok(None) => None,
+        Err(err) => {
+            warn!(url, error = %err, "failed to load fetch validators");
+            None
+        }
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::http::HeaderMap as RequestHeaders;
+    use axum::routing::get;
+
+    async fn feed(
+        headers: RequestHeaders,
+    ) -> (StatusCode, [(HeaderName, &'static str); 2], &'static str) {
+        let validators = [(ETAG, "\"v1\""), (LAST_MODIFIED, "Mon, 01 Jan 2024 00:00:00 GMT")];
+        if headers.get(IF_NONE_MATCH).is_some_and(|value| value == "\"v1\"") {
+            return (StatusCode::NOT_MODIFIED, validators, "");
+        }
+        (StatusCode::OK, validators, "[]")
+    }
+
+    async fn serve() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route("/feed", get(feed))
+            .route("/plain", get(|| async { "[]" }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn fetch_sends_validators_and_reports_not_modified() {
+        let base = serve().await;
+        let client = Client::new();
+        let url = format!("{base}/feed");
+
+        let Response::Body(first) = fetch_with(&client, &url, None).await.unwrap() else {
+            panic!("expected a body without validators");
+        };
+        assert_eq!(first.body.as_ref(), b"[]");
+        assert_eq!(first.validators.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(
+            first.validators.last_modified.as_deref(),
+            Some("Mon, 01 Jan 2024 00:00:00 GMT")
+        );
+        let second = fetch_with(&client, &url, Some(&first.validators)).await.unwrap();
+        assert!(matches!(second, Response::NotModified));
+
+        let plain = format!("{base}/plain");
+        let Response::Body(third) =
+            fetch_with(&client, &plain, Some(&first.validators)).await.unwrap()
+        else {
+            panic!("expected a body from a server without validators");
+        };
+        assert_eq!(third.validators.etag, None);
+        assert_eq!(third.validators.body_hash, first.validators.body_hash);
+    }
+}
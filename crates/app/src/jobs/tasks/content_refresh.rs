@@ -24,7 +24,14 @@ pub async fn run(state: &AppState, rebuild: bool, force: bool) -> Result<JobStat
     };
     if paths_backoff.is_none() {
         match valid_paths_refresh::run(state).await {
-            Ok(()) => clear_backoff(state, RefreshTask::Paths).await,
+            Ok(stats) => {
+                debug!(
+                    count = stats.count,
+                    unchanged = stats.unchanged,
+                    "valid paths refresh complete"
+                );
+                clear_backoff(state, RefreshTask::Paths).await;
+            }
             Err(err) => {
                 warn!(error = %err, "valid paths refresh failed");
                 set_backoff(state, now, RefreshTask::Paths).await;
@@ -56,6 +63,8 @@ pub async fn run(state: &AppState, rebuild: bool, force: bool) -> Result<JobStat
                     indexed: 0,
                     skipped: 0,
                     failed: 1,
+                    deleted: 0,
+                    unchanged: false,
                 }
             }
         }
@@ -69,6 +78,8 @@ pub async fn run(state: &AppState, rebuild: bool, force: bool) -> Result<JobStat
             indexed: 0,
             skipped: 0,
             failed: 0,
+            deleted: 0,
+            unchanged: false,
         }
     } else {
         JobStats {
@@ -76,6 +87,8 @@ pub async fn run(state: &AppState, rebuild: bool, force: bool) -> Result<JobStat
             indexed: 0,
             skipped: 0,
             failed: 0,
+            deleted: 0,
+            unchanged: false,
         }
     };
 
@@ -417,10 +417,10 @@ fn parse_rating_class(class_name: &str) -> Option<u8> {
     }
     if let Some(rest) = class_name.strip_prefix("allstar") {
         let digits: String = rest.chars().take_while(|ch| ch.is_ascii_digit()).collect();
-        if let Ok(value) = digits.parse::<u8>() {
-            if value >= 10 {
-                return Some(value / 10);
-            }
+        if let Ok(value) = digits.parse::<u8>()
+            && value >= 10
+        {
+            return Some(value / 10);
         }
     }
     None
@@ -1,12 +1,42 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
+use roxmltree::Node;
+use scraper::{ElementRef, Html};
 use serde::Deserialize;
 use sha2::{Digest, Sha256};
 use thiserror::Error;
-use tracing::warn;
+use tracing::{debug, info, warn};
 
+use crate::config::FeedSource;
 use crate::jobs::JobError;
+use crate::jobs::tasks::conditional_fetch::{self, FetchOutcome, FetchedBody};
+use crate::jobs::tasks::local_feed;
+use crate::jobs::tasks::search_generation;
+use crate::jobs::tasks::sections::{html_sections, markdown_sections};
 use crate::state::AppState;
-use inkstone_core::domain::search::SearchDocument;
+use inkstone_core::domain::search::{SearchDocument, SearchSection};
+use inkstone_infra::search::{
+    activate_generation, mark_generation_complete, new_generation_dir, prune_generations,
+    resolve_active_dir, SearchIndex, SearchTokenizer,
+};
+
+// Guards against a truncated feed wiping most of the index in one run.
+const PRUNE_MAX_RATIO: f64 = 0.5;
+// Far longer than other processes take to follow `CURRENT` to a new generation.
+const RETIRED_GENERATION_RETENTION: Duration = Duration::from_secs(600);
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+const RSS_CONTENT_NS: &str = "http://purl.org/rss/1.0/modules/content/";
+const DUBLIN_CORE_NS: &str = "http://purl.org/dc/elements/1.1/";
+// Elements that separate words when HTML is flattened to text.
+pub(crate) const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "figcaption",
+    "figure", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "ol", "p",
+    "pre", "section", "table", "td", "th", "tr", "ul",
+];
 
 #[derive(Debug)]
 pub struct JobStats {
@@ -14,6 +44,8 @@ pub struct JobStats {
     pub indexed: usize,
     pub skipped: usize,
     pub failed: usize,
+    pub deleted: usize,
+    pub unchanged: bool,
 }
 
 #[derive(Debug, Error)]
@@ -28,6 +60,47 @@ enum EntryError {
     InvalidTimestamp(String),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FeedFormat {
+    SearchIndex,
+    JsonFeed,
+    Atom,
+    Rss,
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum FeedParseError {
+    #[error("invalid json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid xml: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("feed is not valid utf-8")]
+    Encoding,
+    #[error("unrecognized feed format")]
+    UnknownFormat,
+    #[error("unexpected root element: {0}")]
+    UnexpectedRoot(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFeed {
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFeedItem {
+    id: Option<String>,
+    url: Option<String>,
+    title: Option<String>,
+    summary: Option<String>,
+    content_html: Option<String>,
+    content_text: Option<String>,
+    date_published: Option<String>,
+    date_modified: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct SearchIndexEntry {
     pub title: String,
@@ -38,59 +111,382 @@ pub(crate) struct SearchIndexEntry {
     pub category: Option<String>,
     pub tags: Vec<String>,
     pub content: String,
+    // Headings of HTML content, taken before it was flattened to text; when
+    // `None` they are read from the content as Markdown.
+    #[serde(skip)]
+    pub sections: Option<Vec<SearchSection>>,
 }
 
 pub async fn run(state: &AppState, rebuild: bool) -> Result<JobStats, JobError> {
-    let response = state
-        .http_client
-        .get(&state.config.feed_url)
-        .send()
-        .await?
-        .error_for_status()?;
-    let body = response.bytes().await?;
-    let entries =
-        parse_search_index_entries(&body).map_err(|err| JobError::Feed(err.to_string()))?;
+    let _run = state.feed_index_runs.lock().await;
+    run_sources(state, rebuild, &state.config.feed_sources, true).await
+}
 
-    if rebuild {
-        state.search.delete_all()?;
-    }
+// Refreshes a single source and leaves documents from the others alone.
+pub async fn run_source(state: &AppState, name: &str) -> Result<JobStats, JobError> {
+    let _run = state.feed_index_runs.lock().await;
+    let sources = state
+        .config
+        .feed_sources
+        .iter()
+        .filter(|source| source.name == name)
+        .cloned()
+        .collect::<Vec<_>>();
+    run_sources(state, false, &sources, false).await
+}
 
+async fn run_sources(
+    state: &AppState,
+    rebuild: bool,
+    sources: &[FeedSource],
+    all_sources: bool,
+) -> Result<JobStats, JobError> {
+    if !rebuild {
+        search_generation::reload_locked(state).await?;
+    }
+    let search = state.search.read().await.clone();
+    let rebuild = if rebuild {
+        true
+    } else if search.tokenizer_stale() {
+        info!("search tokenizer changed since the index was built; rebuilding");
+        true
+    } else if search.sections_stale() {
+        info!("search sections are behind the index; rebuilding");
+        true
+    } else if search.dir() != resolve_active_dir(&state.config.index_dir)? {
+        // Still serving the empty stand-in for an index with an outdated schema.
+        info!("search index schema changed since the index was built; rebuilding");
+        true
+    } else {
+        false
+    };
     let mut stats = JobStats {
         fetched: 0,
         indexed: 0,
         skipped: 0,
         failed: 0,
+        deleted: 0,
+        unchanged: !rebuild,
+    };
+
+    if rebuild {
+        let mut documents = Vec::new();
+        let mut fetches = Vec::new();
+        // A rebuild replaces the whole index, so every configured source is loaded.
+        for source in &state.config.feed_sources {
+            let Some(feed) = load_feed(state, source, false).await? else {
+                continue;
+            };
+            let batch = source_documents(&search, source, feed.entries, true, &mut stats)?;
+            documents.extend(batch.documents);
+            fetches.extend(feed.fetched);
+        }
+        stats.indexed = documents.len();
+        swap_in_rebuilt_index(state, documents).await?;
+        for fetched in fetches {
+            conditional_fetch::commit(state, fetched).await;
+        }
+    } else {
+        let mut indexed_ids = search.list_ids_by_source()?;
+        let mut first_error = None;
+        let mut refreshed = 0;
+        for source in sources {
+            let indexed = indexed_ids.remove(&source.name).unwrap_or_default();
+            match refresh_source(state, &search, source, &indexed, &mut stats).await {
+                Ok(()) => refreshed += 1,
+                Err(err) => {
+                    warn!(source = source.name, error = %err, "feed source refresh failed");
+                    stats.failed += 1;
+                    stats.unchanged = false;
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        if refreshed == 0
+            && let Some(err) = first_error
+        {
+            return Err(err);
+        }
+        if all_sources {
+            let configured = sources
+                .iter()
+                .map(|source| source.name.as_str())
+                .collect::<HashSet<_>>();
+            stats.deleted += prune_removed_sources(&search, &configured)?;
+        }
+    }
+    {
+        let mut health = state.admin_health.lock().await;
+        health.feed_index_last_run = Some(Utc::now());
+        health.feed_index_last_deleted = Some(stats.deleted);
+    }
+
+    Ok(stats)
+}
+
+async fn refresh_source(
+    state: &AppState,
+    search: &SearchIndex,
+    source: &FeedSource,
+    indexed_ids: &HashSet<String>,
+    stats: &mut JobStats,
+) -> Result<(), JobError> {
+    // A source with nothing indexed yet always takes the full feed.
+    let Some(feed) = load_feed(state, source, !indexed_ids.is_empty()).await? else {
+        debug!(source = source.name, "feed unchanged; index left as is");
+        return Ok(());
     };
-    let base_url = base_url_from_feed(&state.config.feed_url);
-    let mut to_index = Vec::new();
+    stats.unchanged = false;
+    let batch = source_documents(search, source, feed.entries, false, stats)?;
+    if !batch.documents.is_empty() {
+        search.upsert_documents(&batch.documents)?;
+        stats.indexed += batch.documents.len();
+    }
+    stats.deleted += prune_orphans(search, indexed_ids, &batch.fetched_ids)?;
+    if let Some(fetched) = feed.fetched {
+        conditional_fetch::commit(state, fetched).await;
+    }
+    Ok(())
+}
 
+struct SourceDocuments {
+    documents: Vec<SearchDocument>,
+    fetched_ids: HashSet<String>,
+}
+
+fn source_documents(
+    search: &SearchIndex,
+    source: &FeedSource,
+    entries: Vec<SearchIndexEntry>,
+    rebuild: bool,
+    stats: &mut JobStats,
+) -> Result<SourceDocuments, JobError> {
+    let base_url = source_base_url(source);
+    let mut documents = Vec::new();
+    let mut fetched_ids = HashSet::new();
     for entry in entries {
         stats.fetched += 1;
-        match entry_to_document_from_json(&entry, base_url.as_deref()) {
+        let url_raw = entry.url.trim();
+        if !url_raw.is_empty() {
+            fetched_ids.insert(resolve_entry_url(url_raw, base_url.as_deref()));
+        }
+        match entry_to_document_from_json(&entry, source, base_url.as_deref()) {
             Ok(doc) => {
-                if !rebuild {
-                    if let Some(existing) = state.search.get_checksum(&doc.id)? {
-                        if existing == doc.checksum {
-                            stats.skipped += 1;
-                            continue;
-                        }
-                    }
+                if !rebuild
+                    && let Some(existing) = search.get_checksum(&doc.id)?
+                    && existing == doc.checksum
+                {
+                    stats.skipped += 1;
+                    continue;
                 }
-                to_index.push(doc);
+                documents.push(doc);
             }
             Err(err) => {
                 stats.failed += 1;
-                warn!(error = %err, "failed to parse search index entry");
+                warn!(source = source.name, error = %err, "failed to parse search index entry");
+            }
+        }
+    }
+    Ok(SourceDocuments {
+        documents,
+        fetched_ids,
+    })
+}
+
+fn source_base_url(source: &FeedSource) -> Option<String> {
+    source
+        .base_url
+        .clone()
+        .or_else(|| base_url_from_feed(&source.url))
+}
+
+struct LoadedFeed {
+    entries: Vec<SearchIndexEntry>,
+    fetched: Option<FetchedBody>,
+}
+
+// `file://` feeds are read from disk; anything else is fetched over HTTP, and
+// `None` means the remote feed has not changed since the last committed fetch.
+async fn load_feed(
+    state: &AppState,
+    source: &FeedSource,
+    conditional: bool,
+) -> Result<Option<LoadedFeed>, JobError> {
+    if let Some(path) = local_feed::feed_path(&source.url) {
+        // Walking a Markdown tree is blocking file IO.
+        let entries = tokio::task::spawn_blocking(move || local_feed::load_entries(&path))
+            .await
+            .map_err(|err| JobError::Feed(err.to_string()))?
+            .map_err(|err| JobError::Feed(err.to_string()))?;
+        return Ok(Some(LoadedFeed {
+            entries,
+            fetched: None,
+        }));
+    }
+    let outcome = conditional_fetch::fetch(state, &source.url, conditional).await?;
+    let FetchOutcome::Changed(fetched) = outcome else {
+        return Ok(None);
+    };
+    let entries = parse_feed_entries(fetched.content_type.as_deref(), &fetched.body)
+        .map_err(|err| JobError::Feed(err.to_string()))?;
+    Ok(Some(LoadedFeed {
+        entries,
+        fetched: Some(fetched),
+    }))
+}
+
+// Comments are only synced for posts of the first (primary) source.
+pub(crate) async fn fetch_feed_entries(
+    state: &AppState,
+) -> Result<Vec<SearchIndexEntry>, JobError> {
+    let Some(source) = state.config.feed_sources.first() else {
+        return Ok(Vec::new());
+    };
+    Ok(load_feed(state, source, false)
+        .await?
+        .map(|feed| feed.entries)
+        .unwrap_or_default())
+}
+
+async fn swap_in_rebuilt_index(
+    state: &AppState,
+    documents: Vec<SearchDocument>,
+) -> Result<(), JobError> {
+    if documents.is_empty() {
+        return Err(JobError::IndexValidation(
+            "rebuild produced no documents".to_string(),
+        ));
+    }
+    let docs = documents.len();
+    let base = state.config.index_dir.clone();
+    let tokenizer = state.search_tokenizer.clone();
+    // Indexing, committing and removing generation directories is blocking IO.
+    let (index, dir) = tokio::task::spawn_blocking(move || {
+        let dir = new_generation_dir(&base);
+        match build_generation(&dir, &tokenizer, &documents) {
+            Ok(index) => {
+                activate_generation(&base, &dir)?;
+                Ok((index, dir))
+            }
+            Err(err) => {
+                let _ = std::fs::remove_dir_all(&dir);
+                Err(err)
             }
         }
+    })
+    .await
+    .map_err(std::io::Error::from)??;
+    *state.search.write().await = Arc::new(index);
+    info!(
+        index_dir = %dir.display(),
+        docs,
+        "search index generation activated"
+    );
+    let base = state.config.index_dir.clone();
+    let pruned = tokio::task::spawn_blocking(move || {
+        prune_generations(&base, &[dir.as_path()], RETIRED_GENERATION_RETENTION)
+    })
+    .await
+    .map_err(std::io::Error::from)?;
+    match pruned {
+        Ok(0) => {}
+        Ok(removed) => info!(removed, "pruned stale search index generations"),
+        Err(err) => warn!(error = %err, "failed to prune stale search index generations"),
+    }
+    Ok(())
+}
+
+fn build_generation(
+    dir: &Path,
+    tokenizer: &SearchTokenizer,
+    documents: &[SearchDocument],
+) -> Result<SearchIndex, JobError> {
+    let index = SearchIndex::open_with_tokenizer(dir, tokenizer)?;
+    index.upsert_documents(documents)?;
+    let expected = documents
+        .iter()
+        .map(|doc| doc.id.as_str())
+        .collect::<HashSet<_>>()
+        .len() as u64;
+    let num_docs = index.stats()?.num_docs;
+    if num_docs != expected {
+        return Err(JobError::IndexValidation(format!(
+            "expected {expected} documents, found {num_docs}"
+        )));
     }
+    mark_generation_complete(dir)?;
+    Ok(index)
+}
 
-    if !to_index.is_empty() {
-        state.search.upsert_documents(&to_index)?;
-        stats.indexed = to_index.len();
+fn prune_orphans(
+    search: &SearchIndex,
+    indexed_ids: &HashSet<String>,
+    fetched_ids: &HashSet<String>,
+) -> Result<usize, JobError> {
+    let orphans = select_orphans(indexed_ids, fetched_ids);
+    if orphans.is_empty() {
+        return Ok(0);
+    }
+    if !prune_allowed(indexed_ids.len(), orphans.len(), fetched_ids.len()) {
+        warn!(
+            indexed = indexed_ids.len(),
+            fetched = fetched_ids.len(),
+            orphans = orphans.len(),
+            "orphan pruning skipped: exceeds safety threshold"
+        );
+        return Ok(0);
     }
+    search.delete_documents(&orphans)?;
+    info!(count = orphans.len(), "pruned orphan search documents");
+    Ok(orphans.len())
+}
 
-    Ok(stats)
+// Listed after the refresh, so a document that just moved to a configured
+// source is no longer counted under the removed one.
+fn prune_removed_sources(
+    search: &SearchIndex,
+    configured: &HashSet<&str>,
+) -> Result<usize, JobError> {
+    let mut removed = search.list_ids_by_source()?;
+    let indexed = removed.values().map(HashSet::len).sum::<usize>();
+    removed.retain(|source, _| !configured.contains(source.as_str()));
+    let orphans = removed.values().map(HashSet::len).sum::<usize>();
+    if orphans == 0 {
+        return Ok(0);
+    }
+    if !prune_allowed(indexed, orphans, indexed - orphans) {
+        warn!(
+            indexed,
+            orphans,
+            "removed source pruning skipped: exceeds safety threshold"
+        );
+        return Ok(0);
+    }
+    let mut deleted = 0;
+    for (source, ids) in removed {
+        let mut ids = ids.into_iter().collect::<Vec<_>>();
+        ids.sort();
+        search.delete_documents(&ids)?;
+        info!(source, count = ids.len(), "pruned documents of removed feed source");
+        deleted += ids.len();
+    }
+    Ok(deleted)
+}
+
+fn select_orphans(indexed_ids: &HashSet<String>, fetched_ids: &HashSet<String>) -> Vec<String> {
+    let mut orphans = indexed_ids
+        .difference(fetched_ids)
+        .cloned()
+        .collect::<Vec<_>>();
+    orphans.sort();
+    orphans
+}
+
+fn prune_allowed(indexed: usize, orphans: usize, fetched: usize) -> bool {
+    if fetched == 0 {
+        return false;
+    }
+    orphans as f64 <= indexed as f64 * PRUNE_MAX_RATIO
 }
 
 pub(crate) fn parse_search_index_entries(
@@ -99,6 +495,266 @@ pub(crate) fn parse_search_index_entries(
     serde_json::from_slice(json)
 }
 
+pub(crate) fn parse_feed_entries(
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<Vec<SearchIndexEntry>, FeedParseError> {
+    match detect_feed_format(content_type, body).ok_or(FeedParseError::UnknownFormat)? {
+        FeedFormat::SearchIndex => Ok(parse_search_index_entries(body)?),
+        FeedFormat::JsonFeed => parse_json_feed(body),
+        FeedFormat::Atom => parse_atom_feed(body),
+        FeedFormat::Rss => parse_rss_feed(body),
+    }
+}
+
+// A content type naming a feed format wins; generic JSON/XML types and missing
+// headers fall back to the first significant character or the XML root element.
+pub(crate) fn detect_feed_format(content_type: Option<&str>, body: &[u8]) -> Option<FeedFormat> {
+    let mime = content_type
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase());
+    match mime.as_deref() {
+        Some("application/atom+xml") => return Some(FeedFormat::Atom),
+        Some("application/rss+xml") => return Some(FeedFormat::Rss),
+        Some("application/feed+json") => return Some(FeedFormat::JsonFeed),
+        _ => {}
+    }
+    let text = String::from_utf8_lossy(body.get(..4096).unwrap_or(body));
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    match text.chars().next()? {
+        '[' => Some(FeedFormat::SearchIndex),
+        '{' => Some(FeedFormat::JsonFeed),
+        '<' => match xml_root_name(text)? {
+            "feed" => Some(FeedFormat::Atom),
+            "rss" => Some(FeedFormat::Rss),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Local name of the first element, skipping the declaration, comments and DOCTYPE.
+fn xml_root_name(text: &str) -> Option<&str> {
+    let mut rest = text;
+    loop {
+        let start = rest.find('<')?;
+        rest = &rest[start + 1..];
+        if rest.starts_with(|ch: char| ch.is_alphabetic()) {
+            let end = rest
+                .find(|ch: char| ch.is_whitespace() || ch == '>' || ch == '/')
+                .unwrap_or(rest.len());
+            let name = &rest[..end];
+            return Some(name.rsplit(':').next().unwrap_or(name));
+        }
+    }
+}
+
+fn parse_json_feed(body: &[u8]) -> Result<Vec<SearchIndexEntry>, FeedParseError> {
+    let feed: JsonFeed = serde_json::from_slice(body)?;
+    Ok(feed
+        .items
+        .into_iter()
+        .map(|item| {
+            let (content, sections) = match (item.content_text, item.content_html) {
+                (Some(text), _) => (text, None),
+                (None, Some(html)) => (html_to_text(&html), Some(html_sections(&html))),
+                (None, None) => (item.summary.unwrap_or_default(), None),
+            };
+            SearchIndexEntry {
+                title: item.title.unwrap_or_default(),
+                subtitle: None,
+                url: item.url.or(item.id.filter(|id| is_http_url(id))).unwrap_or_default(),
+                date: item
+                    .date_published
+                    .or_else(|| item.date_modified.clone())
+                    .unwrap_or_default(),
+                updated: item.date_modified,
+                category: None,
+                tags: item.tags,
+                content,
+                sections,
+            }
+        })
+        .collect())
+}
+
+fn parse_atom_feed(body: &[u8]) -> Result<Vec<SearchIndexEntry>, FeedParseError> {
+    let text = std::str::from_utf8(body).map_err(|_| FeedParseError::Encoding)?;
+    let doc = roxmltree::Document::parse(text)?;
+    let root = doc.root_element();
+    if !is_element_named(root, Some(ATOM_NS), "feed") {
+        return Err(FeedParseError::UnexpectedRoot(root.tag_name().name().to_string()));
+    }
+    Ok(root
+        .children()
+        .filter(|node| is_element_named(*node, Some(ATOM_NS), "entry"))
+        .map(|entry| {
+            let published = child_text(entry, Some(ATOM_NS), "published");
+            let updated = child_text(entry, Some(ATOM_NS), "updated");
+            let content_node = child(entry, Some(ATOM_NS), "content")
+                .or_else(|| child(entry, Some(ATOM_NS), "summary"));
+            let content = content_node.map(atom_text).unwrap_or_default();
+            let sections = content_node.and_then(atom_sections);
+            SearchIndexEntry {
+                title: child(entry, Some(ATOM_NS), "title")
+                    .map(atom_text)
+                    .unwrap_or_default(),
+                subtitle: None,
+                url: atom_link(entry).unwrap_or_default(),
+                date: published.clone().or_else(|| updated.clone()).unwrap_or_default(),
+                updated,
+                category: None,
+                tags: entry
+                    .children()
+                    .filter(|node| is_element_named(*node, Some(ATOM_NS), "category"))
+                    .filter_map(|node| node.attribute("term"))
+                    .map(str::to_string)
+                    .collect(),
+                content,
+                sections,
+            }
+        })
+        .collect())
+}
+
+fn atom_link(entry: Node<'_, '_>) -> Option<String> {
+    entry
+        .children()
+        .filter(|node| is_element_named(*node, Some(ATOM_NS), "link"))
+        .find(|node| matches!(node.attribute("rel"), None | Some("alternate")))
+        .and_then(|node| node.attribute("href"))
+        .map(str::to_string)
+        .or_else(|| child_text(entry, Some(ATOM_NS), "id").filter(|id| is_http_url(id)))
+}
+
+// Atom text constructs carry their markup in the `type` attribute.
+fn atom_text(node: Node<'_, '_>) -> String {
+    match node.attribute("type") {
+        Some("html") => html_to_text(&node_text(node)),
+        Some("xhtml") => html_to_text(&node.document().input_text()[node.range()]),
+        _ => node_text(node),
+    }
+}
+
+fn atom_sections(node: Node<'_, '_>) -> Option<Vec<SearchSection>> {
+    match node.attribute("type") {
+        Some("html") => Some(html_sections(&node_text(node))),
+        Some("xhtml") => Some(html_sections(&node.document().input_text()[node.range()])),
+        _ => None,
+    }
+}
+
+fn parse_rss_feed(body: &[u8]) -> Result<Vec<SearchIndexEntry>, FeedParseError> {
+    let text = std::str::from_utf8(body).map_err(|_| FeedParseError::Encoding)?;
+    let doc = roxmltree::Document::parse(text)?;
+    let root = doc.root_element();
+    if !is_element_named(root, None, "rss") {
+        return Err(FeedParseError::UnexpectedRoot(root.tag_name().name().to_string()));
+    }
+    let Some(channel) = child(root, None, "channel") else {
+        return Ok(Vec::new());
+    };
+    Ok(channel
+        .children()
+        .filter(|node| is_element_named(*node, None, "item"))
+        .map(|item| {
+            let guid = child(item, None, "guid")
+                .filter(|node| node.attribute("isPermaLink") != Some("false"))
+                .map(node_text)
+                .filter(|guid| is_http_url(guid.trim()));
+            let date = child_text(item, None, "pubDate")
+                .map(|value| rfc2822_to_rfc3339(&value))
+                .or_else(|| child_text(item, Some(DUBLIN_CORE_NS), "date"))
+                .unwrap_or_default();
+            let html = child(item, Some(RSS_CONTENT_NS), "encoded")
+                .or_else(|| child(item, None, "description"))
+                .map(node_text)
+                .unwrap_or_default();
+            SearchIndexEntry {
+                title: child_text(item, None, "title").unwrap_or_default(),
+                subtitle: None,
+                url: child_text(item, None, "link").or(guid).unwrap_or_default(),
+                date,
+                updated: None,
+                category: None,
+                tags: item
+                    .children()
+                    .filter(|node| is_element_named(*node, None, "category"))
+                    .map(node_text)
+                    .collect(),
+                content: html_to_text(&html),
+                sections: Some(html_sections(&html)),
+            }
+        })
+        .collect())
+}
+
+// RSS dates are RFC 2822; anything else is passed through and rejected later.
+fn rfc2822_to_rfc3339(value: &str) -> String {
+    DateTime::parse_from_rfc2822(value.trim())
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn child<'a, 'input>(
+    node: Node<'a, 'input>,
+    namespace: Option<&str>,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| is_element_named(*child, namespace, name))
+}
+
+fn is_element_named(node: Node<'_, '_>, namespace: Option<&str>, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name && node.tag_name().namespace() == namespace
+}
+
+fn child_text(node: Node<'_, '_>, namespace: Option<&str>, name: &str) -> Option<String> {
+    child(node, namespace, name)
+        .map(node_text)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+// Text and CDATA below `node`, in document order.
+fn node_text(node: Node<'_, '_>) -> String {
+    node.descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect()
+}
+
+fn html_to_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut output = String::with_capacity(html.len());
+    push_element_text(fragment.root_element(), &mut output);
+    output
+}
+
+fn push_element_text(element: ElementRef<'_>, output: &mut String) {
+    for child in element.children() {
+        if let Some(text) = child.value().as_text() {
+            output.push_str(text);
+        } else if let Some(child) = ElementRef::wrap(child) {
+            let name = child.value().name();
+            if matches!(name, "script" | "style" | "template") {
+                continue;
+            }
+            let block = BLOCK_ELEMENTS.contains(&name);
+            if block {
+                output.push(' ');
+            }
+            push_element_text(child, output);
+            if block {
+                output.push(' ');
+            }
+        }
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
 pub(crate) fn base_url_from_feed(feed_url: &str) -> Option<String> {
     let (scheme, rest) = feed_url.split_once("://")?;
     let host = rest.split('/').next()?;
@@ -110,6 +766,7 @@ pub(crate) fn base_url_from_feed(feed_url: &str) -> Option<String> {
 
 fn entry_to_document_from_json(
     entry: &SearchIndexEntry,
+    source: &FeedSource,
     base_url: Option<&str>,
 ) -> Result<SearchDocument, EntryError> {
     let title = entry.title.trim();
@@ -134,6 +791,10 @@ fn entry_to_document_from_json(
 
     let content_raw = sanitize_markdown(&entry.content);
     let content = normalize_whitespace(&content_raw);
+    let sections = entry
+        .sections
+        .clone()
+        .unwrap_or_else(|| markdown_sections(&content_raw));
 
     let subtitle = entry
         .subtitle
@@ -146,6 +807,7 @@ fn entry_to_document_from_json(
         .as_deref()
         .map(str::trim)
         .filter(|value| !value.is_empty())
+        .or(source.category.as_deref())
         .map(|value| value.to_string());
     let tags = entry
         .tags
@@ -164,6 +826,8 @@ fn entry_to_document_from_json(
         &url,
         &tags,
         category.as_deref().unwrap_or(""),
+        &source.name,
+        &sections,
         published_at,
         updated_at,
     );
@@ -176,9 +840,11 @@ fn entry_to_document_from_json(
         url,
         tags,
         category,
+        source: source.name.clone(),
         published_at,
         updated_at,
         checksum,
+        sections,
     })
 }
 
@@ -211,6 +877,7 @@ fn parse_datetime(value: &str) -> Result<DateTime<Utc>, EntryError> {
     Ok(parsed.with_timezone(&Utc))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn compute_checksum(
     id: &str,
     title: &str,
@@ -219,6 +886,8 @@ fn compute_checksum(
     url: &str,
     tags: &[String],
     category: &str,
+    source: &str,
+    sections: &[SearchSection],
     published_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 ) -> String {
@@ -239,13 +908,22 @@ fn compute_checksum(
     }
     hasher.update(category.as_bytes());
     hasher.update([0]);
+    hasher.update(source.as_bytes());
+    hasher.update([0]);
+    // Section text is part of the content; anchors and headings may not be.
+    for section in sections {
+        hasher.update(section.anchor.as_bytes());
+        hasher.update([0]);
+        hasher.update(section.heading.as_bytes());
+        hasher.update([0]);
+    }
     hasher.update(published_at.timestamp().to_string().as_bytes());
     hasher.update([0]);
     hasher.update(updated_at.timestamp().to_string().as_bytes());
     hex::encode(hasher.finalize())
 }
 
-fn normalize_whitespace(input: &str) -> String {
+pub(crate) fn normalize_whitespace(input: &str) -> String {
     let mut parts = input.split_whitespace();
     let Some(first) = parts.next() else {
         return String::new();
@@ -261,9 +939,47 @@ fn normalize_whitespace(input: &str) -> String {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
     use super::{
-        entry_to_document_from_json, parse_search_index_entries, SearchIndexEntry,
+        build_generation, detect_feed_format, entry_to_document_from_json, parse_feed_entries,
+        parse_search_index_entries, prune_allowed, prune_removed_sources, select_orphans,
+        source_base_url, EntryError, FeedFormat, SearchIndexEntry,
     };
+    use crate::config::FeedSource;
+    use crate::test_support::temp_dir;
+    use chrono::{TimeZone, Utc};
+    use inkstone_core::domain::search::{SearchDocument, SearchSection};
+    use inkstone_infra::search::SearchTokenizer;
+
+    const ATOM_FIXTURE: &str = include_str!("../../../tests/fixtures/feeds/atom.xml");
+    const RSS_FIXTURE: &str = include_str!("../../../tests/fixtures/feeds/rss.xml");
+    const JSON_FEED_FIXTURE: &str = include_str!("../../../tests/fixtures/feeds/feed.json");
+
+    fn test_source() -> FeedSource {
+        FeedSource {
+            name: "blog".to_string(),
+            url: "https://blog.example.com/search-index.json".to_string(),
+            base_url: None,
+            category: None,
+            weight: 1.0,
+        }
+    }
+
+    fn document(
+        entry: &SearchIndexEntry,
+        base_url: Option<&str>,
+    ) -> Result<SearchDocument, EntryError> {
+        entry_to_document_from_json(entry, &test_source(), base_url)
+    }
+
+    fn fixture_documents(content_type: Option<&str>, body: &str) -> Vec<SearchDocument> {
+        parse_feed_entries(content_type, body.as_bytes())
+            .unwrap()
+            .iter()
+            .filter_map(|entry| document(entry, Some("https://blog.example.com")).ok())
+            .collect()
+    }
 
     fn base_entry() -> SearchIndexEntry {
         SearchIndexEntry {
@@ -275,6 +991,7 @@ mod tests {
             category: Some("分享境".to_string()),
             tags: vec!["Rust".to_string()],
             content: "Hi".to_string(),
+            sections: None,
         }
     }
 
@@ -283,7 +1000,7 @@ mod tests {
         let mut entry = base_entry();
         entry.content = "<Suspense><div>Hi</div></Suspense>".to_string();
 
-        let doc = entry_to_document_from_json(&entry, Some("https://example.com")).unwrap();
+        let doc = document(&entry, Some("https://example.com")).unwrap();
         assert!(doc.content.contains("<Suspense>"));
     }
 
@@ -299,15 +1016,156 @@ mod tests {
     "updated": "2025-01-02T00:00:00Z",
     "category": "分享境",
     "tags": ["Rust"],
-    "content": "Hi<!--more-->there"
+    "content": "Hi<!--more-->there\n\n## Next steps {#next}\nMore"
   }
 ]
 "#;
         let entries = parse_search_index_entries(json.as_bytes()).unwrap();
-        let doc =
-            entry_to_document_from_json(&entries[0], Some("https://example.com")).unwrap();
+        let doc = document(&entries[0], Some("https://example.com")).unwrap();
         assert_eq!(doc.url, "https://example.com/posts/hello/");
         assert!(doc.content.contains("Hi"));
         assert!(!doc.content.contains("<!--more-->"));
+        assert_eq!(
+            doc.sections,
+            vec![SearchSection {
+                anchor: "next".to_string(),
+                heading: "Next steps".to_string(),
+                content: "More".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn source_sets_name_default_category_and_base_url() {
+        let source = FeedSource {
+            name: "notes".to_string(),
+            url: "file:///srv/notes".to_string(),
+            base_url: Some("https://notes.example.com".to_string()),
+            category: Some("wiki".to_string()),
+            weight: 0.5,
+        };
+        let base_url = source_base_url(&source);
+        assert_eq!(base_url.as_deref(), Some("https://notes.example.com"));
+        assert_eq!(
+            source_base_url(&test_source()).as_deref(),
+            Some("https://blog.example.com")
+        );
+
+        let mut entry = base_entry();
+        entry.category = None;
+        let doc = entry_to_document_from_json(&entry, &source, base_url.as_deref()).unwrap();
+        assert_eq!(doc.source, "notes");
+        assert_eq!(doc.category.as_deref(), Some("wiki"));
+        assert_eq!(doc.url, "https://notes.example.com/posts/hello/");
+        let blog = document(&base_entry(), base_url.as_deref()).unwrap();
+        assert_eq!(blog.category.as_deref(), Some("分享境"));
+        assert_ne!(blog.checksum, doc.checksum);
+    }
+
+    #[test]
+    fn feed_format_follows_content_type_then_body() {
+        let cases = [
+            (Some("application/atom+xml; charset=utf-8"), "", Some(FeedFormat::Atom)),
+            (Some("application/feed+json"), "[]", Some(FeedFormat::JsonFeed)),
+            (Some("application/xml"), ATOM_FIXTURE, Some(FeedFormat::Atom)),
+            (Some("text/xml"), RSS_FIXTURE, Some(FeedFormat::Rss)),
+            (None, JSON_FEED_FIXTURE, Some(FeedFormat::JsonFeed)),
+            (Some("application/json"), "\u{feff} [{}]", Some(FeedFormat::SearchIndex)),
+            (None, "<!-- c --><html></html>", None),
+            (None, "plain", None),
+        ];
+        for (content_type, body, expected) in cases {
+            assert_eq!(detect_feed_format(content_type, body.as_bytes()), expected, "{body}");
+        }
+    }
+
+    #[test]
+    fn atom_fixture_builds_documents() {
+        let docs = fixture_documents(None, ATOM_FIXTURE);
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].title, "Rust <3 Tantivy");
+        assert_eq!(docs[0].url, "https://blog.example.com/posts/rust-tantivy/");
+        assert_eq!(docs[0].tags, vec!["Rust".to_string(), "Search".to_string()]);
+        assert_eq!(docs[0].content, "Building an index with & segments");
+        assert_eq!(docs[0].published_at, Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(docs[0].updated_at, Utc.with_ymd_and_hms(2025, 1, 2, 8, 0, 0).unwrap());
+        assert_eq!(docs[1].title, "Plain <T> generics");
+        assert_eq!(docs[1].content, "Type parameters");
+        assert_eq!(docs[1].published_at, docs[1].updated_at);
+    }
+
+    #[test]
+    fn rss_fixture_builds_documents() {
+        let entries = parse_feed_entries(Some("application/rss+xml"), RSS_FIXTURE.as_bytes())
+            .unwrap();
+        assert_eq!(entries.len(), 3);
+        let docs = fixture_documents(Some("application/rss+xml"), RSS_FIXTURE);
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].url, "https://blog.example.com/posts/vacuum/");
+        assert_eq!(docs[0].tags, vec!["Postgres".to_string(), "Ops".to_string()]);
+        assert_eq!(docs[0].content, "Autovacuum Dead tuples pile up");
+        assert_eq!(docs[0].sections[0].anchor, "autovacuum");
+        assert_eq!(docs[0].sections[0].content, "Dead tuples pile up");
+        assert_eq!(docs[0].published_at, Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(docs[1].url, "https://blog.example.com/posts/guid-only/");
+        assert_eq!(docs[1].content, "Escaped HTML");
+    }
+
+    #[test]
+    fn json_feed_fixture_builds_documents() {
+        let docs = fixture_documents(Some("application/feed+json"), JSON_FEED_FIXTURE);
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].url, "https://blog.example.com/posts/jieba/");
+        assert_eq!(docs[0].content, "结巴分词 词典 停用词");
+        assert_eq!(docs[0].tags, vec!["中文".to_string(), "Search".to_string()]);
+        assert_eq!(docs[0].updated_at, Utc.with_ymd_and_hms(2025, 1, 3, 0, 0, 0).unwrap());
+        assert_eq!(docs[1].url, "https://blog.example.com/posts/plain-text/");
+        assert_eq!(docs[1].content, "Already <plain> text");
+    }
+
+    #[test]
+    fn select_orphans_returns_ids_missing_from_feed() {
+        let indexed: HashSet<String> = ["a", "b", "c"].iter().map(|id| id.to_string()).collect();
+        let fetched: HashSet<String> = ["a", "c", "d"].iter().map(|id| id.to_string()).collect();
+        assert_eq!(select_orphans(&indexed, &fetched), vec!["b".to_string()]);
+    }
+
+    #[test]
+    fn prune_rejects_empty_or_truncated_feed() {
+        assert!(!prune_allowed(10, 10, 0));
+        assert!(!prune_allowed(10, 6, 4));
+        assert!(prune_allowed(10, 5, 5));
+        assert!(prune_allowed(10, 1, 9));
+    }
+
+    #[test]
+    fn build_generation_counts_unique_documents() {
+        let dir = temp_dir("inkstone-feed-generation");
+        let doc = document(&base_entry(), Some("https://example.com")).unwrap();
+        let tokenizer = SearchTokenizer::default();
+        let index = build_generation(&dir, &tokenizer, &[doc.clone(), doc]).unwrap();
+        assert_eq!(index.stats().unwrap().num_docs, 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn removed_sources_are_pruned_within_threshold() {
+        let dir = temp_dir("inkstone-feed-removed");
+        let docs = [("a", "blog"), ("b", "blog"), ("c", "old"), ("d", "old")]
+            .map(|(id, source)| SearchDocument {
+                id: id.to_string(),
+                source: source.to_string(),
+                ..document(&base_entry(), None).unwrap()
+            })
+            .to_vec();
+        let tokenizer = SearchTokenizer::default();
+        let index = build_generation(&dir, &tokenizer, &docs).unwrap();
+
+        // Nothing left of the configured sources looks like a config mistake.
+        assert_eq!(prune_removed_sources(&index, &HashSet::from(["talks"])).unwrap(), 0);
+        assert_eq!(index.stats().unwrap().num_docs, 4);
+        assert_eq!(prune_removed_sources(&index, &HashSet::from(["blog"])).unwrap(), 2);
+        assert_eq!(index.list_ids().unwrap(), HashSet::from(["a".into(), "b".into()]));
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
@@ -0,0 +1,199 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+use crate::jobs::JobError;
+use crate::jobs::tasks::{feed_index, local_feed};
+use crate::state::AppState;
+
+// Editors and site generators touch several files per save.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchTarget {
+    source: String,
+    root: PathBuf,
+    file: Option<PathBuf>,
+}
+
+impl WatchTarget {
+    fn mode(&self) -> RecursiveMode {
+        if self.file.is_some() {
+            RecursiveMode::NonRecursive
+        } else {
+            RecursiveMode::Recursive
+        }
+    }
+}
+
+// Only `file://` sources are watched. A single index file is watched through its
+// directory so that editors replacing the file by rename are still seen. Paths are
+// canonicalized because notify reports absolute paths.
+pub fn watch_targets(config: &AppConfig) -> Vec<WatchTarget> {
+    config
+        .feed_sources
+        .iter()
+        .filter_map(|source| {
+            let path = local_feed::feed_path(&source.url)?;
+            if path.is_dir() {
+                return Some(WatchTarget {
+                    source: source.name.clone(),
+                    root: canonical(path),
+                    file: None,
+                });
+            }
+            let root = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => canonical(parent.to_path_buf()),
+                _ => canonical(PathBuf::from(".")),
+            };
+            let file = path.file_name().map(|name| root.join(name))?;
+            Some(WatchTarget {
+                source: source.name.clone(),
+                root,
+                file: Some(file),
+            })
+        })
+        .collect()
+}
+
+pub async fn run(state: AppState, targets: Vec<WatchTarget>) -> Result<(), JobError> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = sender.send(event);
+    })?;
+    let mut watched = Vec::new();
+    for target in targets {
+        match watcher.watch(&target.root, target.mode()) {
+            Ok(()) => {
+                info!(
+                    source = target.source,
+                    path = %target.root.display(),
+                    "watching local feed for changes"
+                );
+                watched.push(target);
+            }
+            Err(err) => warn!(
+                source = target.source,
+                path = %target.root.display(),
+                error = %err,
+                "feed watcher disabled"
+            ),
+        }
+    }
+    if watched.is_empty() {
+        return Ok(());
+    }
+    let mut changed = BTreeSet::new();
+    loop {
+        let Some(event) = receiver.recv().await else {
+            return Ok(());
+        };
+        collect_changed(event, &watched, &mut changed);
+        if changed.is_empty() {
+            continue;
+        }
+        while let Ok(Some(event)) = timeout(WATCH_DEBOUNCE, receiver.recv()).await {
+            collect_changed(event, &watched, &mut changed);
+        }
+        for source in std::mem::take(&mut changed) {
+            match feed_index::run_source(&state, source).await {
+                Ok(stats) => info!(source, ?stats, "feed watcher reindex complete"),
+                Err(err) => warn!(source, error = %err, "feed watcher reindex failed"),
+            }
+        }
+    }
+}
+
+fn collect_changed<'a>(
+    event: notify::Result<Event>,
+    targets: &'a [WatchTarget],
+    changed: &mut BTreeSet<&'a str>,
+) {
+    match event {
+        Ok(event) => changed.extend(
+            targets
+                .iter()
+                .filter(|target| is_relevant(&event, target))
+                .map(|target| target.source.as_str()),
+        ),
+        Err(err) => warn!(error = %err, "feed watcher error"),
+    }
+}
+
+fn is_relevant(event: &Event, target: &WatchTarget) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    match &target.file {
+        Some(file) => event.paths.iter().any(|path| path == file),
+        // A removed or renamed directory takes its posts with it.
+        None => event.paths.iter().filter(|path| path.starts_with(&target.root)).any(|path| {
+            local_feed::is_markdown_file(path)
+                || (matches!(event.kind, EventKind::Remove(_) | EventKind::Modify(_))
+                    && is_directory_like(path))
+        }),
+    }
+}
+
+fn canonical(path: PathBuf) -> PathBuf {
+    std::fs::canonicalize(&path).unwrap_or(path)
+}
+
+fn is_directory_like(path: &Path) -> bool {
+    path.is_dir() || (!path.exists() && path.extension().is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, DataChange, ModifyKind, RemoveKind};
+
+    fn event(kind: EventKind, path: &str) -> Event {
+        Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn markdown_directories_react_to_posts_and_removed_folders() {
+        let target = WatchTarget {
+            source: "blog".to_string(),
+            root: PathBuf::from("/nonexistent/posts"),
+            file: None,
+        };
+        let modify = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        assert!(is_relevant(&event(modify, "/nonexistent/posts/a.md"), &target));
+        assert!(is_relevant(
+            &event(EventKind::Create(CreateKind::File), "/nonexistent/posts/b/index.markdown"),
+            &target
+        ));
+        assert!(is_relevant(
+            &event(EventKind::Remove(RemoveKind::Folder), "/nonexistent/posts/old"),
+            &target
+        ));
+        assert!(!is_relevant(&event(modify, "/nonexistent/posts/.a.md.swp"), &target));
+        assert!(!is_relevant(&event(modify, "/nonexistent/posts/.a.md"), &target));
+        assert!(!is_relevant(&event(modify, "/nonexistent/posts/image.png"), &target));
+        assert!(!is_relevant(
+            &event(EventKind::Access(AccessKind::Any), "/nonexistent/posts/a.md"),
+            &target
+        ));
+    }
+
+    #[test]
+    fn index_files_only_react_to_the_feed_file() {
+        let target = WatchTarget {
+            source: "blog".to_string(),
+            root: PathBuf::from("/nonexistent"),
+            file: Some(PathBuf::from("/nonexistent/search-index.json")),
+        };
+        let modify = EventKind::Modify(ModifyKind::Any);
+        assert!(is_relevant(&event(modify, "/nonexistent/search-index.json"), &target));
+        assert!(!is_relevant(&event(modify, "/nonexistent/other.json"), &target));
+        assert_eq!(target.mode(), RecursiveMode::NonRecursive);
+    }
+}
@@ -0,0 +1,292 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::jobs::tasks::feed_index::{parse_feed_entries, FeedParseError, SearchIndexEntry};
+
+const FILE_SCHEME: &str = "file://";
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+#[derive(Debug, Error)]
+pub(crate) enum LocalFeedError {
+    #[error("failed to read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("{0}")]
+    Feed(#[from] FeedParseError),
+}
+
+#[derive(Debug, Error)]
+enum FrontMatterError {
+    #[error("missing front matter")]
+    Missing,
+    #[error("unterminated front matter")]
+    Unterminated,
+    #[error("invalid front matter: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FrontMatter {
+    title: Option<String>,
+    subtitle: Option<String>,
+    date: Option<String>,
+    updated: Option<String>,
+    tags: Option<StringList>,
+    category: Option<String>,
+    url: Option<String>,
+    slug: Option<String>,
+}
+
+// Front matter written by hand often uses `tags: rust` for a single tag.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StringList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl StringList {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            StringList::One(value) => vec![value],
+            StringList::Many(values) => values,
+        }
+    }
+}
+
+// `file:///srv/blog/posts` and `file://data/search-index.json` both map to a path.
+pub(crate) fn feed_path(feed_url: &str) -> Option<PathBuf> {
+    let rest = feed_url.trim().strip_prefix(FILE_SCHEME)?;
+    let path = rest
+        .strip_prefix("localhost")
+        .filter(|path| path.starts_with('/'))
+        .unwrap_or(rest);
+    (!path.is_empty()).then(|| PathBuf::from(path))
+}
+
+// A directory is read as Markdown posts; a file goes through the regular feed detection.
+pub(crate) fn load_entries(path: &Path) -> Result<Vec<SearchIndexEntry>, LocalFeedError> {
+    let metadata = std::fs::metadata(path).map_err(|err| LocalFeedError::Io(path.into(), err))?;
+    if !metadata.is_dir() {
+        let body = std::fs::read(path).map_err(|err| LocalFeedError::Io(path.into(), err))?;
+        return Ok(parse_feed_entries(None, &body)?);
+    }
+    let mut files = Vec::new();
+    collect_markdown_files(path, &mut files)?;
+    files.sort();
+    let mut entries = Vec::with_capacity(files.len());
+    for file in files {
+        let text =
+            std::fs::read_to_string(&file).map_err(|err| LocalFeedError::Io(file.clone(), err))?;
+        match markdown_entry(&file, &text) {
+            Ok(entry) => entries.push(entry),
+            Err(FrontMatterError::Missing) => {
+                debug!(path = %file.display(), "markdown file without front matter skipped");
+            }
+            Err(err) => warn!(path = %file.display(), error = %err, "markdown file skipped"),
+        }
+    }
+    Ok(entries)
+}
+
+pub(crate) fn is_markdown_file(path: &Path) -> bool {
+    !is_hidden(path)
+        && path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| MARKDOWN_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+fn collect_markdown_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), LocalFeedError> {
+    let read_dir = std::fs::read_dir(dir).map_err(|err| LocalFeedError::Io(dir.into(), err))?;
+    for entry in read_dir {
+        let entry = entry.map_err(|err| LocalFeedError::Io(dir.into(), err))?;
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .map_err(|err| LocalFeedError::Io(path.clone(), err))?;
+        if file_type.is_dir() {
+            if !is_hidden(&path) {
+                collect_markdown_files(&path, files)?;
+            }
+        } else if is_markdown_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn markdown_entry(path: &Path, text: &str) -> Result<SearchIndexEntry, FrontMatterError> {
+    let (yaml, body) = split_front_matter(text)?;
+    let front: FrontMatter = if yaml.trim().is_empty() {
+        FrontMatter::default()
+    } else {
+        serde_yaml::from_str(yaml)?
+    };
+    let url = front
+        .url
+        .filter(|url| !url.trim().is_empty())
+        .or_else(|| {
+            front
+                .slug
+                .as_deref()
+                .map(|slug| slug.trim().trim_matches('/'))
+                .filter(|slug| !slug.is_empty())
+                .map(post_url)
+        })
+        .or_else(|| file_slug(path).map(|slug| post_url(&slug)))
+        .unwrap_or_default();
+    Ok(SearchIndexEntry {
+        title: front.title.unwrap_or_default(),
+        subtitle: front.subtitle,
+        url,
+        date: front.date.as_deref().map(normalize_date).unwrap_or_default(),
+        updated: front.updated.as_deref().map(normalize_date),
+        category: front.category,
+        tags: front.tags.map(StringList::into_vec).unwrap_or_default(),
+        content: body.to_string(),
+        sections: None,
+    })
+}
+
+fn post_url(slug: &str) -> String {
+    format!("/posts/{slug}/")
+}
+
+// Page bundles keep the post in `<slug>/index.md`.
+fn file_slug(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    if stem.eq_ignore_ascii_case("index") {
+        return path.parent()?.file_name()?.to_str().map(str::to_string);
+    }
+    Some(stem.to_string())
+}
+
+fn split_front_matter(text: &str) -> Result<(&str, &str), FrontMatterError> {
+    let text = text.trim_start_matches('\u{feff}');
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return Err(FrontMatterError::Missing);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            return Ok((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    Err(FrontMatterError::Unterminated)
+}
+
+// Static site generators accept bare dates and local times; those are read as UTC.
+// Anything unrecognised is passed through and rejected when the entry is indexed.
+fn normalize_date(value: &str) -> String {
+    let trimmed = value.trim();
+    if DateTime::parse_from_rfc3339(trimmed).is_ok() {
+        return trimmed.to_string();
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(trimmed, format) {
+            return datetime.and_utc().to_rfc3339();
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(trimmed, "%Y-%m-%d")
+        && let Some(datetime) = date.and_hms_opt(0, 0, 0)
+    {
+        return datetime.and_utc().to_rfc3339();
+    }
+    trimmed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn feed_path_accepts_absolute_relative_and_localhost_urls() {
+        assert_eq!(feed_path("file:///srv/blog"), Some(PathBuf::from("/srv/blog")));
+        assert_eq!(feed_path("file://data/index.json"), Some(PathBuf::from("data/index.json")));
+        assert_eq!(feed_path("file://localhost/srv/blog"), Some(PathBuf::from("/srv/blog")));
+        assert_eq!(feed_path("file://"), None);
+        assert_eq!(feed_path("https://example.com/search-index.json"), None);
+    }
+
+    #[test]
+    fn load_entries_reads_markdown_front_matter() {
+        let dir = temp_dir("inkstone-local-feed");
+        std::fs::create_dir_all(dir.join("bundle")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(
+            dir.join("first.md"),
+            "---\ntitle: First\nsubtitle: One\ndate: 2024-01-02\nupdated: 2024-01-03 08:30\n\
+             tags: rust\ncategory: notes\nslug: hello-world\n---\n# Heading\n\nBody text.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("bundle/index.md"),
+            "---\r\ntitle: Bundled\r\ndate: 2024-02-01T10:00:00+08:00\r\ntags: [a, b]\r\n---\r\nText\r\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("linked.markdown"),
+            "---\ntitle: Linked\ndate: 2024-03-01\nurl: https://example.com/about/\n---\nAbout\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("README.md"), "# Not a post\n").unwrap();
+        std::fs::write(dir.join("broken.md"), "---\ntitle: [\n---\n").unwrap();
+        std::fs::write(dir.join(".git/ignored.md"), "---\ntitle: Hidden\n---\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "---\ntitle: Text\n---\n").unwrap();
+
+        let entries = load_entries(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let titles = entries.iter().map(|entry| entry.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(titles, vec!["Bundled", "First", "Linked"]);
+        let bundled = &entries[0];
+        assert_eq!(bundled.url, "/posts/bundle/");
+        assert_eq!(bundled.date, "2024-02-01T10:00:00+08:00");
+        assert_eq!(bundled.tags, vec!["a", "b"]);
+        assert_eq!(bundled.content, "Text\r\n");
+        let first = &entries[1];
+        assert_eq!(first.url, "/posts/hello-world/");
+        assert_eq!(first.subtitle.as_deref(), Some("One"));
+        assert_eq!(first.date, "2024-01-02T00:00:00+00:00");
+        assert_eq!(first.updated.as_deref(), Some("2024-01-03T08:30:00+00:00"));
+        assert_eq!(first.category.as_deref(), Some("notes"));
+        assert_eq!(first.tags, vec!["rust"]);
+        assert_eq!(first.content, "# Heading\n\nBody text.\n");
+        assert_eq!(entries[2].url, "https://example.com/about/");
+    }
+
+    #[test]
+    fn load_entries_parses_index_files() {
+        let dir = temp_dir("inkstone-local-index");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("search-index.json");
+        std::fs::write(
+            &path,
+            r#"[{"title":"Post","url":"/posts/post/","date":"2024-01-01T00:00:00Z","tags":[],"content":"Hi"}]"#,
+        )
+        .unwrap();
+
+        let entries = load_entries(&path).unwrap();
+        let missing = load_entries(&dir.join("missing.json"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].url, "/posts/post/");
+        assert!(matches!(missing, Err(LocalFeedError::Io(_, _))));
+    }
+}
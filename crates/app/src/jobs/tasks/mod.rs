@@ -1,9 +1,16 @@
 pub mod analytics_rollup;
 pub mod comments_sync;
+pub mod conditional_fetch;
 pub mod content_refresh;
 pub mod douban_crawl;
 pub mod feed_index;
+pub mod feed_watch;
 pub mod github_sync;
 pub mod kudos_cache;
+pub mod local_feed;
+pub mod search_generation;
+pub mod search_popularity;
+pub mod search_query_history;
 pub mod search_reindex;
+pub mod sections;
 pub mod valid_paths_refresh;
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{debug, info};
+
+use crate::jobs::JobError;
+use crate::jobs::scheduler;
+use crate::state::AppState;
+use inkstone_infra::search::{resolve_active_dir, SearchIndex, SearchIndexError};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// API and worker processes can share `INKSTONE_INDEX_DIR`; each follows
+// `CURRENT` so a generation activated by the other is served and written to.
+pub async fn follow(state: AppState) -> Result<(), JobError> {
+    scheduler::run_interval("search_generation", POLL_INTERVAL, move || {
+        let state = state.clone();
+        async move { reload_if_changed(&state).await.map(|_| ()) }
+    })
+    .await
+}
+
+pub async fn reload_if_changed(state: &AppState) -> Result<bool, JobError> {
+    if state.search.read().await.dir() == resolve_active_dir(&state.config.index_dir)? {
+        return Ok(false);
+    }
+    // A feed index run keeps the index it started with; let it finish first.
+    let _run = state.feed_index_runs.lock().await;
+    reload_locked(state).await
+}
+
+// Callers hold `feed_index_runs`. An active generation with an outdated schema is
+// left alone until the worker has rebuilt it.
+pub(crate) async fn reload_locked(state: &AppState) -> Result<bool, JobError> {
+    let active = resolve_active_dir(&state.config.index_dir)?;
+    if state.search.read().await.dir() == active {
+        return Ok(false);
+    }
+    let index = match SearchIndex::open_with_tokenizer(&active, &state.search_tokenizer) {
+        Ok(index) => index,
+        Err(SearchIndexError::OutdatedSchema(version)) => {
+            debug!(version, "active search index awaits a schema rebuild");
+            return Ok(false);
+        }
+        Err(err) => return Err(err.into()),
+    };
+    *state.search.write().await = Arc::new(index);
+    info!(index_dir = %active.display(), "following newly activated search index generation");
+    Ok(true)
+}
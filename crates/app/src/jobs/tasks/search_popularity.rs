@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use tracing::{info, warn};
+
+use crate::jobs::JobError;
+use crate::jobs::scheduler;
+use crate::state::AppState;
+use inkstone_infra::db::{fetch_path_popularity, PathPopularity};
+use inkstone_infra::search::PopularityScores;
+
+const PAGE_VIEW_WINDOW_DAYS: i64 = 30;
+const KUDOS_PAGE_VIEW_WEIGHT: f64 = 10.0;
+
+// Scores rank `sort=popular` searches, so this runs wherever the API is served.
+pub async fn run(state: AppState) -> Result<(), JobError> {
+    let interval = state.config.search_popularity_interval;
+    if state.db.is_none() || interval.as_secs() == 0 {
+        return Ok(());
+    }
+    scheduler::run_interval("search_popularity", interval, move || {
+        let state = state.clone();
+        async move {
+            if let Err(err) = refresh(&state).await {
+                warn!(error = %err, "search popularity refresh failed");
+            }
+            Ok(())
+        }
+    })
+    .await
+}
+
+pub async fn refresh(state: &AppState) -> Result<usize, JobError> {
+    {
+        let mut health = state.admin_health.lock().await;
+        health.search_popularity_last_run = Some(Utc::now());
+    }
+    let Some(pool) = state.db.as_ref() else {
+        warn!("search popularity refresh skipped: db not configured");
+        return Ok(0);
+    };
+    let since = Utc::now() - Duration::days(PAGE_VIEW_WINDOW_DAYS);
+    let rows = fetch_path_popularity(pool, since).await?;
+    let search = state.search.read().await.clone();
+    let urls = search.list_urls_by_source()?;
+    let primary = state.config.feed_sources.first().map(|source| source.name.as_str());
+    let scores = build_popularity_scores(&rows, &urls, primary);
+    let scored = scores.len();
+    *state.search_popularity.write().await = Arc::new(scores);
+    {
+        let mut health = state.admin_health.lock().await;
+        health.search_popularity_last_success = Some(Utc::now());
+    }
+    info!(paths = rows.len(), scored, "search popularity refreshed");
+    Ok(scored)
+}
+
+// Page views count for pages on their own site; kudos, and page views recorded
+// before events carried a site, only come from the primary source's pages.
+fn build_popularity_scores(
+    rows: &[PathPopularity],
+    urls_by_source: &HashMap<String, Vec<String>>,
+    primary: Option<&str>,
+) -> PopularityScores {
+    let mut signals = HashMap::<(Option<&str>, &str), f64>::new();
+    for row in rows {
+        let signal = row.kudos as f64 * KUDOS_PAGE_VIEW_WEIGHT + row.page_views as f64;
+        let key = (row.site.as_deref(), row.path.trim_end_matches('/'));
+        *signals.entry(key).or_default() += signal;
+    }
+    let mut raw = Vec::new();
+    for (source, urls) in urls_by_source {
+        let is_primary = primary == Some(source.as_str());
+        for url in urls {
+            let (host, path) = url_host_path(url);
+            let path = path.trim_end_matches('/');
+            let host = host.to_ascii_lowercase();
+            let mut signal = signals.get(&(Some(host.as_str()), path)).copied().unwrap_or(0.0);
+            if is_primary {
+                signal += signals.get(&(None, path)).copied().unwrap_or(0.0);
+            }
+            if signal > 0.0 {
+                raw.push((url.clone(), signal.ln_1p()));
+            }
+        }
+    }
+    let max = raw.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+    if max <= 0.0 {
+        return PopularityScores::new();
+    }
+    raw.into_iter()
+        .map(|(url, value)| (url, (value / max) as f32))
+        .collect()
+}
+
+// Host without port, as page view events record their site, and the path
+// without query or fragment.
+fn url_host_path(url: &str) -> (&str, &str) {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, "/"),
+    };
+    let authority = authority.split(['?', '#']).next().unwrap_or(authority);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host);
+    (host, path.split(['?', '#']).next().unwrap_or(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{build_popularity_scores, url_host_path};
+    use inkstone_infra::db::PathPopularity;
+
+    #[test]
+    fn url_host_path_strips_port_and_query() {
+        assert_eq!(
+            url_host_path("https://blog.example.com:8443/posts/a/?x=1"),
+            ("blog.example.com", "/posts/a/")
+        );
+        assert_eq!(url_host_path("https://blog.example.com"), ("blog.example.com", "/"));
+    }
+
+    #[test]
+    fn popularity_scores_are_normalized_per_url() {
+        let row = |site: Option<&str>, path: &str, kudos, page_views| PathPopularity {
+            site: site.map(str::to_string),
+            path: path.to_string(),
+            kudos,
+            page_views,
+        };
+        let rows = vec![
+            row(None, "/posts/a", 2, 0),
+            row(Some("blog.example.com"), "/posts/a", 0, 80),
+            row(Some("blog.example.com"), "/posts/b/", 0, 9),
+            row(Some("notes.example.com"), "/posts/c", 0, 500),
+        ];
+        let urls = HashMap::from([
+            (
+                "blog".to_string(),
+                vec![
+                    "https://blog.example.com/posts/a/".to_string(),
+                    "https://blog.example.com/posts/b/".to_string(),
+                    "https://blog.example.com/posts/c/".to_string(),
+                ],
+            ),
+            (
+                "notes".to_string(),
+                vec![
+                    "https://notes.example.com/posts/a/".to_string(),
+                    "https://Notes.example.com/posts/c".to_string(),
+                ],
+            ),
+        ]);
+        let scores = build_popularity_scores(&rows, &urls, Some("blog"));
+        assert_eq!(scores.len(), 3);
+        assert_eq!(scores["https://Notes.example.com/posts/c"], 1.0);
+        let a = scores["https://blog.example.com/posts/a/"];
+        let b = scores["https://blog.example.com/posts/b/"];
+        assert!(b > 0.0 && b < a && a < 1.0);
+        // Same path on another source shares neither page views nor kudos.
+        assert!(!scores.contains_key("https://notes.example.com/posts/a/"));
+    }
+}
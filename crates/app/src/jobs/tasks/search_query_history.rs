@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use crate::jobs::JobError;
+use crate::jobs::scheduler;
+use crate::state::AppState;
+use inkstone_infra::db::fetch_successful_queries;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+const HISTORY_DAYS: i64 = 90;
+const HISTORY_LIMIT: i64 = 5_000;
+
+// Spelling suggestions read past queries from memory instead of grouping
+// search events on every request.
+pub async fn run(state: AppState) -> Result<(), JobError> {
+    if state.db.is_none() {
+        return Ok(());
+    }
+    scheduler::run_interval("search_query_history", REFRESH_INTERVAL, move || {
+        let state = state.clone();
+        async move {
+            if let Err(err) = refresh(&state).await {
+                warn!(error = %err, "search query history refresh failed");
+            }
+            Ok(())
+        }
+    })
+    .await
+}
+
+pub async fn refresh(state: &AppState) -> Result<usize, JobError> {
+    let Some(pool) = state.db.as_ref() else {
+        return Ok(0);
+    };
+    let rows = fetch_successful_queries(pool, HISTORY_DAYS, HISTORY_LIMIT).await?;
+    let history = rows
+        .into_iter()
+        .map(|row| (row.query_norm, row.count))
+        .collect::<Vec<_>>();
+    let loaded = history.len();
+    *state.search_query_history.write().await = Arc::new(history);
+    info!(queries = loaded, "search query history refreshed");
+    Ok(loaded)
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use scraper::{ElementRef, Html};
+
+use crate::jobs::tasks::feed_index::{normalize_whitespace, BLOCK_ELEMENTS};
+use inkstone_core::domain::search::SearchSection;
+
+const HEADING_ELEMENTS: &[&str] = &["h1", "h2", "h3", "h4", "h5", "h6"];
+const FENCES: &[&str] = &["```", "~~~"];
+
+struct Draft {
+    heading: String,
+    id: Option<String>,
+    content: String,
+}
+
+// ATX headings outside fenced code. A trailing `{#id}` sets the anchor, as in
+// Hugo, Pandoc and kramdown; other anchors are slugs of the heading text.
+pub(crate) fn markdown_sections(text: &str) -> Vec<SearchSection> {
+    let mut drafts: Vec<Draft> = Vec::new();
+    let mut fence: Option<&str> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        match fence {
+            Some(marker) if trimmed.starts_with(marker) => fence = None,
+            Some(_) => {}
+            None => {
+                fence = FENCES.iter().copied().find(|marker| trimmed.starts_with(marker));
+                if fence.is_none()
+                    && let Some((heading, id)) = atx_heading(line)
+                {
+                    drafts.push(Draft {
+                        heading,
+                        id,
+                        content: String::new(),
+                    });
+                    continue;
+                }
+            }
+        }
+        if let Some(draft) = drafts.last_mut() {
+            draft.content.push_str(line);
+            draft.content.push('\n');
+        }
+    }
+    finish(drafts)
+}
+
+// `<h1>`..`<h6>` elements; their `id` attribute is the anchor when present.
+pub(crate) fn html_sections(html: &str) -> Vec<SearchSection> {
+    let fragment = Html::parse_fragment(html);
+    let mut drafts = Vec::new();
+    push_sections(fragment.root_element(), &mut drafts);
+    finish(drafts)
+}
+
+fn push_sections(element: ElementRef<'_>, drafts: &mut Vec<Draft>) {
+    for child in element.children() {
+        if let Some(text) = child.value().as_text() {
+            if let Some(draft) = drafts.last_mut() {
+                draft.content.push_str(text);
+            }
+        } else if let Some(child) = ElementRef::wrap(child) {
+            let name = child.value().name();
+            if matches!(name, "script" | "style" | "template") {
+                continue;
+            }
+            if HEADING_ELEMENTS.contains(&name) {
+                drafts.push(Draft {
+                    heading: normalize_whitespace(&child.text().collect::<String>()),
+                    id: child.value().id().map(str::to_string),
+                    content: String::new(),
+                });
+                continue;
+            }
+            let block = BLOCK_ELEMENTS.contains(&name);
+            if block && let Some(draft) = drafts.last_mut() {
+                draft.content.push(' ');
+            }
+            push_sections(child, drafts);
+            if block && let Some(draft) = drafts.last_mut() {
+                draft.content.push(' ');
+            }
+        }
+    }
+}
+
+fn atx_heading(line: &str) -> Option<(String, Option<String>)> {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() > 3 {
+        return None;
+    }
+    let level = rest.bytes().take_while(|byte| *byte == b'#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &rest[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let mut heading = rest.trim();
+    let unclosed = heading.trim_end_matches('#');
+    if unclosed.is_empty() || unclosed.ends_with([' ', '\t']) {
+        heading = unclosed.trim_end();
+    }
+    let (heading, id) = match heading
+        .strip_suffix('}')
+        .and_then(|value| value.rsplit_once("{#"))
+    {
+        Some((text, id)) if !id.is_empty() && !id.contains(char::is_whitespace) => {
+            (text.trim_end(), Some(id.to_string()))
+        }
+        _ => (heading, None),
+    };
+    let heading = plain_heading(heading);
+    (!heading.is_empty()).then_some((heading, id))
+}
+
+// Drops link targets and emphasis markers so `[Tokio](https://tokio.rs) *internals*`
+// reads and slugs as `Tokio internals`.
+fn plain_heading(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let link = after.find("](").and_then(|middle| {
+            let end = after[middle + 2..].find(')')?;
+            Some((middle, middle + 2 + end))
+        });
+        match link {
+            Some((middle, end)) => {
+                output.push_str(&after[..middle]);
+                rest = &after[end + 1..];
+            }
+            None => {
+                output.push('[');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    normalize_whitespace(&output.replace(['`', '*'], ""))
+}
+
+fn finish(drafts: Vec<Draft>) -> Vec<SearchSection> {
+    let mut used: HashMap<String, usize> = HashMap::new();
+    drafts
+        .into_iter()
+        .filter(|draft| !draft.heading.is_empty())
+        .map(|draft| {
+            let base = draft.id.unwrap_or_else(|| slugify(&draft.heading));
+            let count = used.entry(base.clone()).or_default();
+            // Repeated headings get `-1`, `-2`, ... like GitHub and Hugo.
+            let anchor = if *count == 0 {
+                base
+            } else {
+                format!("{base}-{count}")
+            };
+            *count += 1;
+            SearchSection {
+                anchor,
+                heading: draft.heading,
+                content: normalize_whitespace(&draft.content),
+            }
+        })
+        .collect()
+}
+
+// Lowercase letters and digits of any script, with runs of spaces and dashes
+// collapsed to one dash; other punctuation is dropped.
+fn slugify(heading: &str) -> String {
+    let mut slug = String::with_capacity(heading.len());
+    for ch in heading.chars().flat_map(char::to_lowercase) {
+        if ch.is_alphanumeric() || ch == '_' {
+            slug.push(ch);
+        } else if (ch.is_whitespace() || ch == '-') && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchors(sections: &[SearchSection]) -> Vec<&str> {
+        sections.iter().map(|section| section.anchor.as_str()).collect()
+    }
+
+    #[test]
+    fn markdown_sections_split_on_headings_outside_code() {
+        let text = "Intro text.\n\n\
+                    ## Getting started\n\nInstall it.\n\n\
+                    ```sh\n# not a heading\n```\n\n\
+                    ### [Tokio](https://tokio.rs) `runtime` ###\nDetails.\n\
+                    ## Getting started\nAgain.\n\
+                    ## 数据库 索引 {#db-index}\n中文\n\
+                    #hashtag\n    ## indented code\n";
+        let sections = markdown_sections(text);
+        assert_eq!(
+            anchors(&sections),
+            vec!["getting-started", "tokio-runtime", "getting-started-1", "db-index"]
+        );
+        assert_eq!(sections[0].heading, "Getting started");
+        assert_eq!(sections[0].content, "Install it. ```sh # not a heading ```");
+        assert_eq!(sections[1].heading, "Tokio runtime");
+        assert_eq!(sections[3].heading, "数据库 索引");
+        assert_eq!(sections[3].content, "中文 #hashtag ## indented code");
+        assert_eq!(slugify("C# & Rust: 1.0!"), "c-rust-10");
+        assert_eq!(slugify("数据库 索引"), "数据库-索引");
+        assert_eq!(slugify("?!"), "section");
+    }
+
+    #[test]
+    fn html_sections_use_heading_ids() {
+        let html = "<p>Intro</p><h2 id=\"setup\">Set <code>up</code></h2><p>Install</p>\
+                    <ul><li>one</li><li>two</li></ul><h3>Next steps</h3><p>Go</p>\
+                    <script>ignored()</script>";
+        let sections = html_sections(html);
+        assert_eq!(anchors(&sections), vec!["setup", "next-steps"]);
+        assert_eq!(sections[0].heading, "Set up");
+        assert_eq!(sections[0].content, "Install one two");
+        assert_eq!(sections[1].content, "Go");
+    }
+}
@@ -3,24 +3,47 @@ use std::collections::HashSet;
 use tracing::{info, warn};
 
 use crate::jobs::JobError;
+use crate::jobs::tasks::conditional_fetch::{self, FetchOutcome};
 use crate::state::AppState;
 
-pub async fn run(state: &AppState) -> Result<(), JobError> {
+#[derive(Debug)]
+pub struct PathsStats {
+    pub count: usize,
+    pub unchanged: bool,
+}
+
+pub async fn run(state: &AppState) -> Result<PathsStats, JobError> {
     let url = state.config.valid_paths_url.trim();
     if url.is_empty() {
         warn!("valid paths url not configured; skip refresh");
-        return Ok(());
+        return Ok(PathsStats {
+            count: 0,
+            unchanged: true,
+        });
     }
-    let response = state.http_client.get(url).send().await?.error_for_status()?;
-    let body = response.text().await?;
-    let paths = parse_valid_paths(&body);
+    // Validators may outlive the process in Postgres; an empty set always needs the body.
+    let current = state.valid_paths.read().await.len();
+    let fetched = match conditional_fetch::fetch(state, url, current > 0).await? {
+        FetchOutcome::Unchanged => {
+            return Ok(PathsStats {
+                count: current,
+                unchanged: true,
+            });
+        }
+        FetchOutcome::Changed(fetched) => fetched,
+    };
+    let paths = parse_valid_paths(&String::from_utf8_lossy(&fetched.body));
     let count = paths.len();
     {
         let mut guard = state.valid_paths.write().await;
         *guard = paths;
     }
+    conditional_fetch::commit(state, fetched).await;
     info!(count, "valid paths refreshed");
-    Ok(())
+    Ok(PathsStats {
+        count,
+        unchanged: false,
+    })
 }
 
 fn parse_valid_paths(input: &str) -> HashSet<String> {
@@ -48,7 +48,7 @@ impl KudosCache {
     pub fn has(&self, path: &str, interaction_id: &[u8]) -> bool {
         self.entries
             .get(path)
-            .map_or(false, |set| set.contains(interaction_id))
+            .is_some_and(|set| set.contains(interaction_id))
     }
 
     pub fn load_existing<I>(&mut self, items: I) -> usize
@@ -80,7 +80,7 @@ impl KudosCache {
             if self
                 .entries
                 .get(&path)
-                .map_or(false, |set| set.contains(&interaction_id))
+                .is_some_and(|set| set.contains(&interaction_id))
             {
                 self.pending.insert(PendingKudos { path, interaction_id });
             }
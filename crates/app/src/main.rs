@@ -4,6 +4,8 @@ mod http;
 mod jobs;
 mod kudos_cache;
 mod state;
+#[cfg(test)]
+mod test_support;
 mod wiring;
 
 use clap::Parser;
@@ -51,21 +53,19 @@ async fn main() -> Result<(), AppError> {
             "rebuild-schema requires worker mode".to_string(),
         ));
     }
-    if cli.rebuild_schema {
-        let index_dir = &config.index_dir;
-        if index_dir.exists() {
-            info!(
-                index_dir = %index_dir.display(),
-                "rebuild schema requested, deleting index dir"
-            );
-            std::fs::remove_dir_all(index_dir)?;
-        }
-    }
-    let state = wiring::build_state(config)?;
+    let state = wiring::build_state(config, cli.rebuild_schema)?;
     if let Some(pool) = state.db.as_ref() {
         run_migrations(pool).await?;
     }
 
+    if cli.mode.run_api() || cli.mode.run_worker() {
+        tokio::spawn(jobs::tasks::search_generation::follow(state.clone()));
+    }
+    if cli.mode.run_api() {
+        tokio::spawn(jobs::tasks::search_popularity::run(state.clone()));
+        tokio::spawn(jobs::tasks::search_query_history::run(state.clone()));
+    }
+
     let mut api_task = None;
     let mut worker_task = None;
 
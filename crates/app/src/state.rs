@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -8,18 +8,26 @@ use tokio::sync::{Mutex, RwLock};
 
 use crate::config::AppConfig;
 use crate::kudos_cache::KudosCache;
-use inkstone_infra::db::DbPool;
-use inkstone_infra::search::SearchIndex;
+use inkstone_infra::db::{DbPool, FetchValidatorsRecord};
+use inkstone_infra::search::{
+    PopularityScores, QueryHistory, SearchIndex, SearchTokenizer, SynonymMap,
+};
 
 #[derive(Clone)]
 pub struct AppState {
     pub config: Arc<AppConfig>,
-    pub search: Arc<SearchIndex>,
+    pub search: Arc<RwLock<Arc<SearchIndex>>>,
+    pub search_popularity: Arc<RwLock<Arc<PopularityScores>>>,
+    pub search_query_history: Arc<RwLock<Arc<QueryHistory>>>,
+    pub search_synonyms: Arc<RwLock<Arc<SynonymMap>>>,
+    pub search_tokenizer: Arc<SearchTokenizer>,
+    pub feed_index_runs: Arc<Mutex<()>>,
     pub http_client: Client,
     pub db: Option<DbPool>,
     pub valid_paths: Arc<RwLock<HashSet<String>>>,
     pub kudos_cache: Arc<RwLock<KudosCache>>,
     pub content_refresh_backoff: Arc<Mutex<ContentRefreshBackoff>>,
+    pub fetch_validators: Arc<Mutex<HashMap<String, FetchValidatorsRecord>>>,
     pub admin_health: Arc<Mutex<AdminHealthState>>,
 }
 
@@ -39,6 +47,10 @@ pub struct AdminHealthState {
     pub comments_sync_last_success: Option<DateTime<Utc>>,
     pub kudos_flush_last_run: Option<DateTime<Utc>>,
     pub kudos_flush_last_success: Option<DateTime<Utc>>,
+    pub search_popularity_last_run: Option<DateTime<Utc>>,
+    pub search_popularity_last_success: Option<DateTime<Utc>>,
+    pub feed_index_last_run: Option<DateTime<Utc>>,
+    pub feed_index_last_deleted: Option<usize>,
     pub webhook_content_last_received: Option<DateTime<Utc>>,
     pub webhook_discussions_last_received: Option<DateTime<Utc>>,
 }
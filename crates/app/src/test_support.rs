@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use inkstone_core::domain::search::SearchDocument;
+
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::env::temp_dir().join(format!("{name}-{nanos}"))
+}
+
+pub(crate) fn doc(id: &str, title: &str, content: &str) -> SearchDocument {
+    let now = chrono::Utc::now();
+    SearchDocument {
+        id: id.to_string(),
+        title: title.to_string(),
+        subtitle: None,
+        content: content.to_string(),
+        url: format!("https://example.com/posts/{id}"),
+        tags: Vec::new(),
+        category: None,
+        source: "blog".to_string(),
+        published_at: now,
+        updated_at: now,
+        checksum: "checksum".to_string(),
+        sections: Vec::new(),
+    }
+}
@@ -1,15 +1,21 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 
 use reqwest::Client;
 use thiserror::Error;
 use tokio::sync::{Mutex, RwLock};
+use tracing::warn;
 
 use crate::config::AppConfig;
 use crate::kudos_cache::KudosCache;
 use crate::state::{AdminHealthState, AppState, ContentRefreshBackoff};
 use inkstone_infra::db::{connect_lazy, DbPoolError};
-use inkstone_infra::search::{SearchIndex, SearchIndexError};
+use inkstone_infra::search::{
+    mark_generation_complete, new_generation_dir, resolve_active_dir, PopularityScores,
+    QueryHistory, SearchIndex, SearchIndexError, SearchTokenizer, SynonymError, SynonymMap,
+    TokenizerError,
+};
 
 #[derive(Debug, Error)]
 pub enum WiringError {
@@ -19,10 +25,24 @@ pub enum WiringError {
     HttpClient(#[from] reqwest::Error),
     #[error("db pool error: {0}")]
     DbPool(#[from] DbPoolError),
+    #[error("synonyms error: {0}")]
+    Synonyms(#[from] SynonymError),
+    #[error("tokenizer error: {0}")]
+    Tokenizer(#[from] TokenizerError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
-pub fn build_state(config: AppConfig) -> Result<AppState, WiringError> {
-    let search = SearchIndex::open_or_create(&config.index_dir)?;
+pub fn build_state(config: AppConfig, rebuild_schema: bool) -> Result<AppState, WiringError> {
+    let tokenizer = SearchTokenizer::load(
+        config.search_user_dict_path.as_deref(),
+        config.search_stop_words_path.as_deref(),
+    )?;
+    let search = open_search_index(&config.index_dir, &tokenizer, rebuild_schema)?;
+    let synonyms = match config.search_synonyms_path.as_deref() {
+        Some(path) => SynonymMap::load(path)?,
+        None => SynonymMap::default(),
+    };
     let client = Client::builder().timeout(config.request_timeout).build()?;
     let db = match config.database_url.as_deref() {
         Some(url) => Some(connect_lazy(url)?),
@@ -30,12 +50,43 @@ pub fn build_state(config: AppConfig) -> Result<AppState, WiringError> {
     };
     Ok(AppState {
         config: Arc::new(config),
-        search: Arc::new(search),
+        search: Arc::new(RwLock::new(Arc::new(search))),
+        search_popularity: Arc::new(RwLock::new(Arc::new(PopularityScores::new()))),
+        search_query_history: Arc::new(RwLock::new(Arc::new(QueryHistory::new()))),
+        search_synonyms: Arc::new(RwLock::new(Arc::new(synonyms))),
+        search_tokenizer: Arc::new(tokenizer),
+        feed_index_runs: Arc::new(Mutex::new(())),
         http_client: client,
         db,
         valid_paths: Arc::new(RwLock::new(HashSet::new())),
         kudos_cache: Arc::new(RwLock::new(KudosCache::default())),
         content_refresh_backoff: Arc::new(Mutex::new(ContentRefreshBackoff::default())),
+        fetch_validators: Arc::new(Mutex::new(HashMap::new())),
         admin_health: Arc::new(Mutex::new(AdminHealthState::default())),
     })
 }
+
+fn open_search_index(
+    base: &Path,
+    tokenizer: &SearchTokenizer,
+    rebuild_schema: bool,
+) -> Result<SearchIndex, WiringError> {
+    let dir = resolve_active_dir(base)?;
+    match SearchIndex::open_with_tokenizer(&dir, tokenizer) {
+        Ok(index) => Ok(index),
+        // The worker rebuilds an outdated index on its next feed index run.
+        Err(err) if rebuild_schema || matches!(err, SearchIndexError::OutdatedSchema(_)) => {
+            warn!(
+                error = %err,
+                index_dir = %dir.display(),
+                "active index unusable; serving an empty index until rebuild completes"
+            );
+            let dir = new_generation_dir(base);
+            let index = SearchIndex::open_with_tokenizer(&dir, tokenizer)?;
+            // Nothing writes to the stand-in, so pruning may remove it once replaced.
+            mark_generation_complete(&dir)?;
+            Ok(index)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
@@ -5,10 +5,120 @@ use crate::types::time_range::TimeRange;
 
 #[derive(Debug, Clone, Default)]
 pub struct SearchQuery {
-    pub keywords: Vec<String>,
     pub range: Option<TimeRange>,
     pub tags: Vec<String>,
     pub category: Option<String>,
+    pub source: Option<String>,
+    pub excluded_keywords: Vec<String>,
+    pub excluded_tags: Vec<String>,
+    pub excluded_categories: Vec<String>,
+    pub excluded_sources: Vec<String>,
+    // Keywords and phrases as typed, with their AND/OR structure.
+    pub expr: Option<KeywordExpr>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueryOperator {
+    #[default]
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeywordExpr {
+    Keyword(String),
+    Phrase(String),
+    And(Vec<KeywordExpr>),
+    Or(Vec<KeywordExpr>),
+}
+
+impl KeywordExpr {
+    pub fn and(mut items: Vec<KeywordExpr>) -> KeywordExpr {
+        if items.len() == 1 {
+            items.remove(0)
+        } else {
+            KeywordExpr::And(items)
+        }
+    }
+
+    pub fn or(mut items: Vec<KeywordExpr>) -> KeywordExpr {
+        if items.len() == 1 {
+            items.remove(0)
+        } else {
+            KeywordExpr::Or(items)
+        }
+    }
+
+    pub fn terms(&self) -> Vec<&str> {
+        let mut terms = Vec::new();
+        self.collect_terms(&mut terms, &|_| true);
+        terms
+    }
+
+    pub fn keywords(&self) -> Vec<&str> {
+        let mut keywords = Vec::new();
+        self.collect_terms(&mut keywords, &|item| matches!(item, KeywordExpr::Keyword(_)));
+        keywords
+    }
+
+    pub fn phrases(&self) -> Vec<&str> {
+        let mut phrases = Vec::new();
+        self.collect_terms(&mut phrases, &|item| matches!(item, KeywordExpr::Phrase(_)));
+        phrases
+    }
+
+    fn collect_terms<'a>(&'a self, terms: &mut Vec<&'a str>, keep: &dyn Fn(&KeywordExpr) -> bool) {
+        match self {
+            KeywordExpr::Keyword(value) | KeywordExpr::Phrase(value) => {
+                if keep(self) {
+                    terms.push(value);
+                }
+            }
+            KeywordExpr::And(items) | KeywordExpr::Or(items) => {
+                for item in items {
+                    item.collect_terms(terms, keep);
+                }
+            }
+        }
+    }
+
+    pub fn has_or(&self) -> bool {
+        match self {
+            KeywordExpr::Keyword(_) | KeywordExpr::Phrase(_) => false,
+            KeywordExpr::Or(_) => true,
+            KeywordExpr::And(items) => items.iter().any(KeywordExpr::has_or),
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn keyword_expr(&self) -> Option<KeywordExpr> {
+        self.expr.clone()
+    }
+
+    // Plain keywords of the expression in the order they were typed.
+    pub fn keywords(&self) -> Vec<&str> {
+        self.expr.as_ref().map(KeywordExpr::keywords).unwrap_or_default()
+    }
+
+    pub fn phrases(&self) -> Vec<&str> {
+        self.expr.as_ref().map(KeywordExpr::phrases).unwrap_or_default()
+    }
+
+    pub fn has_positive_terms(&self) -> bool {
+        self.expr.is_some()
+            || self.range.is_some()
+            || !self.tags.is_empty()
+            || self.category.is_some()
+            || self.source.is_some()
+    }
+
+    pub fn has_exclusions(&self) -> bool {
+        !self.excluded_keywords.is_empty()
+            || !self.excluded_tags.is_empty()
+            || !self.excluded_categories.is_empty()
+            || !self.excluded_sources.is_empty()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -20,9 +130,19 @@ pub struct SearchDocument {
     pub url: String,
     pub tags: Vec<String>,
     pub category: Option<String>,
+    pub source: String,
     pub published_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub checksum: String,
+    pub sections: Vec<SearchSection>,
+}
+
+// The text under one heading of a post, linked as `<post url>#<anchor>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchSection {
+    pub anchor: String,
+    pub heading: String,
+    pub content: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -34,12 +154,95 @@ pub struct SearchHit {
     pub url: String,
     pub tags: Vec<String>,
     pub category: Option<String>,
+    pub source: String,
     pub published_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<SearchHighlights>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<serde_json::Value>,
+    // Best-matching sections of this post, best first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sections: Vec<SectionHit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionHit {
+    pub heading: String,
+    pub anchor: String,
+    pub url: String,
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<HighlightFragment>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct SearchHighlights {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub title: Vec<HighlightFragment>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subtitle: Vec<HighlightFragment>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub content: Vec<HighlightFragment>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct HighlightFragment {
+    pub text: String,
+    // Half-open `[start, end)` offsets into `text`, in UTF-16 code units.
+    pub ranges: Vec<[usize; 2]>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct SearchResult {
     pub total: usize,
     pub hits: Vec<SearchHit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<SearchFacets>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    // Set when no exact match was found and typo-tolerant matching was used.
+    pub fuzzy: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FacetField {
+    Tags,
+    Category,
+}
+
+impl FacetField {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FacetField::Tags => "tags",
+            FacetField::Category => "category",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchFacets {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<FacetCount>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<Vec<FacetCount>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchSuggestions {
+    pub titles: Vec<TitleSuggestion>,
+    pub tags: Vec<String>,
+    pub categories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TitleSuggestion {
+    pub title: String,
+    pub url: String,
 }
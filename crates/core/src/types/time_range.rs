@@ -1,42 +1,114 @@
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, Utc};
 
 use crate::error::CoreError;
 
+// Which timestamp a range applies to; `Any` matches either.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateField {
+    #[default]
+    Any,
+    Published,
+    Updated,
+}
+
+impl DateField {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DateField::Any => "range",
+            DateField::Published => "published",
+            DateField::Updated => "updated",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TimeRange {
     pub start: Option<NaiveDate>,
     pub end: Option<NaiveDate>,
+    pub field: DateField,
+    // `last-<n><unit>` as written, so the window can be shown without today's dates.
+    pub relative: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Bound {
+    Start,
+    End,
 }
 
 impl TimeRange {
+    // Accepts `start~end` with either side open, a single period, or a
+    // relative `last-<n><d|w|m|y>` window ending today. Dates may be given as
+    // `YYYY`, `YYYY-MM` or `YYYY-MM-DD`; a period covers all of its days.
     pub fn parse(input: &str) -> Result<Self, CoreError> {
+        Self::parse_at(input, Utc::now().date_naive())
+    }
+
+    pub fn parse_at(input: &str, today: NaiveDate) -> Result<Self, CoreError> {
         let trimmed = input.trim();
+        let invalid = || CoreError::InvalidTimeRange(trimmed.to_string());
+        if let Some(window) = trimmed.strip_prefix("last-") {
+            let start = parse_window(window, today).ok_or_else(invalid)?;
+            return Ok(TimeRange {
+                start: Some(start),
+                end: None,
+                field: DateField::Any,
+                relative: Some(trimmed.to_string()),
+            });
+        }
         let Some((left, right)) = trimmed.split_once('~') else {
-            return Err(CoreError::InvalidTimeRange(trimmed.to_string()));
+            if trimmed.is_empty() {
+                return Err(invalid());
+            }
+            return Ok(TimeRange {
+                start: Some(parse_date(trimmed, Bound::Start)?),
+                end: Some(parse_date(trimmed, Bound::End)?),
+                field: DateField::Any,
+                relative: None,
+            });
         };
         let start = if left.trim().is_empty() {
             None
         } else {
-            Some(parse_date(left.trim())?)
+            Some(parse_date(left.trim(), Bound::Start)?)
         };
         let end = if right.trim().is_empty() {
             None
         } else {
-            Some(parse_date(right.trim())?)
+            Some(parse_date(right.trim(), Bound::End)?)
         };
         if start.is_none() && end.is_none() {
-            return Err(CoreError::InvalidTimeRange(trimmed.to_string()));
+            return Err(invalid());
         }
-        if let (Some(start), Some(end)) = (start, end) {
-            if start > end {
-                return Err(CoreError::InvalidTimeRange(trimmed.to_string()));
-            }
-            return Ok(TimeRange {
-                start: Some(start),
-                end: Some(end),
-            });
+        if let (Some(start), Some(end)) = (start, end)
+            && start > end
+        {
+            return Err(invalid());
         }
-        Ok(TimeRange { start, end })
+        Ok(TimeRange {
+            start,
+            end,
+            field: DateField::Any,
+            relative: None,
+        })
+    }
+
+    pub fn year(input: &str) -> Result<Self, CoreError> {
+        let trimmed = input.trim();
+        if trimmed.contains('-') {
+            return Err(CoreError::InvalidTimeRange(trimmed.to_string()));
+        }
+        Ok(TimeRange {
+            start: Some(parse_date(trimmed, Bound::Start)?),
+            end: Some(parse_date(trimmed, Bound::End)?),
+            field: DateField::Any,
+            relative: None,
+        })
+    }
+
+    pub fn with_field(mut self, field: DateField) -> Self {
+        self.field = field;
+        self
     }
 
     pub fn to_timestamp_bounds(&self) -> (Option<i64>, Option<i64>) {
@@ -52,15 +124,65 @@ impl TimeRange {
     }
 }
 
-fn parse_date(input: &str) -> Result<NaiveDate, CoreError> {
-    NaiveDate::parse_from_str(input, "%Y-%m-%d")
-        .map_err(|_| CoreError::InvalidTimeRange(input.to_string()))
+fn parse_date(input: &str, bound: Bound) -> Result<NaiveDate, CoreError> {
+    let invalid = || CoreError::InvalidTimeRange(input.to_string());
+    let parts = input.split('-').collect::<Vec<_>>();
+    let number = |value: &str, len: usize| -> Result<u32, CoreError> {
+        if value.len() != len || !value.chars().all(|ch| ch.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        value.parse().map_err(|_| invalid())
+    };
+    match parts.as_slice() {
+        [year] => {
+            let year = number(year, 4)? as i32;
+            let date = match bound {
+                Bound::Start => NaiveDate::from_ymd_opt(year, 1, 1),
+                Bound::End => NaiveDate::from_ymd_opt(year, 12, 31),
+            };
+            date.ok_or_else(invalid)
+        }
+        [year, month] => {
+            let first = NaiveDate::from_ymd_opt(number(year, 4)? as i32, number(month, 2)?, 1)
+                .ok_or_else(invalid)?;
+            match bound {
+                Bound::Start => Ok(first),
+                Bound::End => first
+                    .checked_add_months(Months::new(1))
+                    .and_then(|next| next.pred_opt())
+                    .ok_or_else(invalid),
+            }
+        }
+        [_, _, _] => NaiveDate::parse_from_str(input, "%Y-%m-%d").map_err(|_| invalid()),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_window(window: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let unit = window.chars().last()?;
+    let digits = &window[..window.len() - unit.len_utf8()];
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    let count = digits.parse::<u32>().ok().filter(|count| *count > 0)?;
+    match unit {
+        'd' => today.checked_sub_days(Days::new(u64::from(count))),
+        'w' => today.checked_sub_days(Days::new(u64::from(count) * 7)),
+        'm' => today.checked_sub_months(Months::new(count)),
+        'y' => today.checked_sub_months(Months::new(count.checked_mul(12)?)),
+        _ => None,
+    }
+    .filter(|date| date.year() > 0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
     #[test]
     fn parse_start_only_range() {
         let range = TimeRange::parse("2020-01-01~").unwrap();
@@ -91,4 +213,37 @@ mod tests {
     fn reject_inverted_range() {
         assert!(TimeRange::parse("2021-01-01~2020-01-01").is_err());
     }
+
+    #[test]
+    fn parse_years_and_months_cover_whole_periods() {
+        let range = TimeRange::parse("2023").unwrap();
+        assert_eq!((range.start, range.end), (Some(date(2023, 1, 1)), Some(date(2023, 12, 31))));
+        let range = TimeRange::parse("2024-02").unwrap();
+        assert_eq!((range.start, range.end), (Some(date(2024, 2, 1)), Some(date(2024, 2, 29))));
+        let range = TimeRange::parse("2023-05~2023-07").unwrap();
+        assert_eq!((range.start, range.end), (Some(date(2023, 5, 1)), Some(date(2023, 7, 31))));
+        let range = TimeRange::year("2022").unwrap();
+        assert_eq!(range.end, Some(date(2022, 12, 31)));
+        for input in ["2023-13", "2023-5", "23", "2023-05-01-02"] {
+            assert!(TimeRange::parse(input).is_err(), "{input}");
+        }
+        assert!(TimeRange::year("2023-05").is_err());
+    }
+
+    #[test]
+    fn parse_relative_windows_end_today() {
+        let today = date(2024, 3, 31);
+        let range = TimeRange::parse_at("last-90d", today).unwrap();
+        assert_eq!((range.start, range.end), (Some(date(2024, 1, 1)), None));
+        assert_eq!(range.relative.as_deref(), Some("last-90d"));
+        let range = TimeRange::parse_at("last-2w", today).unwrap();
+        assert_eq!(range.start, Some(date(2024, 3, 17)));
+        let range = TimeRange::parse_at("last-1m", today).unwrap();
+        assert_eq!(range.start, Some(date(2024, 2, 29)));
+        let range = TimeRange::parse_at("last-1y", today).unwrap();
+        assert_eq!(range.start, Some(date(2023, 3, 31)));
+        for input in ["last-0d", "last-d", "last-5x", "last-+5d", "last-99999999y"] {
+            assert!(TimeRange::parse_at(input, today).is_err(), "{input}");
+        }
+    }
 }
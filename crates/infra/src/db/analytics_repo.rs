@@ -84,6 +84,7 @@ pub async fn upsert_page_view(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn upsert_visitor(
     pool: &PgPool,
     site: &str,
@@ -0,0 +1,64 @@
+use sqlx::{PgPool, Row};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FetchValidatorsRepoError {
+    #[error("sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchValidatorsRecord {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body_hash: String,
+}
+
+pub async fn load_fetch_validators(
+    pool: &PgPool,
+    url: &str,
+) -> Result<Option<FetchValidatorsRecord>, FetchValidatorsRepoError> {
+    let row = sqlx::query(
+        r#"
+        SELECT etag, last_modified, body_hash
+        FROM fetch_validators
+        WHERE url = $1
+        "#,
+    )
+    .bind(url)
+    .fetch_optional(pool)
+    .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    Ok(Some(FetchValidatorsRecord {
+        etag: row.try_get("etag")?,
+        last_modified: row.try_get("last_modified")?,
+        body_hash: row.try_get("body_hash")?,
+    }))
+}
+
+pub async fn upsert_fetch_validators(
+    pool: &PgPool,
+    url: &str,
+    record: &FetchValidatorsRecord,
+) -> Result<(), FetchValidatorsRepoError> {
+    sqlx::query(
+        r#"
+        INSERT INTO fetch_validators (url, etag, last_modified, body_hash, updated_at)
+        VALUES ($1, $2, $3, $4, now())
+        ON CONFLICT (url) DO UPDATE
+        SET etag = EXCLUDED.etag,
+            last_modified = EXCLUDED.last_modified,
+            body_hash = EXCLUDED.body_hash,
+            updated_at = now()
+        "#,
+    )
+    .bind(url)
+    .bind(record.etag.as_deref())
+    .bind(record.last_modified.as_deref())
+    .bind(&record.body_hash)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
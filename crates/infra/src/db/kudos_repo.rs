@@ -26,6 +26,14 @@ pub struct KudosPathCount {
     pub count: i64,
 }
 
+#[derive(Debug, Clone)]
+pub struct PathPopularity {
+    pub site: Option<String>,
+    pub path: String,
+    pub kudos: i64,
+    pub page_views: i64,
+}
+
 #[derive(Debug, Clone)]
 pub struct KudosRecentPath {
     pub path: String,
@@ -144,6 +152,41 @@ pub async fn fetch_kudos_top_paths(
     Ok(items)
 }
 
+pub async fn fetch_path_popularity(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+) -> Result<Vec<PathPopularity>, KudosRepoError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT site, path, SUM(kudos)::BIGINT AS kudos, SUM(page_views)::BIGINT AS page_views
+        FROM (
+            SELECT NULL::TEXT AS site, path, COUNT(*) AS kudos, 0::BIGINT AS page_views
+            FROM kudos
+            GROUP BY path
+            UNION ALL
+            SELECT site, path, 0::BIGINT AS kudos, COUNT(*) AS page_views
+            FROM pulse_events
+            WHERE ts >= $1 AND path IS NOT NULL
+            GROUP BY site, path
+        ) AS signals
+        GROUP BY site, path
+        "#,
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    let mut items = Vec::with_capacity(rows.len());
+    for row in rows {
+        items.push(PathPopularity {
+            site: row.try_get("site")?,
+            path: row.try_get("path")?,
+            kudos: row.try_get("kudos")?,
+            page_views: row.try_get("page_views")?,
+        });
+    }
+    Ok(items)
+}
+
 pub async fn count_recent_kudos(
     pool: &PgPool,
     since: DateTime<Utc>,
@@ -1,6 +1,7 @@
 pub mod analytics_repo;
 pub mod comments_repo;
 pub mod douban_repo;
+pub mod fetch_validators_repo;
 pub mod kudos_repo;
 pub mod likes_repo;
 pub mod migrations;
@@ -23,10 +24,14 @@ pub use douban_repo::{
     fetch_recent_douban_items, insert_douban_items, upsert_douban_items, DoubanItemRecord,
     DoubanMarkRecord, DoubanOverview, DoubanRecentItem, DoubanRepoError, DoubanTypeCount,
 };
+pub use fetch_validators_repo::{
+    load_fetch_validators, upsert_fetch_validators, FetchValidatorsRecord,
+    FetchValidatorsRepoError,
+};
 pub use kudos_repo::{
     count_kudos, count_recent_kudos, fetch_kudos_overview, fetch_kudos_top_paths,
-    fetch_recent_kudos_paths, has_kudos, insert_kudos, load_all_kudos, KudosEntry, KudosOverview,
-    KudosPathCount, KudosRecentPath, KudosRepoError,
+    fetch_path_popularity, fetch_recent_kudos_paths, has_kudos, insert_kudos, load_all_kudos,
+    KudosEntry, KudosOverview, KudosPathCount, KudosRecentPath, KudosRepoError, PathPopularity,
 };
 pub use pulse_admin_repo::{
     fetch_active_country_counts, fetch_active_device_counts, fetch_active_minute_uv,
@@ -37,11 +42,13 @@ pub use pulse_admin_repo::{
     PulseSiteOverview, PulseTopPath, PulseTotals,
 };
 pub use search_events_repo::{
-    fetch_filter_usage, fetch_keyword_usage, fetch_recent_search_query, fetch_search_daily,
-    fetch_search_summary, fetch_top_categories, fetch_top_queries, fetch_top_tags,
-    insert_search_event, SearchDailyRow, SearchDimCount as SearchDimCountRow, SearchEvent,
-    SearchEventsRepoError, SearchFilterUsage, SearchKeywordUsage, SearchSummaryRow,
-    SearchTopQueryRow,
+    fetch_abandoned_queries, fetch_click_summary, fetch_filter_usage, fetch_keyword_usage,
+    fetch_query_click_through, fetch_recent_search_query, fetch_search_daily,
+    fetch_search_summary, fetch_successful_queries, fetch_top_categories, fetch_top_queries,
+    fetch_top_tags, fetch_zero_result_queries, has_recent_search, insert_search_event,
+    SearchClickSummaryRow, SearchDailyRow, SearchDimCount as SearchDimCountRow, SearchEvent,
+    SearchEventsRepoError, SearchFilterUsage, SearchKeywordUsage, SearchQueryClicksRow,
+    SearchQueryCount, SearchSummaryRow, SearchTopQueryRow, SearchZeroResultRow,
 };
 pub use migrations::run_migrations;
 pub use pool::{connect_lazy, DbPool, DbPoolError};
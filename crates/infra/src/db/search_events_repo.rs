@@ -22,6 +22,9 @@ pub struct SearchEvent {
     pub search_user_hash: Option<String>,
     pub result_total: i32,
     pub elapsed_ms: i32,
+    pub suggestion: Option<String>,
+    pub clicked_url: Option<String>,
+    pub click_rank: Option<i32>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -68,6 +71,39 @@ pub struct SearchKeywordUsage {
     pub count: i64,
 }
 
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SearchQueryCount {
+    pub query_norm: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SearchClickSummaryRow {
+    pub searches: i64,
+    pub clicked_searches: i64,
+    pub clicks: i64,
+    pub mean_rank: Option<f64>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SearchQueryClicksRow {
+    pub query_norm: String,
+    pub searches: i64,
+    pub with_results: i64,
+    pub clicked_searches: i64,
+    pub abandoned: i64,
+    pub clicks: i64,
+    pub mean_rank: Option<f64>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SearchZeroResultRow {
+    pub query_norm: String,
+    pub suggestion: Option<String>,
+    pub count: i64,
+    pub resolved: i64,
+}
+
 pub async fn insert_search_event(
     pool: &PgPool,
     event: &SearchEvent,
@@ -86,9 +122,12 @@ pub async fn insert_search_event(
             kind,
             search_user_hash,
             result_total,
-            elapsed_ms
+            elapsed_ms,
+            suggestion,
+            clicked_url,
+            click_rank
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
         "#,
     )
     .bind(&event.query_raw)
@@ -103,6 +142,9 @@ pub async fn insert_search_event(
     .bind(event.search_user_hash.as_deref())
     .bind(event.result_total)
     .bind(event.elapsed_ms)
+    .bind(event.suggestion.as_deref())
+    .bind(event.clicked_url.as_deref())
+    .bind(event.click_rank)
     .execute(pool)
     .await?;
     Ok(())
@@ -131,6 +173,59 @@ pub async fn fetch_recent_search_query(
     Ok(query_norm)
 }
 
+// The most frequent queries that found something, for spelling suggestions.
+pub async fn fetch_successful_queries(
+    pool: &PgPool,
+    within_days: i64,
+    limit: i64,
+) -> Result<Vec<SearchQueryCount>, SearchEventsRepoError> {
+    let rows = sqlx::query_as::<_, SearchQueryCount>(
+        r#"
+        SELECT
+            query_norm,
+            COUNT(*)::bigint AS count
+        FROM search_events
+        WHERE kind = 'search'
+          AND result_total > 0
+          AND ts >= NOW() - ($1 * interval '1 day')
+        GROUP BY query_norm
+        ORDER BY count DESC, query_norm
+        LIMIT $2
+        "#,
+    )
+    .bind(within_days)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn has_recent_search(
+    pool: &PgPool,
+    search_user_hash: &str,
+    query_norm: &str,
+    within_secs: i64,
+) -> Result<bool, SearchEventsRepoError> {
+    let found: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM search_events
+            WHERE kind = 'search'
+              AND search_user_hash = $1
+              AND query_norm = $2
+              AND ts >= NOW() - ($3 * interval '1 second')
+        )
+        "#,
+    )
+    .bind(search_user_hash)
+    .bind(query_norm)
+    .bind(within_secs)
+    .fetch_one(pool)
+    .await?;
+    Ok(found)
+}
+
 pub async fn fetch_search_summary(
     pool: &PgPool,
     from: NaiveDate,
@@ -323,3 +418,184 @@ pub async fn fetch_keyword_usage(
     .await?;
     Ok(rows)
 }
+
+// Zero-result queries with the suggestion they were shown. A suggestion counts as
+// resolved when the same visitor searched for it within 30 minutes and got hits.
+pub async fn fetch_zero_result_queries(
+    pool: &PgPool,
+    from: NaiveDate,
+    to: NaiveDate,
+    limit: i64,
+) -> Result<Vec<SearchZeroResultRow>, SearchEventsRepoError> {
+    let rows = sqlx::query_as::<_, SearchZeroResultRow>(
+        r#"
+        SELECT
+            e.query_norm,
+            e.suggestion,
+            COUNT(*)::bigint AS count,
+            COUNT(*) FILTER (
+                WHERE EXISTS (
+                    SELECT 1
+                    FROM search_events f
+                    WHERE f.kind = 'search'
+                      AND f.search_user_hash = e.search_user_hash
+                      AND f.query_norm = e.suggestion
+                      AND f.result_total > 0
+                      AND f.ts > e.ts
+                      AND f.ts <= e.ts + interval '30 minutes'
+                )
+            )::bigint AS resolved
+        FROM search_events e
+        WHERE e.day BETWEEN $1 AND $2
+          AND e.kind = 'search'
+          AND e.result_total = 0
+        GROUP BY e.query_norm, e.suggestion
+        ORDER BY count DESC, e.query_norm
+        LIMIT $3
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+// Click-through covers searches that carry a visitor hash, since clicks can only
+// be tied to a search through it. A search counts as clicked when the visitor
+// clicked a result for the same query within 30 minutes.
+pub async fn fetch_click_summary(
+    pool: &PgPool,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<SearchClickSummaryRow, SearchEventsRepoError> {
+    let row = sqlx::query_as::<_, SearchClickSummaryRow>(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE s.kind = 'search')::bigint AS searches,
+            COUNT(*) FILTER (
+                WHERE s.kind = 'search'
+                  AND EXISTS (
+                      SELECT 1
+                      FROM search_events c
+                      WHERE c.kind = 'click'
+                        AND c.search_user_hash = s.search_user_hash
+                        AND c.query_norm = s.query_norm
+                        AND c.ts >= s.ts
+                        AND c.ts <= s.ts + interval '30 minutes'
+                  )
+            )::bigint AS clicked_searches,
+            COUNT(*) FILTER (WHERE s.kind = 'click')::bigint AS clicks,
+            (AVG(s.click_rank) FILTER (WHERE s.kind = 'click'))::double precision AS mean_rank
+        FROM search_events s
+        WHERE s.day BETWEEN $1 AND $2
+          AND s.kind IN ('search', 'click')
+          AND s.search_user_hash IS NOT NULL
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+// Per-query search and click counts for days $1 to $2. A search counts as
+// clicked when the same visitor clicked a hit for it within 30 minutes.
+const QUERY_CLICK_STATS_SQL: &str = r#"
+        WITH searches AS (
+            SELECT
+                s.query_norm,
+                s.result_total,
+                EXISTS (
+                    SELECT 1
+                    FROM search_events c
+                    WHERE c.kind = 'click'
+                      AND c.search_user_hash = s.search_user_hash
+                      AND c.query_norm = s.query_norm
+                      AND c.ts >= s.ts
+                      AND c.ts <= s.ts + interval '30 minutes'
+                ) AS clicked
+            FROM search_events s
+            WHERE s.day BETWEEN $1 AND $2
+              AND s.kind = 'search'
+              AND s.search_user_hash IS NOT NULL
+        ),
+        clicks AS (
+            SELECT
+                query_norm,
+                COUNT(*)::bigint AS clicks,
+                AVG(click_rank)::double precision AS mean_rank
+            FROM search_events
+            WHERE day BETWEEN $1 AND $2
+              AND kind = 'click'
+            GROUP BY query_norm
+        )
+        SELECT
+            s.query_norm,
+            COUNT(*)::bigint AS searches,
+            COUNT(*) FILTER (WHERE s.result_total > 0)::bigint AS with_results,
+            COUNT(*) FILTER (WHERE s.clicked)::bigint AS clicked_searches,
+            COUNT(*) FILTER (WHERE s.result_total > 0 AND NOT s.clicked)::bigint AS abandoned,
+            COALESCE(MAX(c.clicks), 0)::bigint AS clicks,
+            MAX(c.mean_rank) AS mean_rank
+        FROM searches s
+        LEFT JOIN clicks c ON c.query_norm = s.query_norm
+        GROUP BY s.query_norm
+"#;
+
+pub async fn fetch_query_click_through(
+    pool: &PgPool,
+    from: NaiveDate,
+    to: NaiveDate,
+    limit: i64,
+) -> Result<Vec<SearchQueryClicksRow>, SearchEventsRepoError> {
+    let sql = format!(
+        r#"
+        SELECT *
+        FROM ({QUERY_CLICK_STATS_SQL}) AS stats
+        ORDER BY searches DESC, query_norm
+        LIMIT $3
+        "#
+    );
+    let rows = sqlx::query_as::<_, SearchQueryClicksRow>(&sql)
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+// Queries whose results were most often left without a click, among queries
+// that found something at least `min_searches` times.
+pub async fn fetch_abandoned_queries(
+    pool: &PgPool,
+    from: NaiveDate,
+    to: NaiveDate,
+    min_searches: i64,
+    limit: i64,
+) -> Result<Vec<SearchQueryClicksRow>, SearchEventsRepoError> {
+    let sql = format!(
+        r#"
+        SELECT *
+        FROM ({QUERY_CLICK_STATS_SQL}) AS stats
+        WHERE with_results >= $3
+          AND abandoned > 0
+        ORDER BY
+            abandoned::double precision / with_results DESC,
+            abandoned DESC,
+            query_norm
+        LIMIT $4
+        "#
+    );
+    let rows = sqlx::query_as::<_, SearchQueryClicksRow>(&sql)
+        .bind(from)
+        .bind(to)
+        .bind(min_searches)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
@@ -0,0 +1,103 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use tantivy::DocAddress;
+
+const CURSOR_VERSION: &str = "v2";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CursorKey {
+    Score(f32),
+    Updated(i64),
+}
+
+// Search-after position: the sort key and address of the last hit of a page,
+// pinned to the index instance and searcher generation that produced it, plus
+// the hit's document id to find it again once that snapshot is released.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchCursor {
+    pub index_id: u64,
+    pub generation: u64,
+    pub key: CursorKey,
+    pub segment_ord: u32,
+    pub doc_id: u32,
+    pub id: String,
+}
+
+impl SearchCursor {
+    pub fn address(&self) -> DocAddress {
+        DocAddress::new(self.segment_ord, self.doc_id)
+    }
+
+    pub fn encode(&self) -> String {
+        let key = match self.key {
+            CursorKey::Score(score) => format!("s{:08x}", score.to_bits()),
+            CursorKey::Updated(updated) => format!("u{updated}"),
+        };
+        let raw = format!(
+            "{CURSOR_VERSION}.{:x}.{:x}.{key}.{:x}.{:x}.{}",
+            self.index_id, self.generation, self.segment_ord, self.doc_id, self.id
+        );
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(value.trim().as_bytes()).ok()?;
+        let raw = String::from_utf8(bytes).ok()?;
+        // Ids may contain dots, so the id takes the rest of the value.
+        let mut parts = raw.splitn(7, '.');
+        if parts.next()? != CURSOR_VERSION {
+            return None;
+        }
+        let index_id = u64::from_str_radix(parts.next()?, 16).ok()?;
+        let generation = u64::from_str_radix(parts.next()?, 16).ok()?;
+        let key = parts.next()?;
+        let key = if let Some(bits) = key.strip_prefix('s') {
+            let score = f32::from_bits(u32::from_str_radix(bits, 16).ok()?);
+            if !score.is_finite() {
+                return None;
+            }
+            CursorKey::Score(score)
+        } else if let Some(updated) = key.strip_prefix('u') {
+            CursorKey::Updated(updated.parse().ok()?)
+        } else {
+            return None;
+        };
+        let segment_ord = u32::from_str_radix(parts.next()?, 16).ok()?;
+        let doc_id = u32::from_str_radix(parts.next()?, 16).ok()?;
+        let id = parts.next().filter(|id| !id.is_empty())?.to_string();
+        Some(Self {
+            index_id,
+            generation,
+            key,
+            segment_ord,
+            doc_id,
+            id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CursorKey, SearchCursor};
+
+    #[test]
+    fn cursor_round_trips() {
+        for key in [CursorKey::Score(1.25), CursorKey::Updated(-1_700_000_000)] {
+            let cursor = SearchCursor {
+                index_id: 42,
+                generation: 7,
+                key,
+                segment_ord: 3,
+                doc_id: 1024,
+                id: "https://example.com/posts/v1.2".to_string(),
+            };
+            assert_eq!(SearchCursor::decode(&cursor.encode()), Some(cursor));
+        }
+    }
+
+    #[test]
+    fn cursor_rejects_garbage() {
+        assert_eq!(SearchCursor::decode("not-a-cursor"), None);
+        assert_eq!(SearchCursor::decode(""), None);
+    }
+}
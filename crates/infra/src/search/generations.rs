@@ -0,0 +1,232 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use chrono::Utc;
+
+const CURRENT_FILE: &str = "CURRENT";
+const GENERATION_PREFIX: &str = "gen-";
+const RETIRED_FILE: &str = "RETIRED";
+const COMPLETE_FILE: &str = "COMPLETE";
+const LEGACY_META_FILE: &str = "meta.json";
+
+// `base/CURRENT` names the active generation; indexes created directly in
+// `base` (before generations existed) stay active until the next rebuild.
+pub fn resolve_active_dir(base: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(base)?;
+    if let Some(name) = current_name(base)? {
+        return Ok(base.join(name));
+    }
+    if base.join(LEGACY_META_FILE).exists() {
+        return Ok(base.to_path_buf());
+    }
+    let dir = new_generation_dir(base);
+    fs::create_dir_all(&dir)?;
+    mark_generation_complete(&dir)?;
+    activate_generation(base, &dir)?;
+    Ok(dir)
+}
+
+// Generations without this marker may still be written to by another process,
+// so pruning never touches them.
+pub fn mark_generation_complete(dir: &Path) -> io::Result<()> {
+    fs::write(dir.join(COMPLETE_FILE), "")
+}
+
+pub fn new_generation_dir(base: &Path) -> PathBuf {
+    let stamp = Utc::now().format("%Y%m%d%H%M%S%6f");
+    base.join(format!("{GENERATION_PREFIX}{stamp}"))
+}
+
+// The generation being replaced gets a `RETIRED` marker, so pruning can tell
+// how long ago other processes sharing `base` were told to move off it; an
+// index created directly in `base` is marked in `base` itself.
+pub fn activate_generation(base: &Path, dir: &Path) -> io::Result<()> {
+    let name = dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid generation dir"))?;
+    let previous = current_name(base)?;
+    let tmp = base.join(format!("{CURRENT_FILE}.tmp"));
+    fs::write(&tmp, name)?;
+    fs::rename(&tmp, base.join(CURRENT_FILE))?;
+    match previous {
+        Some(previous) if previous != name => {
+            let previous = base.join(previous);
+            if previous.is_dir() {
+                fs::write(previous.join(RETIRED_FILE), "")?;
+            }
+        }
+        Some(_) => {}
+        None if base.join(LEGACY_META_FILE).exists() => {
+            fs::write(base.join(RETIRED_FILE), "")?;
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+// Removes completed generations other than the active one and `keep` once they
+// have been retired (or, if never activated, left untouched) for at least
+// `retention`, along with a legacy index in `base` itself.
+pub fn prune_generations(base: &Path, keep: &[&Path], retention: Duration) -> io::Result<usize> {
+    let current = current_name(base)?;
+    let mut removed = usize::from(prune_legacy_index(base, retention)?);
+    for entry in fs::read_dir(base)? {
+        let entry = entry?;
+        let path = entry.path();
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if !name.starts_with(GENERATION_PREFIX) || !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if keep.contains(&path.as_path()) || current.as_deref() == Some(name.as_str()) {
+            continue;
+        }
+        // Retired generations were complete when they were activated.
+        if !path.join(COMPLETE_FILE).exists() && !path.join(RETIRED_FILE).exists() {
+            continue;
+        }
+        if idle_for(&path)? < retention {
+            continue;
+        }
+        fs::remove_dir_all(&path)?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+// Everything in `base` but the generations and `CURRENT` belongs to the index
+// that was created there before generations existed.
+fn prune_legacy_index(base: &Path, retention: Duration) -> io::Result<bool> {
+    if current_name(base)?.is_none() || !base.join(LEGACY_META_FILE).exists() {
+        return Ok(false);
+    }
+    if idle_for(base)? < retention {
+        return Ok(false);
+    }
+    for entry in fs::read_dir(base)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with(GENERATION_PREFIX)
+            || name.starts_with(CURRENT_FILE)
+            || name == RETIRED_FILE
+        {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(entry.path())?;
+        } else {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    match fs::remove_file(base.join(RETIRED_FILE)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(true),
+    }
+}
+
+fn current_name(base: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(base.join(CURRENT_FILE)) {
+        Ok(name) => {
+            let name = name.trim();
+            Ok((!name.is_empty()).then(|| name.to_string()))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn idle_for(dir: &Path) -> io::Result<Duration> {
+    let marker = dir.join(RETIRED_FILE);
+    let metadata = if marker.exists() {
+        fs::metadata(marker)?
+    } else {
+        fs::metadata(dir)?
+    };
+    Ok(SystemTime::now()
+        .duration_since(metadata.modified()?)
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::test_support::temp_dir;
+
+    #[test]
+    fn fresh_base_gets_activated_generation() {
+        let base = temp_dir("inkstone-gen-fresh");
+        let dir = resolve_active_dir(&base).unwrap();
+        assert!(dir.starts_with(&base));
+        assert_ne!(dir, base);
+        assert_eq!(resolve_active_dir(&base).unwrap(), dir);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn legacy_index_in_base_stays_active() {
+        let base = temp_dir("inkstone-gen-legacy");
+        fs::create_dir_all(&base).unwrap();
+        fs::write(base.join("meta.json"), "{}").unwrap();
+        assert_eq!(resolve_active_dir(&base).unwrap(), base);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn activate_and_prune_switch_generations() {
+        let base = temp_dir("inkstone-gen-switch");
+        let old = resolve_active_dir(&base).unwrap();
+        let stale = base.join("gen-stale");
+        fs::create_dir_all(&stale).unwrap();
+        mark_generation_complete(&stale).unwrap();
+        let building = base.join("gen-building");
+        fs::create_dir_all(&building).unwrap();
+        let new = new_generation_dir(&base);
+        fs::create_dir_all(&new).unwrap();
+
+        activate_generation(&base, &new).unwrap();
+        assert_eq!(resolve_active_dir(&base).unwrap(), new);
+        assert!(old.join(RETIRED_FILE).exists());
+        assert!(!new.join(RETIRED_FILE).exists());
+
+        let retention = Duration::from_secs(600);
+        assert_eq!(prune_generations(&base, &[], retention).unwrap(), 0);
+        let removed = prune_generations(&base, &[&old], Duration::ZERO).unwrap();
+        assert_eq!(removed, 1);
+        assert!(!stale.exists());
+        assert!(building.exists());
+        assert!(old.exists());
+        assert!(new.exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn legacy_index_is_pruned_once_a_generation_is_active() {
+        let base = temp_dir("inkstone-gen-legacy-prune");
+        fs::create_dir_all(base.join("sections")).unwrap();
+        fs::write(base.join(LEGACY_META_FILE), "{}").unwrap();
+        fs::write(base.join("segment.idx"), "").unwrap();
+        let new = new_generation_dir(&base);
+        fs::create_dir_all(&new).unwrap();
+        mark_generation_complete(&new).unwrap();
+
+        activate_generation(&base, &new).unwrap();
+        assert!(base.join(RETIRED_FILE).exists());
+        let retention = Duration::from_secs(600);
+        assert_eq!(prune_generations(&base, &[], retention).unwrap(), 0);
+        assert_eq!(prune_generations(&base, &[], Duration::ZERO).unwrap(), 1);
+        let mut left = fs::read_dir(&base)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        left.sort();
+        let new_name = new.file_name().unwrap().to_string_lossy().into_owned();
+        assert_eq!(left, vec![CURRENT_FILE.to_string(), new_name]);
+        assert_eq!(resolve_active_dir(&base).unwrap(), new);
+        let _ = fs::remove_dir_all(&base);
+    }
+}
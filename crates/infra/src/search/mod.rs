@@ -1,17 +1,102 @@
+pub mod cursor;
+pub mod generations;
 pub mod query_parser;
+pub mod spelling;
+pub mod synonyms;
 pub mod tantivy_index;
+#[cfg(test)]
+mod test_support;
+pub mod tokenizer;
 
-pub use query_parser::{parse_query, QueryParseError};
-pub use tantivy_index::{SearchIndex, SearchIndexError, SearchIndexStats};
+pub use cursor::{CursorKey, SearchCursor};
+pub use generations::{
+    activate_generation, mark_generation_complete, new_generation_dir, prune_generations,
+    resolve_active_dir,
+};
+pub use query_parser::{parse_query, parse_query_with_operator, QueryParseError};
+pub use spelling::{closest_query, edit_distance, suggestion_distance};
+pub use synonyms::{SynonymError, SynonymMap};
+pub use tantivy_index::{DocumentRef, SearchIndex, SearchIndexError, SearchIndexStats};
+pub use tokenizer::{SearchTokenizer, TokenizerError};
 
-#[derive(Debug, Clone, Copy)]
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use inkstone_core::domain::search::FacetField;
+
+// Post URL -> popularity in [0, 1], used by `SearchSort::Popular`.
+pub type PopularityScores = HashMap<String, f32>;
+
+// Source name -> score multiplier; sources left out keep their score.
+pub type SourceWeights = HashMap<String, f32>;
+
+// Past successful queries with how often they ran, see `closest_query`.
+pub type QueryHistory = Vec<(String, i64)>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum SearchSort {
+    #[default]
     Relevance,
     Latest,
+    Smart,
+    Popular,
 }
 
-impl Default for SearchSort {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldBoosts {
+    pub title: f32,
+    pub subtitle: f32,
+    pub content: f32,
+    pub tags: f32,
+    pub category: f32,
+}
+
+impl Default for FieldBoosts {
     fn default() -> Self {
-        Self::Relevance
+        Self {
+            title: 3.0,
+            subtitle: 2.0,
+            content: 1.0,
+            tags: 2.0,
+            category: 1.5,
+        }
     }
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HighlightMode {
+    #[default]
+    Html,
+    Ranges,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnippetOptions {
+    pub max_chars: usize,
+    pub fragments: usize,
+}
+
+impl Default for SnippetOptions {
+    fn default() -> Self {
+        Self {
+            max_chars: 240,
+            fragments: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    pub limit: usize,
+    pub offset: usize,
+    pub sort: SearchSort,
+    pub facets: Vec<FacetField>,
+    pub popularity: Option<Arc<PopularityScores>>,
+    pub source_weights: Option<Arc<SourceWeights>>,
+    pub boosts: FieldBoosts,
+    pub explain: bool,
+    pub cursor: Option<SearchCursor>,
+    pub highlight: HighlightMode,
+    pub snippets: SnippetOptions,
+    pub synonyms: Option<Arc<SynonymMap>>,
+}
@@ -1,5 +1,5 @@
-use inkstone_core::domain::search::SearchQuery;
-use inkstone_core::types::time_range::TimeRange;
+use inkstone_core::domain::search::{KeywordExpr, QueryOperator, SearchQuery};
+use inkstone_core::types::time_range::{DateField, TimeRange};
 use thiserror::Error;
 
 const MAX_KEYWORDS: usize = 10;
@@ -12,6 +12,8 @@ pub enum QueryParseError {
     EmptyQuery,
     #[error("empty search token")]
     EmptyToken,
+    #[error("unterminated quoted phrase")]
+    UnterminatedPhrase,
     #[error("too many keywords (max {0})")]
     TooManyKeywords(usize),
     #[error("invalid range filter: {0}")]
@@ -22,9 +24,32 @@ pub enum QueryParseError {
     InvalidTags(String),
     #[error("invalid category filter: {0}")]
     InvalidCategory(String),
+    #[error("invalid source filter: {0}")]
+    InvalidSource(String),
+    #[error("empty negated term")]
+    EmptyNegation,
+    #[error("{0} filter cannot be negated")]
+    UnsupportedNegation(&'static str),
+    #[error("query must include at least one term that is not negated")]
+    NegationOnly,
+    #[error("unbalanced parentheses")]
+    UnbalancedParentheses,
+    #[error("empty parenthesized group")]
+    EmptyGroup,
+    #[error("AND/OR must appear between two terms")]
+    DanglingOperator,
+    #[error("filters and exclusions are not allowed inside parentheses")]
+    FilterInGroup,
 }
 
 pub fn parse_query(input: &str) -> Result<SearchQuery, QueryParseError> {
+    parse_query_with_operator(input, QueryOperator::default())
+}
+
+pub fn parse_query_with_operator(
+    input: &str,
+    operator: QueryOperator,
+) -> Result<SearchQuery, QueryParseError> {
     if input.chars().any(|ch| ch.is_control()) {
         return Err(QueryParseError::ControlCharacter);
     }
@@ -34,43 +59,368 @@ pub fn parse_query(input: &str) -> Result<SearchQuery, QueryParseError> {
     }
 
     let mut query = SearchQuery::default();
-    for token in normalized.split_whitespace() {
+    let mut expr_tokens = Vec::new();
+    let mut depth = 0usize;
+    for token in split_tokens(&normalized)? {
+        let token = match token {
+            RawToken::Open => {
+                depth += 1;
+                expr_tokens.push(ExprToken::Open);
+                continue;
+            }
+            RawToken::Close => {
+                if depth == 0 {
+                    return Err(QueryParseError::UnbalancedParentheses);
+                }
+                depth -= 1;
+                expr_tokens.push(ExprToken::Close);
+                continue;
+            }
+            RawToken::Phrase(phrase) => {
+                if phrase.is_empty() {
+                    return Err(QueryParseError::EmptyToken);
+                }
+                expr_tokens.push(ExprToken::Phrase(phrase));
+                check_keyword_count(&expr_tokens, &query)?;
+                continue;
+            }
+            RawToken::ExcludedPhrase(phrase) => {
+                if depth > 0 {
+                    return Err(QueryParseError::FilterInGroup);
+                }
+                if phrase.is_empty() {
+                    return Err(QueryParseError::EmptyNegation);
+                }
+                query.excluded_keywords.push(phrase);
+                check_keyword_count(&expr_tokens, &query)?;
+                continue;
+            }
+            RawToken::Word(word) => word,
+        };
+        let token = token.as_str();
         if token.trim().is_empty() {
             return Err(QueryParseError::EmptyToken);
         }
-        if let Some(value) = token.strip_prefix("range:") {
-            if query.range.is_some() {
-                return Err(QueryParseError::DuplicateFilter("range"));
+        match token {
+            "OR" => {
+                expr_tokens.push(ExprToken::Or);
+                continue;
+            }
+            "AND" => {
+                expr_tokens.push(ExprToken::And);
+                continue;
+            }
+            _ => {}
+        }
+        if is_filter(token) {
+            if depth > 0 {
+                return Err(QueryParseError::FilterInGroup);
             }
-            let range = TimeRange::parse(value)
-                .map_err(|_| QueryParseError::InvalidRange(value.to_string()))?;
-            query.range = Some(range);
+            parse_filter(token, &mut query)?;
+            check_keyword_count(&expr_tokens, &query)?;
             continue;
         }
-        if let Some(value) = token.strip_prefix("tags:") {
-            let tags = parse_list(value)
-                .ok_or_else(|| QueryParseError::InvalidTags(value.to_string()))?;
-            query.tags.extend(tags);
+        expr_tokens.push(ExprToken::Keyword(token.to_string()));
+        check_keyword_count(&expr_tokens, &query)?;
+    }
+    if depth > 0 {
+        return Err(QueryParseError::UnbalancedParentheses);
+    }
+
+    if !expr_tokens.is_empty() {
+        let mut parser = ExprParser {
+            tokens: &expr_tokens,
+            pos: 0,
+            operator,
+        };
+        query.expr = Some(parser.parse()?);
+    }
+    if !query.has_positive_terms() {
+        return Err(QueryParseError::NegationOnly);
+    }
+    Ok(query)
+}
+
+const DATE_FILTERS: [(&str, DateField); 3] = [
+    ("range:", DateField::Any),
+    ("published:", DateField::Published),
+    ("updated:", DateField::Updated),
+];
+
+fn is_filter(token: &str) -> bool {
+    token.starts_with('-')
+        || is_date_filter(token)
+        || token.starts_with("tags:")
+        || token.starts_with("category:")
+        || token.starts_with("source:")
+}
+
+fn is_date_filter(token: &str) -> bool {
+    token.starts_with("year:") || DATE_FILTERS.iter().any(|(prefix, _)| token.starts_with(prefix))
+}
+
+// `range:`, `published:`, `updated:` and `year:` all set the one date range a
+// query may carry.
+fn parse_date_filter(token: &str, query: &mut SearchQuery) -> Result<(), QueryParseError> {
+    if query.range.is_some() {
+        return Err(QueryParseError::DuplicateFilter("range"));
+    }
+    let range = if let Some(value) = token.strip_prefix("year:") {
+        TimeRange::year(value).map_err(|_| QueryParseError::InvalidRange(value.to_string()))?
+    } else {
+        let Some((value, field)) = DATE_FILTERS
+            .iter()
+            .find_map(|(prefix, field)| Some((token.strip_prefix(prefix)?, *field)))
+        else {
+            return Err(QueryParseError::InvalidRange(token.to_string()));
+        };
+        TimeRange::parse(value)
+            .map_err(|_| QueryParseError::InvalidRange(value.to_string()))?
+            .with_field(field)
+    };
+    query.range = Some(range);
+    Ok(())
+}
+
+fn parse_filter(token: &str, query: &mut SearchQuery) -> Result<(), QueryParseError> {
+    if let Some(negated) = token.strip_prefix('-') {
+        return parse_negated(negated, query);
+    }
+    if is_date_filter(token) {
+        return parse_date_filter(token, query);
+    }
+    if let Some(value) = token.strip_prefix("tags:") {
+        let tags =
+            parse_list(value).ok_or_else(|| QueryParseError::InvalidTags(value.to_string()))?;
+        query.tags.extend(tags);
+        return Ok(());
+    }
+    if let Some(value) = token.strip_prefix("category:") {
+        if query.category.is_some() {
+            return Err(QueryParseError::DuplicateFilter("category"));
+        }
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(QueryParseError::InvalidCategory(value.to_string()));
+        }
+        query.category = Some(value.to_string());
+        return Ok(());
+    }
+    if let Some(value) = token.strip_prefix("source:") {
+        if query.source.is_some() {
+            return Err(QueryParseError::DuplicateFilter("source"));
+        }
+        query.source = Some(parse_source(value)?);
+    }
+    Ok(())
+}
+
+// Source names come from the config and are limited to ASCII word characters.
+fn parse_source(value: &str) -> Result<String, QueryParseError> {
+    let valid = !value.is_empty()
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-');
+    if !valid {
+        return Err(QueryParseError::InvalidSource(value.to_string()));
+    }
+    Ok(value.to_string())
+}
+
+enum ExprToken {
+    Keyword(String),
+    Phrase(String),
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+impl ExprToken {
+    fn starts_operand(&self) -> bool {
+        matches!(
+            self,
+            ExprToken::Keyword(_) | ExprToken::Phrase(_) | ExprToken::Open
+        )
+    }
+}
+
+// AND binds tighter than OR; adjacent operands are joined with the
+// configured default operator.
+struct ExprParser<'a> {
+    tokens: &'a [ExprToken],
+    pos: usize,
+    operator: QueryOperator,
+}
+
+impl ExprParser<'_> {
+    fn parse(&mut self) -> Result<KeywordExpr, QueryParseError> {
+        let expr = self.parse_or()?;
+        match self.tokens.get(self.pos) {
+            None => Ok(expr),
+            Some(ExprToken::Close) => Err(QueryParseError::UnbalancedParentheses),
+            Some(_) => Err(QueryParseError::DanglingOperator),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<KeywordExpr, QueryParseError> {
+        let mut items = vec![self.parse_and()?];
+        loop {
+            match self.tokens.get(self.pos) {
+                Some(ExprToken::Or) => {
+                    self.pos += 1;
+                    items.push(self.parse_and()?);
+                }
+                Some(token) if self.operator == QueryOperator::Or && token.starts_operand() => {
+                    items.push(self.parse_and()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(KeywordExpr::or(items))
+    }
+
+    fn parse_and(&mut self) -> Result<KeywordExpr, QueryParseError> {
+        let mut items = vec![self.parse_operand()?];
+        loop {
+            match self.tokens.get(self.pos) {
+                Some(ExprToken::And) => {
+                    self.pos += 1;
+                    items.push(self.parse_operand()?);
+                }
+                Some(token) if self.operator == QueryOperator::And && token.starts_operand() => {
+                    items.push(self.parse_operand()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(KeywordExpr::and(items))
+    }
+
+    fn parse_operand(&mut self) -> Result<KeywordExpr, QueryParseError> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        match token {
+            Some(ExprToken::Keyword(value)) => Ok(KeywordExpr::Keyword(value.clone())),
+            Some(ExprToken::Phrase(value)) => Ok(KeywordExpr::Phrase(value.clone())),
+            Some(ExprToken::Open) => {
+                if matches!(self.tokens.get(self.pos), Some(ExprToken::Close)) {
+                    return Err(QueryParseError::EmptyGroup);
+                }
+                let expr = self.parse_or()?;
+                match self.tokens.get(self.pos) {
+                    Some(ExprToken::Close) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(QueryParseError::UnbalancedParentheses),
+                }
+            }
+            Some(ExprToken::Close) => Err(QueryParseError::EmptyGroup),
+            Some(ExprToken::And | ExprToken::Or) | None => Err(QueryParseError::DanglingOperator),
+        }
+    }
+}
+
+fn parse_negated(token: &str, query: &mut SearchQuery) -> Result<(), QueryParseError> {
+    if token.is_empty() {
+        return Err(QueryParseError::EmptyNegation);
+    }
+    if is_date_filter(token) {
+        return Err(QueryParseError::UnsupportedNegation("range"));
+    }
+    if let Some(value) = token.strip_prefix("tags:") {
+        let tags =
+            parse_list(value).ok_or_else(|| QueryParseError::InvalidTags(value.to_string()))?;
+        query.excluded_tags.extend(tags);
+        return Ok(());
+    }
+    if let Some(value) = token.strip_prefix("category:") {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(QueryParseError::InvalidCategory(value.to_string()));
+        }
+        query.excluded_categories.push(value.to_string());
+        return Ok(());
+    }
+    if let Some(value) = token.strip_prefix("source:") {
+        query.excluded_sources.push(parse_source(value)?);
+        return Ok(());
+    }
+    query.excluded_keywords.push(token.to_string());
+    Ok(())
+}
+
+enum RawToken {
+    Word(String),
+    Phrase(String),
+    ExcludedPhrase(String),
+    Open,
+    Close,
+}
+
+fn split_tokens(input: &str) -> Result<Vec<RawToken>, QueryParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if ch == '(' || ch == ')' {
+            chars.next();
+            tokens.push(if ch == '(' { RawToken::Open } else { RawToken::Close });
             continue;
         }
-        if let Some(value) = token.strip_prefix("category:") {
-            if query.category.is_some() {
-                return Err(QueryParseError::DuplicateFilter("category"));
+        let negated = ch == '-' && chars.clone().nth(1) == Some('"');
+        if ch == '"' || negated {
+            if negated {
+                chars.next();
+            }
+            chars.next();
+            let mut phrase = String::new();
+            let mut closed = false;
+            for ch in chars.by_ref() {
+                if ch == '"' {
+                    closed = true;
+                    break;
+                }
+                phrase.push(ch);
             }
-            let value = value.trim();
-            if value.is_empty() {
-                return Err(QueryParseError::InvalidCategory(value.to_string()));
+            if !closed {
+                return Err(QueryParseError::UnterminatedPhrase);
+            }
+            let phrase = normalize_whitespace(&phrase);
+            if negated {
+                tokens.push(RawToken::ExcludedPhrase(phrase));
+            } else {
+                tokens.push(RawToken::Phrase(phrase));
             }
-            query.category = Some(value.to_string());
             continue;
         }
-        query.keywords.push(token.to_string());
-        if query.keywords.len() > MAX_KEYWORDS {
-            return Err(QueryParseError::TooManyKeywords(MAX_KEYWORDS));
+        let mut word = String::new();
+        while let Some(&ch) = chars.peek() {
+            if ch.is_whitespace() || ch == '(' || ch == ')' {
+                break;
+            }
+            word.push(ch);
+            chars.next();
         }
+        tokens.push(RawToken::Word(word));
     }
+    Ok(tokens)
+}
 
-    Ok(query)
+fn check_keyword_count(tokens: &[ExprToken], query: &SearchQuery) -> Result<(), QueryParseError> {
+    let positive = tokens
+        .iter()
+        .filter(|token| matches!(token, ExprToken::Keyword(_) | ExprToken::Phrase(_)))
+        .count();
+    let count = positive + query.excluded_keywords.len();
+    if count > MAX_KEYWORDS {
+        return Err(QueryParseError::TooManyKeywords(MAX_KEYWORDS));
+    }
+    Ok(())
 }
 
 fn parse_list(input: &str) -> Option<Vec<String>> {
@@ -110,11 +460,12 @@ fn normalize_whitespace(input: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::NaiveDate;
 
     #[test]
     fn parse_keywords_only() {
         let query = parse_query("Python Linux").unwrap();
-        assert_eq!(query.keywords, vec!["Python", "Linux"]);
+        assert_eq!(query.keywords(), vec!["Python", "Linux"]);
     }
 
     #[test]
@@ -123,6 +474,37 @@ mod tests {
         assert!(query.range.is_some());
     }
 
+    #[test]
+    fn parse_date_qualifiers() {
+        let query = parse_query("rust year:2023").unwrap();
+        let range = query.range.unwrap();
+        assert_eq!(range.start, NaiveDate::from_ymd_opt(2023, 1, 1));
+        assert_eq!(range.end, NaiveDate::from_ymd_opt(2023, 12, 31));
+        assert_eq!(range.field, DateField::Any);
+
+        let range = parse_query("published:2023-05").unwrap().range.unwrap();
+        assert_eq!(range.end, NaiveDate::from_ymd_opt(2023, 5, 31));
+        assert_eq!(range.field, DateField::Published);
+        let range = parse_query("updated:last-90d").unwrap().range.unwrap();
+        assert!(range.start.is_some() && range.end.is_none());
+        assert_eq!(range.field, DateField::Updated);
+
+        for input in ["year:2023-05", "range:last-0d", "published:2023-13", "updated:"] {
+            assert!(
+                matches!(parse_query(input), Err(QueryParseError::InvalidRange(_))),
+                "{input}"
+            );
+        }
+        assert!(matches!(
+            parse_query("year:2023 updated:2024"),
+            Err(QueryParseError::DuplicateFilter("range"))
+        ));
+        assert!(matches!(
+            parse_query("rust -published:2023"),
+            Err(QueryParseError::UnsupportedNegation("range"))
+        ));
+    }
+
     #[test]
     fn parse_tags_filter() {
         let query = parse_query("tags:Python,Linux").unwrap();
@@ -135,14 +517,152 @@ mod tests {
         assert_eq!(query.category, Some("share".to_string()));
     }
 
+    #[test]
+    fn parse_source_filters() {
+        let query = parse_query("rust source:notes -source:talks").unwrap();
+        assert_eq!(query.source, Some("notes".to_string()));
+        assert_eq!(query.excluded_sources, vec!["talks"]);
+        let err = parse_query("rust source:a source:b").unwrap_err();
+        assert!(matches!(err, QueryParseError::DuplicateFilter("source")));
+        let err = parse_query("rust source:a,b").unwrap_err();
+        assert!(matches!(err, QueryParseError::InvalidSource(_)));
+    }
+
     #[test]
     fn parse_combined_filters() {
         let query = parse_query("Python range:2018-01-01~2020-01-01 tags:Rust").unwrap();
-        assert_eq!(query.keywords, vec!["Python"]);
+        assert_eq!(query.keywords(), vec!["Python"]);
         assert_eq!(query.tags, vec!["Rust"]);
         assert!(query.range.is_some());
     }
 
+    #[test]
+    fn parse_quoted_phrase() {
+        let query = parse_query("\"rust  async\" tokio").unwrap();
+        assert_eq!(query.phrases(), vec!["rust async"]);
+        assert_eq!(query.keywords(), vec!["tokio"]);
+    }
+
+    #[test]
+    fn parse_rejects_unterminated_phrase() {
+        let err = parse_query("\"rust async").unwrap_err();
+        assert!(matches!(err, QueryParseError::UnterminatedPhrase));
+    }
+
+    #[test]
+    fn parse_rejects_empty_phrase() {
+        let err = parse_query("rust \"\"").unwrap_err();
+        assert!(matches!(err, QueryParseError::EmptyToken));
+    }
+
+    #[test]
+    fn parse_negated_terms() {
+        let query =
+            parse_query("tokio -actix tags:rust -tags:wasm -category:随笔 -\"web framework\"")
+                .unwrap();
+        assert_eq!(query.keywords(), vec!["tokio"]);
+        assert_eq!(query.tags, vec!["rust"]);
+        assert_eq!(query.excluded_keywords, vec!["actix", "web framework"]);
+        assert_eq!(query.excluded_tags, vec!["wasm"]);
+        assert_eq!(query.excluded_categories, vec!["随笔"]);
+    }
+
+    #[test]
+    fn parse_rejects_negation_only_query() {
+        let err = parse_query("-actix -tags:wasm").unwrap_err();
+        assert!(matches!(err, QueryParseError::NegationOnly));
+    }
+
+    #[test]
+    fn parse_rejects_empty_negation() {
+        let err = parse_query("tokio -").unwrap_err();
+        assert!(matches!(err, QueryParseError::EmptyNegation));
+    }
+
+    #[test]
+    fn parse_defaults_to_and() {
+        let query = parse_query("rust tantivy").unwrap();
+        assert_eq!(
+            query.expr,
+            Some(KeywordExpr::And(vec![
+                KeywordExpr::Keyword("rust".to_string()),
+                KeywordExpr::Keyword("tantivy".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_or_and_groups() {
+        let query = parse_query("rust (tantivy OR \"full text\") tags:search").unwrap();
+        assert_eq!(query.keywords(), vec!["rust", "tantivy"]);
+        assert_eq!(query.phrases(), vec!["full text"]);
+        assert_eq!(query.tags, vec!["search"]);
+        assert_eq!(
+            query.expr,
+            Some(KeywordExpr::And(vec![
+                KeywordExpr::Keyword("rust".to_string()),
+                KeywordExpr::Or(vec![
+                    KeywordExpr::Keyword("tantivy".to_string()),
+                    KeywordExpr::Phrase("full text".to_string()),
+                ]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_and_binds_tighter_than_or() {
+        let query = parse_query("a b OR c").unwrap();
+        assert_eq!(
+            query.expr,
+            Some(KeywordExpr::Or(vec![
+                KeywordExpr::And(vec![
+                    KeywordExpr::Keyword("a".to_string()),
+                    KeywordExpr::Keyword("b".to_string()),
+                ]),
+                KeywordExpr::Keyword("c".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_with_or_default_operator() {
+        let query = parse_query_with_operator("rust tantivy AND search", QueryOperator::Or).unwrap();
+        assert_eq!(
+            query.expr,
+            Some(KeywordExpr::Or(vec![
+                KeywordExpr::Keyword("rust".to_string()),
+                KeywordExpr::And(vec![
+                    KeywordExpr::Keyword("tantivy".to_string()),
+                    KeywordExpr::Keyword("search".to_string()),
+                ]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_groups() {
+        assert!(matches!(
+            parse_query("(rust tantivy").unwrap_err(),
+            QueryParseError::UnbalancedParentheses
+        ));
+        assert!(matches!(
+            parse_query("rust )").unwrap_err(),
+            QueryParseError::UnbalancedParentheses
+        ));
+        assert!(matches!(
+            parse_query("rust ()").unwrap_err(),
+            QueryParseError::EmptyGroup
+        ));
+        assert!(matches!(
+            parse_query("rust OR").unwrap_err(),
+            QueryParseError::DanglingOperator
+        ));
+        assert!(matches!(
+            parse_query("(rust tags:wasm)").unwrap_err(),
+            QueryParseError::FilterInGroup
+        ));
+    }
+
     #[test]
     fn parse_empty_query_returns_error() {
         let err = parse_query(" ").unwrap_err();
@@ -157,7 +677,7 @@ mod tests {
 
     #[test]
     fn parse_rejects_too_many_keywords() {
-        let query = vec![
+        let query = [
             "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
             "eleven",
         ]
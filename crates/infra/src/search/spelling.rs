@@ -0,0 +1,82 @@
+// Edit distance helpers behind "did you mean" suggestions.
+
+// Optimal string alignment distance over chars: insertions, deletions,
+// substitutions and adjacent transpositions all cost one.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let width = b.len() + 1;
+    let mut rows = vec![0usize; (a.len() + 1) * width];
+    for (j, cell) in rows.iter_mut().take(width).enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        rows[i * width] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[(i - 1) * width + j] + 1)
+                .min(rows[i * width + j - 1] + 1)
+                .min(rows[(i - 1) * width + j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[(i - 2) * width + j - 2] + 1);
+            }
+            rows[i * width + j] = best;
+        }
+    }
+    rows[a.len() * width + b.len()]
+}
+
+// How many edits a whole query may be away from a suggestion. Hanzi carry more
+// meaning per char, so a single slip is allowed from two chars on.
+pub fn suggestion_distance(text: &str) -> Option<usize> {
+    let chars = text.chars().count();
+    if !text.is_ascii() {
+        return (chars >= 2).then_some(1);
+    }
+    match chars {
+        0..=3 => None,
+        4..=7 => Some(1),
+        _ => Some(2),
+    }
+}
+
+// Closest earlier query within the edit budget; ties go to the most frequent.
+pub fn closest_query<'a>(query: &str, candidates: &'a [(String, i64)]) -> Option<&'a str> {
+    let budget = suggestion_distance(query)?;
+    let len = query.chars().count();
+    candidates
+        .iter()
+        .filter(|(candidate, _)| candidate != query)
+        .filter(|(candidate, _)| candidate.chars().count().abs_diff(len) <= budget)
+        .map(|(candidate, count)| (edit_distance(query, candidate), *count, candidate))
+        .filter(|(distance, _, _)| *distance <= budget)
+        .min_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)).then_with(|| a.2.cmp(b.2)))
+        .map(|(_, _, candidate)| candidate.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closest_query, edit_distance, suggestion_distance};
+
+    #[test]
+    fn edit_distance_counts_transpositions_once() {
+        assert_eq!(edit_distance("tantivy", "tantivy"), 0);
+        assert_eq!(edit_distance("tantvy", "tantivy"), 1);
+        assert_eq!(edit_distance("tnativy", "tantivy"), 1);
+        assert_eq!(edit_distance("数据苦", "数据库"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn closest_query_respects_budget_and_prefers_frequent_queries() {
+        let candidates = vec![
+            ("rust async".to_string(), 3),
+            ("rust asynk".to_string(), 9),
+            ("数据库 索引".to_string(), 2),
+        ];
+        assert_eq!(closest_query("rust asyn", &candidates), Some("rust asynk"));
+        assert_eq!(closest_query("数据苦 索引", &candidates), Some("数据库 索引"));
+        assert_eq!(closest_query("go", &candidates), None);
+        assert_eq!(suggestion_distance("abc"), None);
+    }
+}
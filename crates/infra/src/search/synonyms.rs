@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SynonymError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+// Groups of interchangeable search terms, one comma-separated group per line:
+//
+//   # comments and blank lines are ignored
+//   k8s, Kubernetes
+//   JS, JavaScript
+//
+// Lookups are case-insensitive; lines sharing a term are merged into one group.
+#[derive(Debug, Clone, Default)]
+pub struct SynonymMap {
+    groups: Vec<Vec<String>>,
+    lookup: HashMap<String, usize>,
+}
+
+impl SynonymMap {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SynonymError> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&contents))
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut map = Self::default();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let terms = line
+                .split(',')
+                .map(str::trim)
+                .filter(|term| !term.is_empty())
+                .collect::<Vec<_>>();
+            if terms.len() >= 2 {
+                map.add_group(&terms);
+            }
+        }
+        map.groups.retain(|group| !group.is_empty());
+        map.lookup = map
+            .groups
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, group)| group.iter().map(move |term| (term.to_lowercase(), idx)))
+            .collect();
+        map
+    }
+
+    fn add_group(&mut self, terms: &[&str]) {
+        let mut target = None;
+        for term in terms {
+            let Some(&idx) = self.lookup.get(&term.to_lowercase()) else {
+                continue;
+            };
+            match target {
+                None => target = Some(idx),
+                Some(target) if target != idx => {
+                    let moved = std::mem::take(&mut self.groups[idx]);
+                    for value in moved {
+                        self.lookup.insert(value.to_lowercase(), target);
+                        self.groups[target].push(value);
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        let target = target.unwrap_or_else(|| {
+            self.groups.push(Vec::new());
+            self.groups.len() - 1
+        });
+        for term in terms {
+            let key = term.to_lowercase();
+            if self.lookup.get(&key) != Some(&target) {
+                self.lookup.insert(key, target);
+                self.groups[target].push(term.to_string());
+            }
+        }
+    }
+
+    // Other spellings of `term`, excluding the term itself.
+    pub fn expand(&self, term: &str) -> Vec<&str> {
+        let key = term.to_lowercase();
+        let Some(&idx) = self.lookup.get(&key) else {
+            return Vec::new();
+        };
+        self.groups[idx]
+            .iter()
+            .filter(|value| value.to_lowercase() != key)
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SynonymMap;
+
+    #[test]
+    fn parse_skips_comments_and_expands_case_insensitively() {
+        let map = SynonymMap::parse("# aliases\n\nk8s, Kubernetes\nJS,JavaScript\nsolo\n");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.expand("K8S"), vec!["Kubernetes"]);
+        assert_eq!(map.expand("javascript"), vec!["JS"]);
+        assert!(map.expand("solo").is_empty());
+    }
+
+    #[test]
+    fn parse_merges_groups_sharing_a_term() {
+        let map = SynonymMap::parse("数据库, DB\nPostgres, pg\ndb, database, pg\n");
+        assert_eq!(map.len(), 1);
+        let mut expanded = map.expand("数据库");
+        expanded.sort();
+        assert_eq!(expanded, vec!["DB", "Postgres", "database", "pg"]);
+    }
+}
@@ -1,22 +1,69 @@
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use chrono::{DateTime, Utc};
-use inkstone_core::domain::search::{SearchDocument, SearchHit, SearchQuery, SearchResult};
+use inkstone_core::domain::search::{
+    FacetCount, FacetField, HighlightFragment, KeywordExpr, SearchDocument, SearchFacets,
+    SearchHighlights, SearchHit, SearchQuery, SearchResult, SearchSection, SearchSuggestions,
+    SectionHit, TitleSuggestion,
+};
+use inkstone_core::types::time_range::DateField;
 use std::ops::Bound;
-use tantivy::collector::{Count, TopDocs};
+use tantivy::aggregation::agg_req::{Aggregation, AggregationVariants, Aggregations};
+use tantivy::aggregation::agg_result::{AggregationResult, BucketResult};
+use tantivy::aggregation::bucket::TermsAggregation;
+use tantivy::aggregation::{AggregationCollector, AggregationLimitsGuard, Key};
+use tantivy::columnar::{Column, StrColumn};
+use tantivy::collector::{Count, DocSetCollector, TopDocs};
 use tantivy::query::{
-    AllQuery, BooleanQuery, EmptyQuery, Occur, PhraseQuery, Query, RangeQuery, TermQuery,
+    AllQuery, BooleanQuery, BoostQuery, EmptyQuery, FuzzyTermQuery, MoreLikeThisQuery, Occur,
+    PhrasePrefixQuery, PhraseQuery, Query, RangeQuery, TermQuery, TermSetQuery,
 };
 use tantivy::schema::{
-    Field, IndexRecordOption, Schema, SchemaBuilder, TextFieldIndexing, TextOptions, Value, FAST,
-    STORED, STRING,
+    Field, IndexRecordOption, OwnedValue, Schema, SchemaBuilder, TextFieldIndexing, TextOptions,
+    Value, FAST, STORED, STRING,
+};
+use tantivy::snippet::{collapse_overlapped_ranges, Snippet, SnippetGenerator};
+use tantivy::tokenizer::TextAnalyzer;
+use tantivy::index::SegmentId;
+use tantivy::{
+    DocAddress, DocId, Index, IndexReader, IndexWriter, Opstamp, Order, ReloadPolicy, Score,
+    Searcher, SegmentReader, TantivyDocument, Term,
 };
-use tantivy::snippet::SnippetGenerator;
-use tantivy::tokenizer::{LowerCaser, RemoveLongFilter, Stemmer, TextAnalyzer};
-use tantivy::{DocAddress, Index, IndexReader, Order, ReloadPolicy, TantivyDocument, Term};
 use thiserror::Error;
 
-use super::SearchSort;
+use super::{
+    CursorKey, FieldBoosts, HighlightMode, PopularityScores, SearchCursor, SearchOptions,
+    SearchSort, SearchTokenizer, SourceWeights,
+};
+use super::spelling::edit_distance;
+use super::tokenizer::PinyinStyle;
+
+const MAX_FACET_VALUES: u32 = 100;
+const SMART_HALF_LIFE_DAYS: f32 = 180.0;
+const RELATED_MAX_QUERY_TERMS: usize = 25;
+const RELATED_TAG_BOOST: f32 = 2.0;
+const RELATED_CATEGORY_BOOST: f32 = 1.0;
+// Searchers kept alive so cursors from recent reloads keep paging the same snapshot;
+// older cursors continue on the current one after their hit's new position.
+const CURSOR_SEARCHER_RETENTION: usize = 8;
+const EXCERPT_CHARS: usize = 120;
+const FRAGMENT_SEPARATOR: &str = " … ";
+const TOKENIZER_FINGERPRINT_FILE: &str = "tokenizer.fingerprint";
+// Sections live in their own index so they never count towards post term statistics.
+const SECTIONS_DIR: &str = "sections";
+const SCHEMA_VERSION_FILE: &str = "schema.version";
+// Bump whenever fields are added or their options change; older indexes are rebuilt.
+const SCHEMA_VERSION: u32 = 8;
+// Pinyin matches are a fallback and rank below direct hanzi matches.
+const PINYIN_BOOST: f32 = 0.1;
+const FUZZY_BOOST: f32 = 0.5;
+const MAX_SECTIONS_PER_HIT: usize = 3;
+// Matching sections read per hit before the best ones are picked.
+const SECTION_CANDIDATES_PER_HIT: usize = 50;
 
 #[derive(Debug, Error)]
 pub enum SearchIndexError {
@@ -32,6 +79,16 @@ pub enum SearchIndexError {
     MissingValue(&'static str),
     #[error("invalid stored timestamp: {0}")]
     InvalidTimestamp(&'static str),
+    #[error("field is not a fast field, rebuild the index: {0}")]
+    FieldNotFast(&'static str),
+    #[error("index schema version {0} is outdated, rebuild the index")]
+    OutdatedSchema(u32),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(&'static str),
+    #[error("cursor expired, restart from the first page")]
+    CursorExpired,
 }
 
 #[derive(Debug, Clone)]
@@ -43,113 +100,783 @@ struct SearchFields {
     url: Field,
     tags: Field,
     category: Field,
+    source: Field,
     published: Field,
     updated: Field,
     checksum: Field,
+    pinyin: Field,
+    pinyin_initials: Field,
+    spelling: Field,
+    tag_lookup: Field,
+    category_lookup: Field,
+}
+
+#[derive(Debug, Clone)]
+struct SectionFields {
+    parent: Field,
+    heading: Field,
+    content: Field,
+    anchor: Field,
+}
+
+struct SectionIndex {
+    index: Index,
+    reader: IndexReader,
+    fields: SectionFields,
 }
 
 pub struct SearchIndex {
     index: Index,
     reader: IndexReader,
     fields: SearchFields,
+    sections: SectionIndex,
+    dir: PathBuf,
+    instance_id: u64,
+    recent_searchers: Mutex<VecDeque<Searcher>>,
+    tokenizer_stale: bool,
+    sections_stale: AtomicBool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DocumentRef<'a> {
+    Id(&'a str),
+    Url(&'a str),
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct SearchIndexStats {
     pub num_docs: u64,
+    pub num_sections: u64,
     pub num_segments: usize,
 }
 
 impl SearchIndex {
     pub fn open_or_create(path: impl AsRef<Path>) -> Result<Self, SearchIndexError> {
+        Self::open_with_tokenizer(path, &SearchTokenizer::default())
+    }
+
+    // New indexes record the tokenizer fingerprint and schema version they were
+    // built with; `tokenizer_stale` reports an existing index built with another
+    // tokenizer, and an older schema fails with `OutdatedSchema`.
+    pub fn open_with_tokenizer(
+        path: impl AsRef<Path>,
+        tokenizer: &SearchTokenizer,
+    ) -> Result<Self, SearchIndexError> {
         let dir = path.as_ref();
         std::fs::create_dir_all(dir)?;
 
         let schema = build_schema();
-        let index = if dir.join("meta.json").exists() {
-            Index::open_in_dir(dir)?
+        let fingerprint_path = dir.join(TOKENIZER_FINGERPRINT_FILE);
+        let version_path = dir.join(SCHEMA_VERSION_FILE);
+        let sections_dir = dir.join(SECTIONS_DIR);
+        let (index, sections) = if dir.join("meta.json").exists() {
+            let version = match std::fs::read_to_string(&version_path) {
+                Ok(value) => value.trim().parse().unwrap_or_default(),
+                // Indexes from before versions were recorded count as version 0.
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+                Err(err) => return Err(err.into()),
+            };
+            if version != SCHEMA_VERSION {
+                return Err(SearchIndexError::OutdatedSchema(version));
+            }
+            (Index::open_in_dir(dir)?, Index::open_in_dir(&sections_dir)?)
         } else {
-            Index::create_in_dir(dir, schema)?
+            std::fs::create_dir_all(&sections_dir)?;
+            let sections = Index::create_in_dir(&sections_dir, build_section_schema())?;
+            let index = Index::create_in_dir(dir, schema)?;
+            std::fs::write(&fingerprint_path, tokenizer.fingerprint())?;
+            std::fs::write(&version_path, SCHEMA_VERSION.to_string())?;
+            (index, sections)
         };
-        register_jieba_tokenizer(&index);
+        register_tokenizers(&index, tokenizer);
+        register_tokenizers(&sections, tokenizer);
+        let indexed_fingerprint = match std::fs::read_to_string(&fingerprint_path) {
+            Ok(value) => value.trim().to_string(),
+            // Indexes from before fingerprints were recorded predate Chinese folding.
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+        // Sections commit after the main index and record its opstamp; indexes
+        // from before that have no payload and are taken to be in sync.
+        let opstamp = index.load_metas()?.opstamp;
+        let sections_stale = sections
+            .load_metas()?
+            .payload
+            .is_some_and(|payload| payload.parse::<Opstamp>().ok() != Some(opstamp));
         let schema = index.schema();
         let fields = SearchFields::from_schema(&schema)?;
         let reader = index
             .reader_builder()
             .reload_policy(ReloadPolicy::OnCommitWithDelay)
             .try_into()?;
+        let sections = SectionIndex {
+            fields: SectionFields::from_schema(&sections.schema())?,
+            reader: sections
+                .reader_builder()
+                .reload_policy(ReloadPolicy::OnCommitWithDelay)
+                .try_into()?,
+            index: sections,
+        };
+        let instance_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
         Ok(Self {
             index,
             reader,
             fields,
+            sections,
+            dir: dir.to_path_buf(),
+            instance_id,
+            recent_searchers: Mutex::new(VecDeque::new()),
+            tokenizer_stale: indexed_fingerprint != tokenizer.fingerprint(),
+            sections_stale: AtomicBool::new(sections_stale),
         })
     }
 
+    pub fn tokenizer_stale(&self) -> bool {
+        self.tokenizer_stale
+    }
+
+    // Sections only come from the feeds, so a section commit that failed after
+    // the main index committed is repaired by rebuilding from them.
+    pub fn sections_stale(&self) -> bool {
+        self.sections_stale.load(Ordering::Relaxed)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
     pub fn search(
         &self,
         query: &SearchQuery,
-        limit: usize,
-        offset: usize,
-        sort: SearchSort,
+        options: &SearchOptions,
     ) -> Result<SearchResult, SearchIndexError> {
-        let searcher = self.reader.searcher();
-        let built_query = build_query(&self.index, &self.fields, query)?;
-        let (title_snippet, subtitle_snippet, content_snippet) =
-            if let Some(keyword_query) = built_query.keyword.as_ref() {
-                let mut title_snippet =
-                    SnippetGenerator::create(&searcher, &**keyword_query, self.fields.title)?;
-                title_snippet.set_max_num_chars(240);
-                let mut subtitle_snippet =
-                    SnippetGenerator::create(&searcher, &**keyword_query, self.fields.subtitle)?;
-                subtitle_snippet.set_max_num_chars(240);
-                let mut content_snippet =
-                    SnippetGenerator::create(&searcher, &**keyword_query, self.fields.content)?;
-                content_snippet.set_max_num_chars(240);
-                (Some(title_snippet), Some(subtitle_snippet), Some(content_snippet))
-            } else {
-                (None, None, None)
-            };
-        let total = searcher.search(&built_query.query, &Count)?;
-        let docs: Vec<DocAddress> = match sort {
-            SearchSort::Relevance => searcher
-                .search(
-                    &built_query.query,
-                    &TopDocs::with_limit(limit.saturating_add(offset)),
-                )?
-                .into_iter()
-                .map(|(_, address)| address)
-                .collect(),
-            SearchSort::Latest => {
-                let collector = TopDocs::with_limit(limit.saturating_add(offset))
-                    .order_by_fast_field::<i64>("updated", Order::Desc);
+        let SearchOptions {
+            limit,
+            offset,
+            sort,
+            ref facets,
+            ref popularity,
+            ref source_weights,
+            boosts: _,
+            explain,
+            ref cursor,
+            highlight,
+            snippets,
+            synonyms: _,
+        } = *options;
+        let (searcher, snapshot) = match cursor {
+            Some(cursor) => self.cursor_searcher(cursor, sort)?,
+            None => (self.current_searcher(), None),
+        };
+        let mut built_query = build_query(&self.index, &self.fields, query, options)?;
+        let mut total = searcher.search(&built_query.query, &Count)?;
+        let mut fuzzy = false;
+        if total == 0 && query.keyword_expr().is_some() {
+            let fuzzy_query = build_query_matching(
+                &self.index,
+                &self.fields,
+                query,
+                options,
+                KeywordMatch::Fuzzy,
+            )?;
+            let fuzzy_total = searcher.search(&fuzzy_query.query, &Count)?;
+            if fuzzy_total > 0 {
+                built_query = fuzzy_query;
+                total = fuzzy_total;
+                fuzzy = true;
+            }
+        }
+        let highlighter = match built_query.keyword.as_ref() {
+            Some(keyword_query) => {
+                let generator = |field| -> Result<SnippetGenerator, SearchIndexError> {
+                    let mut generator =
+                        SnippetGenerator::create(&searcher, &**keyword_query, field)?;
+                    generator.set_max_num_chars(snippets.max_chars);
+                    Ok(generator)
+                };
+                Some(Highlighter {
+                    title: generator(self.fields.title)?,
+                    subtitle: generator(self.fields.subtitle)?,
+                    content: generator(self.fields.content)?,
+                    mode: highlight,
+                    fragments: snippets.fragments.max(1),
+                })
+            }
+            None => None,
+        };
+        // With a cursor the page starts right after it; one extra doc tells us whether
+        // another page exists.
+        let skip = if cursor.is_some() { 0 } else { offset };
+        let fetch = skip.saturating_add(limit).saturating_add(1);
+        let source_weights = source_weights.clone().unwrap_or_default();
+        let after = match (cursor, snapshot) {
+            (Some(cursor), Some(address)) => Some((cursor.key, address)),
+            (Some(cursor), None) => Some(self.locate_cursor(
+                &searcher,
+                &*built_query.query,
+                cursor,
+                &source_weights,
+            )?),
+            (None, _) => None,
+        };
+        let docs: Vec<(Option<CursorKey>, DocAddress)> = match sort {
+            SearchSort::Relevance => match after {
+                Some((key, after)) => {
+                    let CursorKey::Score(after_score) = key else {
+                        return Err(SearchIndexError::InvalidCursor("sort mismatch"));
+                    };
+                    let segment_ids = segment_ids(&searcher);
+                    let collector = TopDocs::with_limit(fetch).tweak_score(
+                        move |segment_reader: &SegmentReader| {
+                            let segment_ord = segment_ord(&segment_ids, segment_reader);
+                            let weights =
+                                SegmentSourceWeights::new(segment_reader, &source_weights);
+                            move |doc: DocId, score: Score| {
+                                let score = score * weights.get(doc);
+                                let address = DocAddress::new(segment_ord, doc);
+                                if is_after(score, address, after_score, after) {
+                                    score
+                                } else {
+                                    f32::NEG_INFINITY
+                                }
+                            }
+                        },
+                    );
+                    searcher
+                        .search(&built_query.query, &collector)?
+                        .into_iter()
+                        .filter(|(score, _)| *score != f32::NEG_INFINITY)
+                        .map(|(score, address)| (Some(CursorKey::Score(score)), address))
+                        .collect()
+                }
+                None if source_weights.is_empty() => searcher
+                    .search(&built_query.query, &TopDocs::with_limit(fetch))?
+                    .into_iter()
+                    .map(|(score, address)| (Some(CursorKey::Score(score)), address))
+                    .collect(),
+                None => {
+                    let collector = TopDocs::with_limit(fetch).tweak_score(
+                        move |segment_reader: &SegmentReader| {
+                            let weights =
+                                SegmentSourceWeights::new(segment_reader, &source_weights);
+                            move |doc: DocId, score: Score| score * weights.get(doc)
+                        },
+                    );
+                    searcher
+                        .search(&built_query.query, &collector)?
+                        .into_iter()
+                        .map(|(score, address)| (Some(CursorKey::Score(score)), address))
+                        .collect()
+                }
+            },
+            SearchSort::Latest => match after {
+                Some((key, after)) => {
+                    let CursorKey::Updated(after_updated) = key else {
+                        return Err(SearchIndexError::InvalidCursor("sort mismatch"));
+                    };
+                    let segment_ids = segment_ids(&searcher);
+                    let collector = TopDocs::with_limit(fetch).custom_score(
+                        move |segment_reader: &SegmentReader| {
+                            let segment_ord = segment_ord(&segment_ids, segment_reader);
+                            let updated = segment_reader.fast_fields().i64("updated").ok();
+                            move |doc: DocId| {
+                                let value = updated
+                                    .as_ref()
+                                    .and_then(|column| column.first(doc))
+                                    .unwrap_or(i64::MIN);
+                                let address = DocAddress::new(segment_ord, doc);
+                                if is_after(value, address, after_updated, after) {
+                                    value
+                                } else {
+                                    i64::MIN
+                                }
+                            }
+                        },
+                    );
+                    searcher
+                        .search(&built_query.query, &collector)?
+                        .into_iter()
+                        .filter(|(updated, _)| *updated != i64::MIN)
+                        .map(|(updated, address)| (Some(CursorKey::Updated(updated)), address))
+                        .collect()
+                }
+                None => {
+                    let collector = TopDocs::with_limit(fetch)
+                        .order_by_fast_field::<i64>("updated", Order::Desc);
+                    searcher
+                        .search(&built_query.query, &collector)?
+                        .into_iter()
+                        .map(|(updated, address)| (Some(CursorKey::Updated(updated)), address))
+                        .collect()
+                }
+            },
+            SearchSort::Smart => {
+                let now = Utc::now().timestamp();
+                let collector = TopDocs::with_limit(fetch).tweak_score(
+                    move |segment_reader: &SegmentReader| {
+                        let updated = segment_reader.fast_fields().i64("updated").ok();
+                        let weights = SegmentSourceWeights::new(segment_reader, &source_weights);
+                        move |doc: DocId, score: Score| {
+                            let updated = updated
+                                .as_ref()
+                                .and_then(|column| column.first(doc))
+                                .unwrap_or(now);
+                            let weight = weights.get(doc);
+                            score * weight * (1.0 + recency_decay(now - updated))
+                        }
+                    },
+                );
                 searcher
                     .search(&built_query.query, &collector)?
                     .into_iter()
-                    .map(|(_, address)| address)
+                    .map(|(_, address)| (None, address))
+                    .collect()
+            }
+            SearchSort::Popular => {
+                let popularity = popularity.clone().unwrap_or_default();
+                let collector = TopDocs::with_limit(fetch).tweak_score(
+                    move |segment_reader: &SegmentReader| {
+                        let mut boosts = SegmentPopularity::new(segment_reader, &popularity);
+                        let weights = SegmentSourceWeights::new(segment_reader, &source_weights);
+                        move |doc: DocId, score: Score| {
+                            let weight = weights.get(doc);
+                            score * weight * (1.0 + boosts.get(doc))
+                        }
+                    },
+                );
+                searcher
+                    .search(&built_query.query, &collector)?
+                    .into_iter()
+                    .map(|(_, address)| (None, address))
                     .collect()
             }
         };
+        let has_more = docs.len() > skip.saturating_add(limit);
+        let page = docs.into_iter().skip(skip).take(limit).collect::<Vec<_>>();
 
         let mut hits = Vec::new();
-        for address in docs.into_iter().skip(offset).take(limit) {
+        let mut hit_ids = Vec::new();
+        for (_, address) in &page {
+            let address = *address;
             let doc: TantivyDocument = searcher.doc(address)?;
-            hits.push(self.document_to_hit(
-                &doc,
-                title_snippet.as_ref(),
-                subtitle_snippet.as_ref(),
-                content_snippet.as_ref(),
-            )?);
+            let id = get_string(&doc, self.fields.id).ok_or(SearchIndexError::MissingValue("id"))?;
+            hit_ids.push(id);
+            let mut hit = self.document_to_hit(&doc, highlighter.as_ref())?;
+            if explain {
+                let explanation = built_query.query.explain(&searcher, address)?;
+                hit.explanation = Some(serde_json::to_value(&explanation)?);
+            }
+            hits.push(hit);
+        }
+        let next_cursor = match (page.last(), hit_ids.last()) {
+            (Some((Some(key), address)), Some(id)) if has_more => Some(
+                SearchCursor {
+                    index_id: self.instance_id,
+                    generation: searcher.generation().generation_id(),
+                    key: *key,
+                    segment_ord: address.segment_ord,
+                    doc_id: address.doc_id,
+                    id: id.clone(),
+                }
+                .encode(),
+            ),
+            _ => None,
+        };
+        if !fuzzy {
+            self.attach_sections(query, options, &hit_ids, &mut hits)?;
+        }
+
+        let facets = if facets.is_empty() {
+            None
+        } else {
+            Some(self.collect_facets(&searcher, &*built_query.query, facets)?)
+        };
+
+        Ok(SearchResult {
+            total,
+            hits,
+            facets,
+            next_cursor,
+            fuzzy,
+        })
+    }
+
+    fn current_searcher(&self) -> Searcher {
+        let searcher = self.reader.searcher();
+        let generation = searcher.generation().generation_id();
+        let mut recent = self
+            .recent_searchers
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if recent
+            .back()
+            .is_none_or(|last| last.generation().generation_id() != generation)
+        {
+            recent.push_back(searcher.clone());
+            while recent.len() > CURSOR_SEARCHER_RETENTION {
+                recent.pop_front();
+            }
+        }
+        searcher
+    }
+
+    // The snapshot the cursor's page came from, with the address that breaks
+    // sort key ties in it; once released, the current searcher without one.
+    fn cursor_searcher(
+        &self,
+        cursor: &SearchCursor,
+        sort: SearchSort,
+    ) -> Result<(Searcher, Option<DocAddress>), SearchIndexError> {
+        if !matches!(sort, SearchSort::Relevance | SearchSort::Latest) {
+            return Err(SearchIndexError::InvalidCursor(
+                "only relevance and latest sorts support cursors",
+            ));
+        }
+        if cursor.index_id == self.instance_id {
+            let recent = self
+                .recent_searchers
+                .lock()
+                .unwrap_or_else(|err| err.into_inner());
+            let searcher = recent
+                .iter()
+                .find(|searcher| searcher.generation().generation_id() == cursor.generation)
+                .cloned();
+            if let Some(searcher) = searcher {
+                return Ok((searcher, Some(cursor.address())));
+            }
+        }
+        Ok((self.current_searcher(), None))
+    }
+
+    // Finds the cursor's hit in a searcher other than its own snapshot and
+    // returns its sort key there: scores change with the index statistics, so
+    // the key stored in the cursor cannot be compared against the new ones.
+    fn locate_cursor(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        cursor: &SearchCursor,
+        source_weights: &Arc<SourceWeights>,
+    ) -> Result<(CursorKey, DocAddress), SearchIndexError> {
+        let id_query = TermQuery::new(
+            Term::from_field_text(self.fields.id, &cursor.id),
+            IndexRecordOption::Basic,
+        );
+        let Some((_, anchor)) = searcher
+            .search(&id_query, &TopDocs::with_limit(1))?
+            .into_iter()
+            .next()
+        else {
+            return Err(SearchIndexError::CursorExpired);
+        };
+        let segment_reader = searcher.segment_reader(anchor.segment_ord);
+        match cursor.key {
+            CursorKey::Score(_) => {
+                let source_weights = source_weights.clone();
+                let segment_id = segment_reader.segment_id();
+                let collector = TopDocs::with_limit(1).tweak_score(
+                    move |segment_reader: &SegmentReader| {
+                        let weights = SegmentSourceWeights::new(segment_reader, &source_weights);
+                        let in_segment = segment_reader.segment_id() == segment_id;
+                        move |doc: DocId, score: Score| {
+                            if in_segment && doc == anchor.doc_id {
+                                score * weights.get(doc)
+                            } else {
+                                f32::NEG_INFINITY
+                            }
+                        }
+                    },
+                );
+                // A hit that no longer matches has no place in the new order.
+                match searcher.search(query, &collector)?.into_iter().next() {
+                    Some((score, address)) if address == anchor => {
+                        Ok((CursorKey::Score(score), anchor))
+                    }
+                    _ => Err(SearchIndexError::CursorExpired),
+                }
+            }
+            CursorKey::Updated(_) => {
+                let updated = segment_reader
+                    .fast_fields()
+                    .i64("updated")?
+                    .first(anchor.doc_id)
+                    .unwrap_or(i64::MIN);
+                Ok((CursorKey::Updated(updated), anchor))
+            }
+        }
+    }
+
+    fn collect_facets(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        facets: &[FacetField],
+    ) -> Result<SearchFacets, SearchIndexError> {
+        let schema = self.index.schema();
+        let mut aggregations = Aggregations::new();
+        for facet in facets {
+            let field = match facet {
+                FacetField::Tags => self.fields.tags,
+                FacetField::Category => self.fields.category,
+            };
+            if !schema.get_field_entry(field).is_fast() {
+                return Err(SearchIndexError::FieldNotFast(facet.as_str()));
+            }
+            let terms = TermsAggregation {
+                field: facet.as_str().to_string(),
+                size: Some(MAX_FACET_VALUES),
+                ..Default::default()
+            };
+            aggregations.insert(
+                facet.as_str().to_string(),
+                Aggregation {
+                    agg: AggregationVariants::Terms(terms),
+                    sub_aggregation: Aggregations::new(),
+                },
+            );
+        }
+        let collector =
+            AggregationCollector::from_aggs(aggregations, AggregationLimitsGuard::default());
+        let results = searcher.search(query, &collector)?;
+
+        let mut output = SearchFacets::default();
+        for facet in facets {
+            let counts = match results.0.get(facet.as_str()) {
+                Some(AggregationResult::BucketResult(BucketResult::Terms { buckets, .. })) => {
+                    buckets
+                        .iter()
+                        .filter_map(|bucket| match &bucket.key {
+                            Key::Str(value) => Some(FacetCount {
+                                value: value.clone(),
+                                count: bucket.doc_count,
+                            }),
+                            _ => None,
+                        })
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            match facet {
+                FacetField::Tags => output.tags = Some(counts),
+                FacetField::Category => output.category = Some(counts),
+            }
+        }
+        Ok(output)
+    }
+
+    pub fn suggest(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<SearchSuggestions, SearchIndexError> {
+        let searcher = self.reader.searcher();
+        let mut analyzer = self
+            .index
+            .tokenizers()
+            .get("jieba")
+            .ok_or(SearchIndexError::MissingTokenizer("jieba"))?;
+        let tokens = tokenize_keyword(&mut analyzer, prefix);
+        let mut titles = Vec::new();
+        if !tokens.is_empty() && limit > 0 {
+            let terms = tokens
+                .into_iter()
+                .map(|(offset, token)| (offset, Term::from_field_text(self.fields.title, &token)))
+                .collect::<Vec<_>>();
+            let query = PhrasePrefixQuery::new_with_offset(terms);
+            let collector =
+                TopDocs::with_limit(limit).order_by_fast_field::<i64>("updated", Order::Desc);
+            for (_, address) in searcher.search(&query, &collector)? {
+                let doc: TantivyDocument = searcher.doc(address)?;
+                let title = get_string(&doc, self.fields.title)
+                    .ok_or(SearchIndexError::MissingValue("title"))?;
+                let url =
+                    get_string(&doc, self.fields.url).ok_or(SearchIndexError::MissingValue("url"))?;
+                titles.push(TitleSuggestion { title, url });
+            }
+        }
+        Ok(SearchSuggestions {
+            titles,
+            tags: suggest_terms(&searcher, self.fields.tag_lookup, prefix, limit)?,
+            categories: suggest_terms(&searcher, self.fields.category_lookup, prefix, limit)?,
+        })
+    }
+
+    // Hits for `query` with exact keyword matching, without collecting documents.
+    pub fn count(
+        &self,
+        query: &SearchQuery,
+        options: &SearchOptions,
+    ) -> Result<usize, SearchIndexError> {
+        let searcher = self.current_searcher();
+        let built_query = build_query(&self.index, &self.fields, query, options)?;
+        Ok(searcher.search(&built_query.query, &Count)?)
+    }
+
+    // Closest indexed spelling of a word the index does not know, preferring the
+    // fewest edits and then the most documents. Only terms sharing the first letter
+    // are walked, which keeps the scan small at the cost of missing typos there.
+    pub fn spelling_neighbour(&self, word: &str) -> Result<Option<String>, SearchIndexError> {
+        let word = word.to_lowercase();
+        let Some(budget) = fuzzy_distance(&word) else {
+            return Ok(None);
+        };
+        let Some(first) = word.chars().next() else {
+            return Ok(None);
+        };
+        let upper = char::from_u32(first as u32 + 1).unwrap_or(first).to_string();
+        let searcher = self.reader.searcher();
+        let mut doc_freqs: HashMap<String, u64> = HashMap::new();
+        for segment in searcher.segment_readers() {
+            let inverted_index = segment.inverted_index(self.fields.spelling)?;
+            let terms = inverted_index.terms();
+            if terms.get(word.as_bytes())?.is_some() {
+                return Ok(None);
+            }
+            let mut stream = terms
+                .range()
+                .ge(first.to_string())
+                .lt(&upper)
+                .into_stream()?;
+            while stream.advance() {
+                let Ok(term) = std::str::from_utf8(stream.key()) else {
+                    continue;
+                };
+                if term.len().abs_diff(word.len()) > budget as usize
+                    || !term.chars().all(|ch| ch.is_ascii_alphanumeric())
+                {
+                    continue;
+                }
+                *doc_freqs.entry(term.to_string()).or_default() +=
+                    u64::from(stream.value().doc_freq);
+            }
+        }
+        Ok(doc_freqs
+            .into_iter()
+            .map(|(term, doc_freq)| (edit_distance(&word, &term), doc_freq, term))
+            .filter(|(distance, _, _)| *distance <= budget as usize)
+            .min_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)).then_with(|| a.2.cmp(&b.2)))
+            .map(|(_, _, term)| term))
+    }
+
+    pub fn related(
+        &self,
+        target: DocumentRef<'_>,
+        limit: usize,
+        recency: bool,
+    ) -> Result<Option<Vec<SearchHit>>, SearchIndexError> {
+        let searcher = self.reader.searcher();
+        let (field, value) = match target {
+            DocumentRef::Id(value) => (self.fields.id, value),
+            DocumentRef::Url(value) => (self.fields.url, value),
+        };
+        let lookup = TermQuery::new(Term::from_field_text(field, value), IndexRecordOption::Basic);
+        let Some((_, address)) = searcher
+            .search(&lookup, &TopDocs::with_limit(1))?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        let doc: TantivyDocument = searcher.doc(address)?;
+        let id = get_string(&doc, self.fields.id).ok_or(SearchIndexError::MissingValue("id"))?;
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        let text_fields = [self.fields.title, self.fields.subtitle, self.fields.content]
+            .into_iter()
+            .map(|field| {
+                let values = get_strings(&doc, field)
+                    .into_iter()
+                    .map(OwnedValue::Str)
+                    .collect::<Vec<_>>();
+                (field, values)
+            })
+            .filter(|(_, values)| !values.is_empty())
+            .collect::<Vec<_>>();
+        if !text_fields.is_empty() {
+            let query = MoreLikeThisQuery::builder()
+                .with_min_doc_frequency(2)
+                .with_min_term_frequency(1)
+                .with_min_word_length(2)
+                .with_max_query_terms(RELATED_MAX_QUERY_TERMS)
+                .with_document_fields(text_fields);
+            clauses.push((Occur::Should, Box::new(query)));
+        }
+        for tag in get_strings(&doc, self.fields.tags) {
+            let query = TermQuery::new(
+                Term::from_field_text(self.fields.tags, &tag),
+                IndexRecordOption::Basic,
+            );
+            clauses.push((
+                Occur::Should,
+                Box::new(BoostQuery::new(Box::new(query), RELATED_TAG_BOOST)),
+            ));
+        }
+        if let Some(category) = get_string(&doc, self.fields.category) {
+            let query = TermQuery::new(
+                Term::from_field_text(self.fields.category, &category),
+                IndexRecordOption::Basic,
+            );
+            clauses.push((
+                Occur::Should,
+                Box::new(BoostQuery::new(Box::new(query), RELATED_CATEGORY_BOOST)),
+            ));
         }
+        if clauses.is_empty() || limit == 0 {
+            return Ok(Some(Vec::new()));
+        }
+        clauses.push((
+            Occur::MustNot,
+            Box::new(TermQuery::new(
+                Term::from_field_text(self.fields.id, &id),
+                IndexRecordOption::Basic,
+            )),
+        ));
+        let query = BooleanQuery::new(clauses);
+
+        let addresses: Vec<DocAddress> = if recency {
+            let collector = TopDocs::with_limit(limit).tweak_score(
+                |segment_reader: &SegmentReader| {
+                    let updated = segment_reader.fast_fields().i64("updated").ok();
+                    move |doc: DocId, score: Score| {
+                        let updated = updated
+                            .as_ref()
+                            .and_then(|column| column.first(doc))
+                            .unwrap_or(0);
+                        (score, updated)
+                    }
+                },
+            );
+            searcher
+                .search(&query, &collector)?
+                .into_iter()
+                .map(|(_, address)| address)
+                .collect()
+        } else {
+            searcher
+                .search(&query, &TopDocs::with_limit(limit))?
+                .into_iter()
+                .map(|(_, address)| address)
+                .collect()
+        };
 
-        Ok(SearchResult { total, hits })
+        let mut hits = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            hits.push(self.document_to_hit(&doc, None)?);
+        }
+        Ok(Some(hits))
     }
 
-    pub fn stats(&self) -> SearchIndexStats {
+    pub fn stats(&self) -> Result<SearchIndexStats, SearchIndexError> {
         let searcher = self.reader.searcher();
-        SearchIndexStats {
+        Ok(SearchIndexStats {
             num_docs: searcher.num_docs(),
+            num_sections: self.sections.reader.searcher().num_docs(),
             num_segments: searcher.segment_readers().len(),
-        }
+        })
     }
 
     pub fn get_checksum(&self, id: &str) -> Result<Option<String>, SearchIndexError> {
@@ -168,19 +895,105 @@ impl SearchIndex {
 
     pub fn upsert_documents(&self, documents: &[SearchDocument]) -> Result<(), SearchIndexError> {
         let mut writer = self.index.writer::<TantivyDocument>(50_000_000)?;
+        let section_writer = self.sections.index.writer::<TantivyDocument>(50_000_000)?;
+        let parent = self.sections.fields.parent;
         for doc in documents {
             writer.delete_term(Term::from_field_text(self.fields.id, &doc.id));
+            section_writer.delete_term(Term::from_field_text(parent, &doc.id));
             writer.add_document(self.domain_to_document(doc))?;
+            for section in &doc.sections {
+                section_writer.add_document(self.section_to_document(&doc.id, section))?;
+            }
         }
-        writer.commit()?;
+        let opstamp = writer.commit()?;
+        self.commit_sections(section_writer, opstamp)?;
+        self.sections.reader.reload()?;
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    fn commit_sections(
+        &self,
+        mut writer: IndexWriter<TantivyDocument>,
+        opstamp: Opstamp,
+    ) -> Result<(), SearchIndexError> {
+        let committed = writer.prepare_commit().and_then(|mut prepared| {
+            prepared.set_payload(&opstamp.to_string());
+            prepared.commit()
+        });
+        if committed.is_err() {
+            self.sections_stale.store(true, Ordering::Relaxed);
+        }
+        committed?;
+        Ok(())
+    }
+
+    pub fn list_urls_by_source(&self) -> Result<HashMap<String, Vec<String>>, SearchIndexError> {
+        let searcher = self.reader.searcher();
+        let addresses = searcher.search(&AllQuery, &DocSetCollector)?;
+        let mut urls = HashMap::<String, Vec<String>>::new();
+        for address in addresses {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let url =
+                get_string(&doc, self.fields.url).ok_or(SearchIndexError::MissingValue("url"))?;
+            let source = get_string(&doc, self.fields.source).unwrap_or_default();
+            urls.entry(source).or_default().push(url);
+        }
+        Ok(urls)
+    }
+
+    pub fn list_ids(&self) -> Result<HashSet<String>, SearchIndexError> {
+        let searcher = self.reader.searcher();
+        let addresses = searcher.search(&AllQuery, &DocSetCollector)?;
+        let mut ids = HashSet::with_capacity(addresses.len());
+        for address in addresses {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let id =
+                get_string(&doc, self.fields.id).ok_or(SearchIndexError::MissingValue("id"))?;
+            ids.insert(id);
+        }
+        Ok(ids)
+    }
+
+    pub fn list_ids_by_source(&self) -> Result<HashMap<String, HashSet<String>>, SearchIndexError> {
+        let searcher = self.reader.searcher();
+        let addresses = searcher.search(&AllQuery, &DocSetCollector)?;
+        let mut ids = HashMap::<String, HashSet<String>>::new();
+        for address in addresses {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let id =
+                get_string(&doc, self.fields.id).ok_or(SearchIndexError::MissingValue("id"))?;
+            let source = get_string(&doc, self.fields.source).unwrap_or_default();
+            ids.entry(source).or_default().insert(id);
+        }
+        Ok(ids)
+    }
+
+    pub fn delete_documents(&self, ids: &[String]) -> Result<(), SearchIndexError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut writer = self.index.writer::<TantivyDocument>(50_000_000)?;
+        let section_writer = self.sections.index.writer::<TantivyDocument>(50_000_000)?;
+        for id in ids {
+            writer.delete_term(Term::from_field_text(self.fields.id, id));
+            section_writer.delete_term(Term::from_field_text(self.sections.fields.parent, id));
+        }
+        let opstamp = writer.commit()?;
+        self.commit_sections(section_writer, opstamp)?;
+        self.sections.reader.reload()?;
         self.reader.reload()?;
         Ok(())
     }
 
     pub fn delete_all(&self) -> Result<(), SearchIndexError> {
         let mut writer = self.index.writer::<TantivyDocument>(50_000_000)?;
+        let section_writer = self.sections.index.writer::<TantivyDocument>(50_000_000)?;
         writer.delete_all_documents()?;
-        writer.commit()?;
+        section_writer.delete_all_documents()?;
+        let opstamp = writer.commit()?;
+        self.commit_sections(section_writer, opstamp)?;
+        self.sections.reader.reload()?;
         self.reader.reload()?;
         Ok(())
     }
@@ -196,88 +1009,216 @@ impl SearchIndex {
         document.add_text(self.fields.url, &doc.url);
         for tag in &doc.tags {
             document.add_text(self.fields.tags, tag);
+            document.add_text(self.fields.tag_lookup, lookup_term(tag));
         }
         if let Some(category) = &doc.category {
             document.add_text(self.fields.category, category);
+            document.add_text(self.fields.category_lookup, lookup_term(category));
         }
+        document.add_text(self.fields.source, &doc.source);
         document.add_i64(self.fields.published, doc.published_at.timestamp());
         document.add_i64(self.fields.updated, doc.updated_at.timestamp());
         document.add_text(self.fields.checksum, &doc.checksum);
+        for field in [self.fields.pinyin, self.fields.pinyin_initials] {
+            document.add_text(field, &doc.title);
+            for tag in &doc.tags {
+                document.add_text(field, tag);
+            }
+        }
+        document.add_text(self.fields.spelling, &doc.title);
+        if let Some(subtitle) = &doc.subtitle {
+            document.add_text(self.fields.spelling, subtitle);
+        }
+        document.add_text(self.fields.spelling, &doc.content);
+        for tag in &doc.tags {
+            document.add_text(self.fields.spelling, tag);
+        }
+        document
+    }
+
+    fn section_to_document(&self, parent: &str, section: &SearchSection) -> TantivyDocument {
+        let fields = &self.sections.fields;
+        let mut document = TantivyDocument::default();
+        document.add_text(fields.parent, parent);
+        document.add_text(fields.heading, &section.heading);
+        document.add_text(fields.content, &section.content);
+        document.add_text(fields.anchor, &section.anchor);
         document
     }
 
+    // Ranks the sections of the hits on this page by the query's terms, any of
+    // which may match, and keeps the best few under each hit.
+    fn attach_sections(
+        &self,
+        query: &SearchQuery,
+        options: &SearchOptions,
+        hit_ids: &[String],
+        hits: &mut [SearchHit],
+    ) -> Result<(), SearchIndexError> {
+        if hits.is_empty() {
+            return Ok(());
+        }
+        let fields = &self.sections.fields;
+        let terms_query =
+            build_section_terms_query(&self.sections.index, fields, query, options)?;
+        let Some(terms_query) = terms_query else {
+            return Ok(());
+        };
+        let searcher = self.sections.reader.searcher();
+        let parents =
+            TermSetQuery::new(hit_ids.iter().map(|id| Term::from_field_text(fields.parent, id)));
+        let section_query = BooleanQuery::new(vec![
+            (Occur::Must, Box::new(parents) as Box<dyn Query>),
+            (Occur::Must, terms_query.box_clone()),
+        ]);
+        let limit = hits.len().saturating_mul(SECTION_CANDIDATES_PER_HIT);
+        let top = searcher.search(&section_query, &TopDocs::with_limit(limit))?;
+        if top.is_empty() {
+            return Ok(());
+        }
+        let mut generator = SnippetGenerator::create(&searcher, &*terms_query, fields.content)?;
+        generator.set_max_num_chars(options.snippets.max_chars);
+
+        let mut grouped: HashMap<String, Vec<SectionHit>> = HashMap::new();
+        for (_, address) in top {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let parent = get_string(&doc, fields.parent)
+                .ok_or(SearchIndexError::MissingValue("parent"))?;
+            let sections = grouped.entry(parent).or_default();
+            if sections.len() >= MAX_SECTIONS_PER_HIT {
+                continue;
+            }
+            let heading = get_string(&doc, fields.heading).unwrap_or_default();
+            let anchor = get_string(&doc, fields.anchor)
+                .ok_or(SearchIndexError::MissingValue("anchor"))?;
+            let text = field_text(&doc, fields.content);
+            let fragments = snippet_fragments(&generator, &text, 1);
+            let (content, highlights) = match (fragments.first(), options.highlight) {
+                (Some(snippet), HighlightMode::Ranges) => (
+                    Some(snippet.fragment().trim().to_string()),
+                    highlight_fragments(&fragments),
+                ),
+                (Some(snippet), HighlightMode::Html) => {
+                    (Some(snippet.to_html().trim().to_string()), Vec::new())
+                }
+                (None, _) => (excerpt(&text, EXCERPT_CHARS), Vec::new()),
+            };
+            sections.push(SectionHit {
+                heading,
+                anchor,
+                url: String::new(),
+                content,
+                highlights,
+            });
+        }
+        for (id, hit) in hit_ids.iter().zip(hits) {
+            let Some(mut sections) = grouped.remove(id) else {
+                continue;
+            };
+            for section in &mut sections {
+                section.url = format!("{}#{}", hit.url, section.anchor);
+            }
+            hit.sections = sections;
+        }
+        Ok(())
+    }
+
     fn document_to_hit(
         &self,
         doc: &TantivyDocument,
-        title_snippet: Option<&SnippetGenerator>,
-        subtitle_snippet: Option<&SnippetGenerator>,
-        content_snippet: Option<&SnippetGenerator>,
+        highlighter: Option<&Highlighter>,
     ) -> Result<SearchHit, SearchIndexError> {
         let title = get_string(doc, self.fields.title).ok_or(SearchIndexError::MissingValue("title"))?;
         let url = get_string(doc, self.fields.url).ok_or(SearchIndexError::MissingValue("url"))?;
         let id = url.clone();
         let tags = get_strings(doc, self.fields.tags);
         let category = get_string(doc, self.fields.category);
+        let source = get_string(doc, self.fields.source).unwrap_or_default();
         let published = get_i64(doc, self.fields.published)
             .ok_or(SearchIndexError::MissingValue("published"))?;
         let updated = get_i64(doc, self.fields.updated)
             .ok_or(SearchIndexError::MissingValue("updated"))?;
 
+        let (title, subtitle, content, highlights) = match highlighter {
+            Some(highlighter) if highlighter.mode == HighlightMode::Ranges => {
+                let subtitle = get_string(doc, self.fields.subtitle)
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty());
+                let content = field_text(doc, self.fields.content);
+                let content_fragments =
+                    snippet_fragments(&highlighter.content, &content, highlighter.fragments);
+                let highlights = SearchHighlights {
+                    title: highlight_fragments(&snippet_fragments(&highlighter.title, &title, 1)),
+                    subtitle: subtitle
+                        .as_deref()
+                        .map(|value| {
+                            highlight_fragments(&snippet_fragments(&highlighter.subtitle, value, 1))
+                        })
+                        .unwrap_or_default(),
+                    content: highlight_fragments(&content_fragments),
+                };
+                let content = if content_fragments.is_empty() {
+                    excerpt(&content, EXCERPT_CHARS)
+                } else {
+                    Some(
+                        content_fragments
+                            .iter()
+                            .map(|snippet| snippet.fragment().trim())
+                            .collect::<Vec<_>>()
+                            .join(FRAGMENT_SEPARATOR),
+                    )
+                };
+                (title, subtitle, content, Some(highlights))
+            }
+            _ => {
+                let title_snippet = highlighter.map(|highlighter| &highlighter.title);
+                let subtitle_snippet = highlighter.map(|highlighter| &highlighter.subtitle);
+                let content = match highlighter {
+                    Some(highlighter) => {
+                        let text = field_text(doc, self.fields.content);
+                        let fragments =
+                            snippet_fragments(&highlighter.content, &text, highlighter.fragments);
+                        let html = fragments
+                            .iter()
+                            .map(|snippet| snippet.to_html().trim().to_string())
+                            .collect::<Vec<_>>()
+                            .join(FRAGMENT_SEPARATOR);
+                        if html.is_empty() {
+                            excerpt(&text, EXCERPT_CHARS)
+                        } else {
+                            Some(html)
+                        }
+                    }
+                    None => snippet_or_excerpt(None, doc, self.fields.content, EXCERPT_CHARS),
+                };
+                (
+                    snippet_or_excerpt(title_snippet, doc, self.fields.title, EXCERPT_CHARS)
+                        .unwrap_or(title),
+                    snippet_html(subtitle_snippet, doc),
+                    content,
+                    None,
+                )
+            }
+        };
+
         Ok(SearchHit {
             id,
-            title: snippet_or_excerpt(title_snippet, doc, self.fields.title, 120)
-                .unwrap_or(title),
-            subtitle: snippet_html(subtitle_snippet, doc),
-            content: snippet_or_excerpt(content_snippet, doc, self.fields.content, 120),
+            title,
+            subtitle,
+            content,
             url,
             tags,
             category,
+            source,
             published_at: timestamp_to_datetime(published, "published")?,
             updated_at: timestamp_to_datetime(updated, "updated")?,
+            highlights,
+            explanation: None,
+            sections: Vec::new(),
         })
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::SearchIndex;
-    use inkstone_core::domain::search::SearchDocument;
-    use std::fs;
-    use std::path::PathBuf;
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    fn temp_dir(name: &str) -> PathBuf {
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        std::env::temp_dir().join(format!("{name}-{nanos}"))
-    }
-
-    #[test]
-    fn stats_reflect_indexed_docs() {
-        let dir = temp_dir("inkstone-search-stats");
-        fs::create_dir_all(&dir).unwrap();
-        let index = SearchIndex::open_or_create(&dir).unwrap();
-        let doc = SearchDocument {
-            id: "doc-1".to_string(),
-            title: "Hello".to_string(),
-            subtitle: None,
-            content: "World".to_string(),
-            url: "https://example.com/posts/hello".to_string(),
-            tags: vec![],
-            category: None,
-            published_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-            checksum: "checksum".to_string(),
-        };
-        index.upsert_documents(&[doc]).unwrap();
-        let stats = index.stats();
-        assert_eq!(stats.num_docs, 1);
-        let _ = fs::remove_dir_all(&dir);
-    }
-}
-
 impl SearchFields {
     fn from_schema(schema: &Schema) -> Result<Self, SearchIndexError> {
         Ok(Self {
@@ -302,6 +1243,9 @@ impl SearchFields {
             category: schema
                 .get_field("category")
                 .map_err(|_| SearchIndexError::MissingField("category"))?,
+            source: schema
+                .get_field("source")
+                .map_err(|_| SearchIndexError::MissingField("source"))?,
             published: schema
                 .get_field("published")
                 .map_err(|_| SearchIndexError::MissingField("published"))?,
@@ -311,6 +1255,40 @@ impl SearchFields {
             checksum: schema
                 .get_field("checksum")
                 .map_err(|_| SearchIndexError::MissingField("checksum"))?,
+            pinyin: schema
+                .get_field("pinyin")
+                .map_err(|_| SearchIndexError::MissingField("pinyin"))?,
+            pinyin_initials: schema
+                .get_field("pinyin_initials")
+                .map_err(|_| SearchIndexError::MissingField("pinyin_initials"))?,
+            spelling: schema
+                .get_field("spelling")
+                .map_err(|_| SearchIndexError::MissingField("spelling"))?,
+            tag_lookup: schema
+                .get_field("tag_lookup")
+                .map_err(|_| SearchIndexError::MissingField("tag_lookup"))?,
+            category_lookup: schema
+                .get_field("category_lookup")
+                .map_err(|_| SearchIndexError::MissingField("category_lookup"))?,
+        })
+    }
+}
+
+impl SectionFields {
+    fn from_schema(schema: &Schema) -> Result<Self, SearchIndexError> {
+        Ok(Self {
+            parent: schema
+                .get_field("parent")
+                .map_err(|_| SearchIndexError::MissingField("parent"))?,
+            heading: schema
+                .get_field("heading")
+                .map_err(|_| SearchIndexError::MissingField("heading"))?,
+            content: schema
+                .get_field("content")
+                .map_err(|_| SearchIndexError::MissingField("content"))?,
+            anchor: schema
+                .get_field("anchor")
+                .map_err(|_| SearchIndexError::MissingField("anchor"))?,
         })
     }
 }
@@ -321,12 +1299,31 @@ fn build_schema() -> Schema {
     builder.add_text_field("title", jieba_text_options(true));
     builder.add_text_field("subtitle", jieba_text_options(true));
     builder.add_text_field("content", jieba_text_options(true));
-    builder.add_text_field("url", STRING | STORED);
-    builder.add_text_field("tags", STRING | STORED);
-    builder.add_text_field("category", STRING | STORED);
+    builder.add_text_field("url", STRING | STORED | FAST);
+    builder.add_text_field("tags", STRING | STORED | FAST);
+    builder.add_text_field("category", STRING | STORED | FAST);
+    builder.add_text_field("source", STRING | STORED | FAST);
     builder.add_i64_field("published", STORED | FAST);
     builder.add_i64_field("updated", STORED | FAST);
     builder.add_text_field("checksum", STRING | STORED);
+    builder.add_text_field("pinyin", pinyin_text_options("pinyin"));
+    builder.add_text_field("pinyin_initials", pinyin_text_options("pinyin_initials"));
+    let spelling = TextFieldIndexing::default()
+        .set_tokenizer("jieba_plain")
+        .set_index_option(IndexRecordOption::Basic);
+    builder.add_text_field("spelling", TextOptions::default().set_indexing_options(spelling));
+    // Lowercased copies of tags and categories, see `lookup_term`.
+    builder.add_text_field("tag_lookup", STRING);
+    builder.add_text_field("category_lookup", STRING);
+    builder.build()
+}
+
+fn build_section_schema() -> Schema {
+    let mut builder = SchemaBuilder::default();
+    builder.add_text_field("parent", STRING | STORED);
+    builder.add_text_field("heading", jieba_text_options(true));
+    builder.add_text_field("content", jieba_text_options(true));
+    builder.add_text_field("anchor", STORED);
     builder.build()
 }
 
@@ -342,24 +1339,43 @@ fn jieba_text_options(stored: bool) -> TextOptions {
     }
 }
 
-fn register_jieba_tokenizer(index: &Index) {
-    let tokenizer = tantivy_jieba::JiebaTokenizer {};
-    let analyzer = TextAnalyzer::builder(tokenizer)
-        .filter(RemoveLongFilter::limit(40))
-        .filter(LowerCaser)
-        .filter(Stemmer::default())
-        .build();
-    index.tokenizers().register("jieba", analyzer);
+fn pinyin_text_options(tokenizer: &str) -> TextOptions {
+    let indexing = TextFieldIndexing::default()
+        .set_tokenizer(tokenizer)
+        .set_index_option(IndexRecordOption::WithFreqs);
+    TextOptions::default().set_indexing_options(indexing)
+}
+
+fn register_tokenizers(index: &Index, tokenizer: &SearchTokenizer) {
+    let tokenizers = index.tokenizers();
+    tokenizers.register("jieba", tokenizer.analyzer());
+    tokenizers.register("jieba_plain", tokenizer.plain_analyzer());
+    tokenizers.register("pinyin", tokenizer.pinyin_analyzer(PinyinStyle::Full));
+    tokenizers.register(
+        "pinyin_initials",
+        tokenizer.pinyin_analyzer(PinyinStyle::Initials),
+    );
 }
 
 fn build_query(
     index: &Index,
     fields: &SearchFields,
     query: &SearchQuery,
+    options: &SearchOptions,
+) -> Result<BuiltQuery, SearchIndexError> {
+    build_query_matching(index, fields, query, options, KeywordMatch::Exact)
+}
+
+fn build_query_matching(
+    index: &Index,
+    fields: &SearchFields,
+    query: &SearchQuery,
+    options: &SearchOptions,
+    matching: KeywordMatch,
 ) -> Result<BuiltQuery, SearchIndexError> {
     let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
 
-    let keyword_query = build_keyword_query(index, fields, query)?;
+    let keyword_query = build_keyword_query(index, fields, query, options, matching)?;
     if let Some(keyword_query) = keyword_query.as_ref() {
         clauses.push((Occur::Must, keyword_query.box_clone()));
     }
@@ -378,95 +1394,455 @@ fn build_query(
         clauses.push((Occur::Must, Box::new(category_query)));
     }
 
+    if let Some(source) = &query.source {
+        let term = Term::from_field_text(fields.source, source);
+        let source_query = TermQuery::new(term, IndexRecordOption::Basic);
+        clauses.push((Occur::Must, Box::new(source_query)));
+    }
+
     if let Some(range) = &query.range {
         let (start, end) = range.to_timestamp_bounds();
         if start.is_some() || end.is_some() {
-            let published_query = build_range_query(fields.published, start, end);
-            let updated_query = build_range_query(fields.updated, start, end);
-            let range_query = BooleanQuery::from(vec![
-                (Occur::Should, published_query),
-                (Occur::Should, updated_query),
-            ]);
-            clauses.push((Occur::Must, Box::new(range_query)));
+            let range_query: Box<dyn Query> = match range.field {
+                DateField::Any => Box::new(BooleanQuery::from(vec![
+                    (Occur::Should, build_range_query(fields.published, start, end)),
+                    (Occur::Should, build_range_query(fields.updated, start, end)),
+                ])),
+                DateField::Published => build_range_query(fields.published, start, end),
+                DateField::Updated => build_range_query(fields.updated, start, end),
+            };
+            clauses.push((Occur::Must, range_query));
         }
     }
 
-    let query: Box<dyn Query> = if clauses.is_empty() {
-        Box::new(AllQuery)
-    } else {
-        Box::new(BooleanQuery::from(clauses))
-    };
+    // A boolean query made only of MustNot clauses matches nothing.
+    if clauses.is_empty() {
+        clauses.push((Occur::Must, Box::new(AllQuery)));
+    }
+    let excluded = build_excluded_clauses(index, fields, query, options)?;
+    clauses.extend(excluded.into_iter().map(|query| (Occur::MustNot, query)));
+
+    let query: Box<dyn Query> = Box::new(BooleanQuery::from(clauses));
     Ok(BuiltQuery { query, keyword: keyword_query })
 }
 
-struct BuiltQuery {
-    query: Box<dyn Query>,
-    keyword: Option<Box<dyn Query>>,
-}
+// Every keyword and phrase of the query, and their synonyms, as optional
+// matches on section headings and text.
+fn build_section_terms_query(
+    index: &Index,
+    fields: &SectionFields,
+    query: &SearchQuery,
+    options: &SearchOptions,
+) -> Result<Option<Box<dyn Query>>, SearchIndexError> {
+    let Some(expr) = query.keyword_expr() else {
+        return Ok(None);
+    };
+    let mut analyzer = index
+        .tokenizers()
+        .get("jieba")
+        .ok_or(SearchIndexError::MissingTokenizer("jieba"))?;
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    for term in expr.terms() {
+        let synonyms = options
+            .synonyms
+            .as_deref()
+            .map(|synonyms| synonyms.expand(term))
+            .unwrap_or_default();
+        for alternative in std::iter::once(term).chain(synonyms) {
+            let tokens = tokenize_keyword(&mut analyzer, alternative);
+            if let Some(query) = build_field_query(fields.heading, &tokens) {
+                clauses.push((Occur::Should, boosted(query, options.boosts.title)));
+            }
+            if let Some(query) = build_field_query(fields.content, &tokens) {
+                clauses.push((Occur::Should, boosted(query, options.boosts.content)));
+            }
+        }
+    }
+    if clauses.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Box::new(BooleanQuery::new(clauses))))
+}
+
+struct Highlighter {
+    title: SnippetGenerator,
+    subtitle: SnippetGenerator,
+    content: SnippetGenerator,
+    mode: HighlightMode,
+    fragments: usize,
+}
+
+struct BuiltQuery {
+    query: Box<dyn Query>,
+    keyword: Option<Box<dyn Query>>,
+}
+
+// `Fuzzy` adds typo-tolerant alternatives for Latin-script keywords; `search`
+// only uses it when exact matching finds nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeywordMatch {
+    Exact,
+    Fuzzy,
+}
 
 fn build_keyword_query(
     index: &Index,
     fields: &SearchFields,
     query: &SearchQuery,
+    options: &SearchOptions,
+    matching: KeywordMatch,
 ) -> Result<Option<Box<dyn Query>>, SearchIndexError> {
-    if query.keywords.is_empty() {
+    let Some(expr) = query.keyword_expr() else {
         return Ok(None);
-    }
+    };
     let mut analyzer = index
         .tokenizers()
         .get("jieba")
         .ok_or(SearchIndexError::MissingTokenizer("jieba"))?;
-    let mut keyword_queries = Vec::new();
-    for keyword in &query.keywords {
-        let tokens = tokenize_keyword(&mut analyzer, keyword);
-        let title_query = build_field_query(fields.title, &tokens);
-        let subtitle_query = build_field_query(fields.subtitle, &tokens);
-        let content_query = build_field_query(fields.content, &tokens);
-        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
-        if let Some(query) = title_query {
-            clauses.push((Occur::Should, query));
+    // A query made only of stop words keeps its raw tag/category matches.
+    let expr = without_stop_words(&mut analyzer, &expr).unwrap_or(expr);
+    Ok(Some(build_expr_query(
+        &mut analyzer,
+        fields,
+        options,
+        matching,
+        &expr,
+    )))
+}
+
+// Stop words analyze to no tokens, so only their raw tag/category clauses would
+// remain and a `Must` on them would reject every document.
+fn without_stop_words(analyzer: &mut TextAnalyzer, expr: &KeywordExpr) -> Option<KeywordExpr> {
+    match expr {
+        KeywordExpr::Keyword(keyword) => {
+            (!tokenize_keyword(analyzer, keyword).is_empty()).then(|| expr.clone())
         }
-        if let Some(query) = subtitle_query {
-            clauses.push((Occur::Should, query));
+        KeywordExpr::Phrase(_) => Some(expr.clone()),
+        KeywordExpr::And(items) | KeywordExpr::Or(items) => {
+            let mut kept = items
+                .iter()
+                .filter_map(|item| without_stop_words(analyzer, item))
+                .collect::<Vec<_>>();
+            match kept.len() {
+                0 => None,
+                1 => kept.pop(),
+                _ if matches!(expr, KeywordExpr::And(_)) => Some(KeywordExpr::And(kept)),
+                _ => Some(KeywordExpr::Or(kept)),
+            }
         }
-        if let Some(query) = content_query {
-            clauses.push((Occur::Should, query));
+    }
+}
+
+fn build_expr_query(
+    analyzer: &mut TextAnalyzer,
+    fields: &SearchFields,
+    options: &SearchOptions,
+    matching: KeywordMatch,
+    expr: &KeywordExpr,
+) -> Box<dyn Query> {
+    let (occur, items) = match expr {
+        // Phrases tokenize into several positioned terms, so build_field_query
+        // turns them into PhraseQuery just like multi-token keywords.
+        KeywordExpr::Keyword(keyword) | KeywordExpr::Phrase(keyword) => {
+            return build_synonym_clause(analyzer, fields, options, matching, keyword)
+                .unwrap_or_else(|| Box::new(EmptyQuery));
         }
-        if !keyword.is_empty() {
-            let tag_query = TermQuery::new(
-                Term::from_field_text(fields.tags, keyword),
-                IndexRecordOption::Basic,
-            );
-            clauses.push((Occur::Should, Box::new(tag_query)));
-            let category_query = TermQuery::new(
-                Term::from_field_text(fields.category, keyword),
+        KeywordExpr::And(items) => (Occur::Must, items),
+        KeywordExpr::Or(items) => (Occur::Should, items),
+    };
+    let clauses = items
+        .iter()
+        .map(|item| {
+            let query = build_expr_query(analyzer, fields, options, matching, item);
+            (occur, query)
+        })
+        .collect::<Vec<_>>();
+    Box::new(BooleanQuery::new(clauses))
+}
+
+// A keyword and each of its synonyms are interchangeable alternatives.
+fn build_synonym_clause(
+    analyzer: &mut TextAnalyzer,
+    fields: &SearchFields,
+    options: &SearchOptions,
+    matching: KeywordMatch,
+    keyword: &str,
+) -> Option<Box<dyn Query>> {
+    let synonyms = options
+        .synonyms
+        .as_deref()
+        .map(|synonyms| synonyms.expand(keyword))
+        .unwrap_or_default();
+    let mut alternatives = std::iter::once(keyword)
+        .chain(synonyms)
+        .filter_map(|term| build_keyword_clause(analyzer, fields, &options.boosts, matching, term))
+        .collect::<Vec<_>>();
+    if alternatives.len() <= 1 {
+        return alternatives.pop();
+    }
+    let clauses = alternatives
+        .into_iter()
+        .map(|query| (Occur::Should, query))
+        .collect::<Vec<_>>();
+    Some(Box::new(BooleanQuery::new(clauses)))
+}
+
+fn build_keyword_clause(
+    analyzer: &mut TextAnalyzer,
+    fields: &SearchFields,
+    boosts: &FieldBoosts,
+    matching: KeywordMatch,
+    keyword: &str,
+) -> Option<Box<dyn Query>> {
+    let tokens = tokenize_keyword(analyzer, keyword);
+    let title_query = build_field_query(fields.title, &tokens);
+    let subtitle_query = build_field_query(fields.subtitle, &tokens);
+    let content_query = build_field_query(fields.content, &tokens);
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    if let Some(query) = title_query {
+        clauses.push((Occur::Should, boosted(query, boosts.title)));
+    }
+    if let Some(query) = subtitle_query {
+        clauses.push((Occur::Should, boosted(query, boosts.subtitle)));
+    }
+    if let Some(query) = content_query {
+        clauses.push((Occur::Should, boosted(query, boosts.content)));
+    }
+    // Typos are matched against unstemmed words: `postgress` is one edit from
+    // `postgres` but three from its stem `postgr`.
+    if matching == KeywordMatch::Fuzzy
+        && let Some(distance) = fuzzy_distance(keyword)
+    {
+        let term = Term::from_field_text(fields.spelling, &keyword.to_ascii_lowercase());
+        let fuzzy_query = FuzzyTermQuery::new(term, distance, true);
+        clauses.push((Occur::Should, boosted(Box::new(fuzzy_query), FUZZY_BOOST)));
+    }
+    if !keyword.is_empty() {
+        let tag_query = TermQuery::new(
+            Term::from_field_text(fields.tags, keyword),
+            IndexRecordOption::Basic,
+        );
+        clauses.push((Occur::Should, boosted(Box::new(tag_query), boosts.tags)));
+        let category_query = TermQuery::new(
+            Term::from_field_text(fields.category, keyword),
+            IndexRecordOption::Basic,
+        );
+        clauses.push((
+            Occur::Should,
+            boosted(Box::new(category_query), boosts.category),
+        ));
+    }
+    if is_pinyin_keyword(keyword) {
+        let keyword = keyword.to_ascii_lowercase();
+        for field in [fields.pinyin, fields.pinyin_initials] {
+            let pinyin_query = TermQuery::new(
+                Term::from_field_text(field, &keyword),
                 IndexRecordOption::Basic,
             );
-            clauses.push((Occur::Should, Box::new(category_query)));
+            clauses.push((Occur::Should, boosted(Box::new(pinyin_query), PINYIN_BOOST)));
         }
-        let keyword_query = if clauses.is_empty() {
+    }
+    if clauses.is_empty() {
+        None
+    } else {
+        Some(Box::new(BooleanQuery::new(clauses)))
+    }
+}
+
+fn boosted(query: Box<dyn Query>, boost: f32) -> Box<dyn Query> {
+    if boost == 1.0 {
+        query
+    } else {
+        Box::new(BoostQuery::new(query, boost))
+    }
+}
+
+fn build_excluded_clauses(
+    index: &Index,
+    fields: &SearchFields,
+    query: &SearchQuery,
+    options: &SearchOptions,
+) -> Result<Vec<Box<dyn Query>>, SearchIndexError> {
+    let mut queries: Vec<Box<dyn Query>> = Vec::new();
+    if !query.excluded_keywords.is_empty() {
+        let mut analyzer = index
+            .tokenizers()
+            .get("jieba")
+            .ok_or(SearchIndexError::MissingTokenizer("jieba"))?;
+        for keyword in &query.excluded_keywords {
+            if let Some(keyword_query) =
+                build_synonym_clause(&mut analyzer, fields, options, KeywordMatch::Exact, keyword)
+            {
+                queries.push(keyword_query);
+            }
+        }
+    }
+    for tag in &query.excluded_tags {
+        let term = Term::from_field_text(fields.tags, tag);
+        let tag_query = TermQuery::new(term, IndexRecordOption::Basic);
+        queries.push(Box::new(tag_query));
+    }
+    for category in &query.excluded_categories {
+        let term = Term::from_field_text(fields.category, category);
+        let category_query = TermQuery::new(term, IndexRecordOption::Basic);
+        queries.push(Box::new(category_query));
+    }
+    for source in &query.excluded_sources {
+        let term = Term::from_field_text(fields.source, source);
+        let source_query = TermQuery::new(term, IndexRecordOption::Basic);
+        queries.push(Box::new(source_query));
+    }
+    Ok(queries)
+}
+
+fn segment_ids(searcher: &Searcher) -> Vec<SegmentId> {
+    searcher
+        .segment_readers()
+        .iter()
+        .map(|segment_reader| segment_reader.segment_id())
+        .collect()
+}
+
+fn segment_ord(segment_ids: &[SegmentId], segment_reader: &SegmentReader) -> u32 {
+    segment_ids
+        .iter()
+        .position(|id| *id == segment_reader.segment_id())
+        .unwrap_or_default() as u32
+}
+
+// Top docs are ordered by descending sort key, then ascending address.
+fn is_after<T: PartialOrd>(
+    value: T,
+    address: DocAddress,
+    after_value: T,
+    after: DocAddress,
+) -> bool {
+    value < after_value || (value == after_value && address > after)
+}
+
+// Halves every SMART_HALF_LIFE_DAYS, so a fresh post scores up to twice its BM25.
+fn recency_decay(age_secs: i64) -> f32 {
+    let age_days = age_secs.max(0) as f32 / 86_400.0;
+    0.5_f32.powf(age_days / SMART_HALF_LIFE_DAYS)
+}
+
+// Popularity of each scored doc, looked up by its `url` fast field.
+struct SegmentPopularity {
+    urls: Option<StrColumn>,
+    popularity: Arc<PopularityScores>,
+    url: String,
+}
+
+impl SegmentPopularity {
+    fn new(segment_reader: &SegmentReader, popularity: &Arc<PopularityScores>) -> Self {
+        let urls = if popularity.is_empty() {
             None
         } else {
-            Some(Box::new(BooleanQuery::new(clauses)) as Box<dyn Query>)
+            segment_reader.fast_fields().str("url").ok().flatten()
         };
-        if let Some(keyword_query) = keyword_query {
-            keyword_queries.push(keyword_query);
+        Self { urls, popularity: popularity.clone(), url: String::new() }
+    }
+
+    fn get(&mut self, doc: DocId) -> f32 {
+        let Some(urls) = self.urls.as_ref() else {
+            return 0.0;
+        };
+        let Some(ord) = urls.ords().first(doc) else {
+            return 0.0;
+        };
+        self.url.clear();
+        match urls.ord_to_str(ord, &mut self.url) {
+            Ok(true) => self.popularity.get(&self.url).copied().unwrap_or(0.0),
+            _ => 0.0,
         }
     }
+}
 
-    if keyword_queries.is_empty() {
-        return Ok(Some(Box::new(EmptyQuery)));
+// Source weights by the segment's `source` term ordinals, read per doc from the fast field.
+struct SegmentSourceWeights {
+    ords: Option<Column<u64>>,
+    weights: Vec<f32>,
+}
+
+impl SegmentSourceWeights {
+    fn new(segment_reader: &SegmentReader, weights: &SourceWeights) -> Self {
+        let Ok(Some(column)) = segment_reader.fast_fields().str("source") else {
+            return Self { ords: None, weights: Vec::new() };
+        };
+        let mut by_ord = vec![1.0; column.num_terms()];
+        for (source, weight) in weights {
+            if let Ok(Some(ord)) = column.dictionary().term_ord(source) {
+                by_ord[ord as usize] = *weight;
+            }
+        }
+        Self { ords: Some(column.ords().clone()), weights: by_ord }
     }
-    if keyword_queries.len() == 1 {
-        Ok(Some(keyword_queries.remove(0)))
-    } else {
-        Ok(Some(Box::new(BooleanQuery::new(
-            keyword_queries
-                .into_iter()
-                .map(|query| (Occur::Should, query))
-                .collect(),
-        ))))
+
+    fn get(&self, doc: DocId) -> f32 {
+        self.ords
+            .as_ref()
+            .and_then(|ords| ords.first(doc))
+            .and_then(|ord| self.weights.get(ord as usize).copied())
+            .unwrap_or(1.0)
+    }
+}
+
+// Lowercased value, a NUL and the value as written: terms sort by the
+// lowercased form and still carry the original casing for display.
+fn lookup_term(value: &str) -> String {
+    format!("{}\0{value}", value.to_lowercase())
+}
+
+// Reads the lookup terms from the lowercased prefix onwards and ranks the
+// values by how many posts carry them.
+fn suggest_terms(
+    searcher: &Searcher,
+    field: Field,
+    prefix: &str,
+    limit: usize,
+) -> Result<Vec<String>, SearchIndexError> {
+    let prefix = prefix.to_lowercase();
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for segment in searcher.segment_readers() {
+        let inverted_index = segment.inverted_index(field)?;
+        let mut stream = inverted_index.terms().range().ge(&prefix).into_stream()?;
+        while stream.advance() {
+            if !stream.key().starts_with(prefix.as_bytes()) {
+                break;
+            }
+            let Some((_, value)) = std::str::from_utf8(stream.key())
+                .ok()
+                .and_then(|term| term.split_once('\0'))
+            else {
+                continue;
+            };
+            *counts.entry(value.to_string()).or_default() += u64::from(stream.value().doc_freq);
+        }
     }
+    let mut counted = counts.into_iter().collect::<Vec<_>>();
+    counted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(counted
+        .into_iter()
+        .take(limit)
+        .map(|(value, _)| value)
+        .collect())
+}
+
+// Typo budget by length; very short words have too many close neighbours.
+fn fuzzy_distance(keyword: &str) -> Option<u8> {
+    if !keyword.chars().all(|ch| ch.is_ascii_alphanumeric()) {
+        return None;
+    }
+    match keyword.len() {
+        0..=3 => None,
+        4..=7 => Some(1),
+        _ => Some(2),
+    }
+}
+
+// Single letters would match the initial of nearly every one-hanzi token.
+fn is_pinyin_keyword(keyword: &str) -> bool {
+    keyword.len() >= 2 && keyword.chars().all(|ch| ch.is_ascii_alphabetic())
 }
 
 fn tokenize_keyword(
@@ -555,7 +1931,10 @@ fn snippet_or_excerpt(
     if let Some(snippet) = snippet_html(generator, doc) {
         return Some(snippet);
     }
-    let text = get_string(doc, field)?;
+    excerpt(&get_string(doc, field)?, max_chars)
+}
+
+fn excerpt(text: &str, max_chars: usize) -> Option<String> {
     let trimmed = text.trim();
     if trimmed.is_empty() {
         None
@@ -564,6 +1943,77 @@ fn snippet_or_excerpt(
     }
 }
 
+// Same text SnippetGenerator::snippet_from_doc highlights.
+fn field_text(doc: &TantivyDocument, field: Field) -> String {
+    get_strings(doc, field).join(" ").trim().to_string()
+}
+
+// Picks up to `max_fragments` non-overlapping fragments, best first, and returns
+// them in text order. Tantivy only ever selects one, so later picks search the
+// text left over on either side of the earlier ones.
+fn snippet_fragments(
+    generator: &SnippetGenerator,
+    text: &str,
+    max_fragments: usize,
+) -> Vec<Snippet> {
+    let mut segments = vec![(0, text)];
+    let mut picked: Vec<(usize, Snippet)> = Vec::new();
+    while picked.len() < max_fragments {
+        let best = segments
+            .iter()
+            .enumerate()
+            .map(|(idx, (start, segment))| (idx, *start, generator.snippet(segment)))
+            .filter(|(_, _, snippet)| !snippet.is_empty())
+            .max_by(|left, right| {
+                left.2
+                    .highlighted()
+                    .len()
+                    .cmp(&right.2.highlighted().len())
+                    .then(right.1.cmp(&left.1))
+            });
+        let Some((idx, start, snippet)) = best else {
+            break;
+        };
+        let (_, segment) = segments.remove(idx);
+        let Some(pos) = segment.find(snippet.fragment()) else {
+            break;
+        };
+        let end = pos + snippet.fragment().len();
+        if end < segment.len() {
+            segments.insert(idx, (start + end, &segment[end..]));
+        }
+        if pos > 0 {
+            segments.insert(idx, (start, &segment[..pos]));
+        }
+        picked.push((start + pos, snippet));
+    }
+    picked.sort_by_key(|(start, _)| *start);
+    picked.into_iter().map(|(_, snippet)| snippet).collect()
+}
+
+fn highlight_fragments(snippets: &[Snippet]) -> Vec<HighlightFragment> {
+    snippets
+        .iter()
+        .map(|snippet| {
+            let fragment = snippet.fragment();
+            let leading = fragment.len() - fragment.trim_start().len();
+            let text = fragment.trim();
+            let utf16_offset = |byte_offset: usize| {
+                let byte_offset = byte_offset.saturating_sub(leading).min(text.len());
+                text[..byte_offset].encode_utf16().count()
+            };
+            let ranges = collapse_overlapped_ranges(snippet.highlighted())
+                .into_iter()
+                .map(|range| [utf16_offset(range.start), utf16_offset(range.end)])
+                .collect();
+            HighlightFragment {
+                text: text.to_string(),
+                ranges,
+            }
+        })
+        .collect()
+}
+
 fn timestamp_to_datetime(ts: i64, field: &'static str) -> Result<DateTime<Utc>, SearchIndexError> {
     DateTime::<Utc>::from_timestamp(ts, 0).ok_or(SearchIndexError::InvalidTimestamp(field))
 }
@@ -571,9 +2021,12 @@ fn timestamp_to_datetime(ts: i64, field: &'static str) -> Result<DateTime<Utc>,
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tantivy::collector::TopDocs;
+    use crate::search::test_support::{doc, temp_dir};
+    use crate::search::{SnippetOptions, SynonymMap};
+    use tantivy::collector::{Count, TopDocs};
     use tantivy::doc;
     use tantivy::tokenizer::TokenStream;
+    use std::fs;
 
     #[test]
     fn jieba_tokenizer_searches_chinese() -> Result<(), SearchIndexError> {
@@ -582,7 +2035,7 @@ mod tests {
         let subtitle = schema.get_field("subtitle")?;
         let content = schema.get_field("content")?;
         let index = Index::create_in_ram(schema);
-        register_jieba_tokenizer(&index);
+        register_tokenizers(&index, &SearchTokenizer::default());
 
         let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
         writer.add_document(doc!(
@@ -595,10 +2048,10 @@ mod tests {
         let fields = SearchFields::from_schema(&index.schema())?;
         let searcher = index.reader()?.searcher();
         let search_query = SearchQuery {
-            keywords: vec!["售货员".to_string()],
+            expr: Some(KeywordExpr::Keyword("售货员".to_string())),
             ..Default::default()
         };
-        let built = build_query(&index, &fields, &search_query)?;
+        let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
         let top_docs = searcher.search(&built.query, &TopDocs::with_limit(5))?;
         assert!(!top_docs.is_empty());
         let doc_address = top_docs[0].1;
@@ -616,11 +2069,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn traditional_and_full_width_keywords_match_folded_text() -> Result<(), SearchIndexError> {
+        let schema = build_schema();
+        let title = schema.get_field("title")?;
+        let content = schema.get_field("content")?;
+        let index = Index::create_in_ram(schema);
+        register_tokenizers(&index, &SearchTokenizer::default());
+
+        let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
+        writer.add_document(doc!(
+            title => "台湾旅行笔记",
+            content => "用 Rust 写了一个行程规划工具。"
+        ))?;
+        writer.add_document(doc!(
+            title => "臺灣美食",
+            content => "夜市小吃清单"
+        ))?;
+        writer.commit()?;
+
+        let fields = SearchFields::from_schema(&index.schema())?;
+        let searcher = index.reader()?.searcher();
+        for (keyword, expected) in [("臺灣", 2), ("台湾", 2), ("ＲＵＳＴ", 1)] {
+            let search_query = SearchQuery {
+                expr: Some(KeywordExpr::Keyword(keyword.to_string())),
+                ..Default::default()
+            };
+            let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
+            let count = searcher.search(&built.query, &Count)?;
+            assert_eq!(count, expected, "keyword {keyword}");
+        }
+        Ok(())
+    }
+
     #[test]
     fn jieba_tokenizer_outputs_tokens_for_content() -> Result<(), SearchIndexError> {
         let schema = build_schema();
         let index = Index::create_in_ram(schema);
-        register_jieba_tokenizer(&index);
+        register_tokenizers(&index, &SearchTokenizer::default());
 
         let mut analyzer = index
             .tokenizers()
@@ -644,7 +2130,7 @@ mod tests {
         let subtitle = schema.get_field("subtitle")?;
         let content = schema.get_field("content")?;
         let index = Index::create_in_ram(schema);
-        register_jieba_tokenizer(&index);
+        register_tokenizers(&index, &SearchTokenizer::default());
 
         let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
         writer.add_document(doc!(
@@ -657,10 +2143,10 @@ mod tests {
         let fields = SearchFields::from_schema(&index.schema())?;
         let searcher = index.reader()?.searcher();
         let search_query = SearchQuery {
-            keywords: vec!["三年".to_string()],
+            expr: Some(KeywordExpr::Keyword("三年".to_string())),
             ..Default::default()
         };
-        let built = build_query(&index, &fields, &search_query)?;
+        let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
         let top_docs = searcher.search(&built.query, &TopDocs::with_limit(5))?;
         assert!(!top_docs.is_empty());
         Ok(())
@@ -673,7 +2159,7 @@ mod tests {
         let subtitle = schema.get_field("subtitle")?;
         let content = schema.get_field("content")?;
         let index = Index::create_in_ram(schema);
-        register_jieba_tokenizer(&index);
+        register_tokenizers(&index, &SearchTokenizer::default());
 
         let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
         writer.add_document(doc!(
@@ -686,10 +2172,10 @@ mod tests {
         let fields = SearchFields::from_schema(&index.schema())?;
         let searcher = index.reader()?.searcher();
         let search_query = SearchQuery {
-            keywords: vec!["关键词".to_string()],
+            expr: Some(KeywordExpr::Keyword("关键词".to_string())),
             ..Default::default()
         };
-        let built = build_query(&index, &fields, &search_query)?;
+        let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
         let top_docs = searcher.search(&built.query, &TopDocs::with_limit(5))?;
         assert!(!top_docs.is_empty());
         Ok(())
@@ -702,7 +2188,7 @@ mod tests {
         let subtitle = schema.get_field("subtitle")?;
         let content = schema.get_field("content")?;
         let index = Index::create_in_ram(schema);
-        register_jieba_tokenizer(&index);
+        register_tokenizers(&index, &SearchTokenizer::default());
 
         let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
         writer.add_document(doc!(
@@ -715,10 +2201,10 @@ mod tests {
         let fields = SearchFields::from_schema(&index.schema())?;
         let searcher = index.reader()?.searcher();
         let search_query = SearchQuery {
-            keywords: vec!["正文".to_string()],
+            expr: Some(KeywordExpr::Keyword("正文".to_string())),
             ..Default::default()
         };
-        let built = build_query(&index, &fields, &search_query)?;
+        let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
         let top_docs = searcher.search(&built.query, &TopDocs::with_limit(1))?;
         let doc_address = top_docs[0].1;
         let doc: TantivyDocument = searcher.doc(doc_address)?;
@@ -735,11 +2221,10 @@ mod tests {
     fn keyword_query_matches_tags() -> Result<(), SearchIndexError> {
         let schema = build_schema();
         let title = schema.get_field("title")?;
-        let subtitle = schema.get_field("subtitle")?;
         let content = schema.get_field("content")?;
         let tags = schema.get_field("tags")?;
         let index = Index::create_in_ram(schema);
-        register_jieba_tokenizer(&index);
+        register_tokenizers(&index, &SearchTokenizer::default());
 
         let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
         writer.add_document(doc!(
@@ -749,25 +2234,14 @@ mod tests {
         ))?;
         writer.commit()?;
 
-        let fields = SearchFields {
-            id: index.schema().get_field("id")?,
-            title,
-            subtitle,
-            content,
-            url: index.schema().get_field("url")?,
-            tags,
-            category: index.schema().get_field("category")?,
-            published: index.schema().get_field("published")?,
-            updated: index.schema().get_field("updated")?,
-            checksum: index.schema().get_field("checksum")?,
-        };
+        let fields = SearchFields::from_schema(&index.schema())?;
 
         let searcher = index.reader_builder().try_into()?.searcher();
         let search_query = SearchQuery {
-            keywords: vec!["售货员".to_string()],
+            expr: Some(KeywordExpr::Keyword("售货员".to_string())),
             ..Default::default()
         };
-        let built = build_query(&index, &fields, &search_query)?;
+        let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
         let hits = searcher.search(&built.query, &TopDocs::with_limit(10))?;
         assert_eq!(hits.len(), 1);
         Ok(())
@@ -777,12 +2251,11 @@ mod tests {
     fn range_query_matches_updated() -> Result<(), SearchIndexError> {
         let schema = build_schema();
         let title = schema.get_field("title")?;
-        let subtitle = schema.get_field("subtitle")?;
         let content = schema.get_field("content")?;
         let published = schema.get_field("published")?;
         let updated = schema.get_field("updated")?;
         let index = Index::create_in_ram(schema);
-        register_jieba_tokenizer(&index);
+        register_tokenizers(&index, &SearchTokenizer::default());
 
         let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
         writer.add_document(doc!(
@@ -793,18 +2266,7 @@ mod tests {
         ))?;
         writer.commit()?;
 
-        let fields = SearchFields {
-            id: index.schema().get_field("id")?,
-            title,
-            subtitle,
-            content,
-            url: index.schema().get_field("url")?,
-            tags: index.schema().get_field("tags")?,
-            category: index.schema().get_field("category")?,
-            published,
-            updated,
-            checksum: index.schema().get_field("checksum")?,
-        };
+        let fields = SearchFields::from_schema(&index.schema())?;
 
         let range = inkstone_core::types::time_range::TimeRange::parse("2024-01-01~2026-01-01")
             .unwrap();
@@ -812,10 +2274,22 @@ mod tests {
             range: Some(range),
             ..Default::default()
         };
-        let built = build_query(&index, &fields, &search_query)?;
+        let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
         let searcher = index.reader_builder().try_into()?.searcher();
         let hits = searcher.search(&built.query, &TopDocs::with_limit(10))?;
         assert_eq!(hits.len(), 1);
+
+        for (input, expected) in [
+            ("updated:2025", 1),
+            ("published:2025", 0),
+            ("published:2020-01", 1),
+            ("year:2020", 1),
+        ] {
+            let search_query = crate::search::parse_query(input).unwrap();
+            let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
+            let hits = searcher.search(&built.query, &TopDocs::with_limit(10))?;
+            assert_eq!(hits.len(), expected, "{input}");
+        }
         Ok(())
     }
 
@@ -823,11 +2297,10 @@ mod tests {
     fn keyword_query_matches_category() -> Result<(), SearchIndexError> {
         let schema = build_schema();
         let title = schema.get_field("title")?;
-        let subtitle = schema.get_field("subtitle")?;
         let content = schema.get_field("content")?;
         let category = schema.get_field("category")?;
         let index = Index::create_in_ram(schema);
-        register_jieba_tokenizer(&index);
+        register_tokenizers(&index, &SearchTokenizer::default());
 
         let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
         writer.add_document(doc!(
@@ -837,27 +2310,952 @@ mod tests {
         ))?;
         writer.commit()?;
 
-        let fields = SearchFields {
-            id: index.schema().get_field("id")?,
-            title,
-            subtitle,
-            content,
-            url: index.schema().get_field("url")?,
-            tags: index.schema().get_field("tags")?,
-            category,
-            published: index.schema().get_field("published")?,
-            updated: index.schema().get_field("updated")?,
-            checksum: index.schema().get_field("checksum")?,
-        };
+        let fields = SearchFields::from_schema(&index.schema())?;
 
         let searcher = index.reader_builder().try_into()?.searcher();
         let search_query = SearchQuery {
-            keywords: vec!["实验室".to_string()],
+            expr: Some(KeywordExpr::Keyword("实验室".to_string())),
             ..Default::default()
         };
-        let built = build_query(&index, &fields, &search_query)?;
+        let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
         let hits = searcher.search(&built.query, &TopDocs::with_limit(10))?;
         assert_eq!(hits.len(), 1);
         Ok(())
     }
+
+    #[test]
+    fn stats_reflect_indexed_docs() {
+        let dir = temp_dir("inkstone-search-stats");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let post = SearchDocument {
+            sections: vec![SearchSection {
+                anchor: "intro".to_string(),
+                heading: "Intro".to_string(),
+                content: "World".to_string(),
+            }],
+            ..doc("doc-1", "Hello", "World")
+        };
+        index.upsert_documents(&[post.clone(), post]).unwrap();
+        let stats = index.stats().unwrap();
+        assert_eq!((stats.num_docs, stats.num_sections), (1, 1));
+        assert_eq!(index.list_ids().unwrap(), HashSet::from(["doc-1".to_string()]));
+
+        index.delete_documents(&["doc-1".to_string()]).unwrap();
+        let stats = index.stats().unwrap();
+        assert_eq!((stats.num_docs, stats.num_sections), (0, 0));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn tokenizer_fingerprint_marks_index_stale_when_dictionary_changes() {
+        let dir = temp_dir("inkstone-search-tokenizer");
+        let stock = SearchTokenizer::default();
+        let custom = SearchTokenizer::parse(Some("墨石博客\n"), None).unwrap();
+
+        let index = SearchIndex::open_with_tokenizer(&dir, &custom).unwrap();
+        assert!(!index.tokenizer_stale());
+        drop(index);
+        assert!(!SearchIndex::open_with_tokenizer(&dir, &custom).unwrap().tokenizer_stale());
+        assert!(SearchIndex::open_with_tokenizer(&dir, &stock).unwrap().tokenizer_stale());
+
+        fs::remove_file(dir.join(TOKENIZER_FINGERPRINT_FILE)).unwrap();
+        assert!(SearchIndex::open_or_create(&dir).unwrap().tokenizer_stale());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stop_words_do_not_reject_keyword_matches() {
+        let dir = temp_dir("inkstone-search-stop-words");
+        let tokenizer = SearchTokenizer::parse(None, Some("the\n")).unwrap();
+        let index = SearchIndex::open_with_tokenizer(&dir, &tokenizer).unwrap();
+        let docs = vec![
+            doc("doc-1", "Rust async runtimes", "World"),
+            doc("doc-2", "Gardening", "World"),
+        ];
+        index.upsert_documents(&docs).unwrap();
+
+        let options = SearchOptions {
+            limit: 10,
+            ..Default::default()
+        };
+        for input in ["the rust", "the OR rust"] {
+            let query = crate::search::parse_query(input).unwrap();
+            let result = index.search(&query, &options).unwrap();
+            let urls = result.hits.iter().map(|hit| hit.url.as_str()).collect::<Vec<_>>();
+            assert_eq!(urls, vec!["https://example.com/posts/doc-1"], "{input}");
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sections_behind_the_main_index_are_stale() {
+        let dir = temp_dir("inkstone-search-sections-stale");
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        index.upsert_documents(&[doc("doc-1", "Hello", "World")]).unwrap();
+        assert!(!index.sections_stale());
+        assert!(!SearchIndex::open_or_create(&dir).unwrap().sections_stale());
+
+        // The main index committed again, but the matching section commit never landed.
+        let mut writer = index.index.writer::<TantivyDocument>(50_000_000).unwrap();
+        writer
+            .add_document(index.domain_to_document(&doc("doc-2", "Hello", "World")))
+            .unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+        drop(index);
+        assert!(SearchIndex::open_or_create(&dir).unwrap().sections_stale());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn outdated_schema_version_is_rejected() {
+        let dir = temp_dir("inkstone-search-schema-version");
+        drop(SearchIndex::open_or_create(&dir).unwrap());
+        assert!(SearchIndex::open_or_create(&dir).is_ok());
+
+        fs::remove_file(dir.join(SCHEMA_VERSION_FILE)).unwrap();
+        assert!(matches!(
+            SearchIndex::open_or_create(&dir),
+            Err(SearchIndexError::OutdatedSchema(0))
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn delete_documents_removes_listed_ids() {
+        let dir = temp_dir("inkstone-search-delete");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = ["doc-1", "doc-2"]
+            .map(|id| doc(id, "Hello", "World"))
+            .to_vec();
+        index.upsert_documents(&docs).unwrap();
+        assert_eq!(index.list_ids().unwrap().len(), 2);
+
+        index.delete_documents(&["doc-1".to_string()]).unwrap();
+        let ids = index.list_ids().unwrap();
+        assert_eq!(ids.len(), 1);
+        assert!(ids.contains("doc-2"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn facets_count_full_matching_set() {
+        let dir = temp_dir("inkstone-search-facets");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = [
+            ("doc-1", vec!["rust", "search"], Some("tech")),
+            ("doc-2", vec!["rust"], Some("tech")),
+            ("doc-3", vec!["wasm"], Some("notes")),
+            ("doc-4", vec!["rust"], None),
+        ]
+        .into_iter()
+        .map(|(id, tags, category)| SearchDocument {
+            tags: tags.into_iter().map(str::to_string).collect(),
+            category: category.map(str::to_string),
+            ..doc(id, "Hello", "World")
+        })
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+
+        let query = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("hello".to_string())),
+            excluded_tags: vec!["wasm".to_string()],
+            ..Default::default()
+        };
+        let options = SearchOptions {
+            limit: 1,
+            facets: vec![FacetField::Tags, FacetField::Category],
+            ..Default::default()
+        };
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.hits.len(), 1);
+        let facets = result.facets.expect("facets");
+        let tags = facets.tags.expect("tag facets");
+        assert_eq!(
+            tags,
+            vec![
+                FacetCount {
+                    value: "rust".to_string(),
+                    count: 3
+                },
+                FacetCount {
+                    value: "search".to_string(),
+                    count: 1
+                },
+            ]
+        );
+        let category = facets.category.expect("category facets");
+        assert_eq!(
+            category,
+            vec![FacetCount {
+                value: "tech".to_string(),
+                count: 2
+            }]
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn suggest_completes_titles_tags_and_categories() {
+        let dir = temp_dir("inkstone-search-suggest");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = [
+            ("doc-1", "Rust async runtimes", vec!["Rust"], Some("Rambling")),
+            ("doc-2", "Tantivy internals", vec!["Rust", "Search"], None),
+            ("doc-3", "Gardening", vec!["Life", "Ruby"], None),
+        ]
+        .into_iter()
+        .map(|(id, title, tags, category)| SearchDocument {
+            tags: tags.into_iter().map(str::to_string).collect(),
+            category: category.map(str::to_string),
+            ..doc(id, title, "World")
+        })
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+
+        let suggestions = index.suggest("ru", 5).unwrap();
+        let titles = suggestions
+            .titles
+            .iter()
+            .map(|title| title.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["Rust async runtimes"]);
+        assert_eq!(suggestions.tags, vec!["Rust", "Ruby"]);
+        assert!(suggestions.categories.is_empty());
+
+        let suggestions = index.suggest("ra", 5).unwrap();
+        assert!(suggestions.titles.is_empty());
+        assert_eq!(suggestions.categories, vec!["Rambling"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn related_ranks_similar_posts_and_skips_source() {
+        let dir = temp_dir("inkstone-search-related");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = [
+            ("doc-1", "Tokio runtime internals", "tokio scheduler tasks wakers", vec!["Rust"]),
+            ("doc-2", "Tokio task budgeting", "tokio scheduler budgets tasks", vec!["Rust"]),
+            ("doc-3", "Sourdough starter", "flour water patience", vec!["Life"]),
+        ]
+        .into_iter()
+        .map(|(id, title, content, tags)| SearchDocument {
+            tags: tags.into_iter().map(str::to_string).collect(),
+            ..doc(id, title, content)
+        })
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+
+        let hits = index
+            .related(DocumentRef::Url("https://example.com/posts/doc-1"), 5, true)
+            .unwrap()
+            .expect("source document");
+        let urls = hits.iter().map(|hit| hit.url.as_str()).collect::<Vec<_>>();
+        assert_eq!(urls, vec!["https://example.com/posts/doc-2"]);
+
+        let missing = index.related(DocumentRef::Id("doc-404"), 5, false).unwrap();
+        assert!(missing.is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn smart_and_popular_sorts_reorder_ties() {
+        let dir = temp_dir("inkstone-search-blended");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let now = chrono::Utc::now();
+        let docs = [
+            ("doc-old", now - chrono::Duration::days(720)),
+            ("doc-new", now),
+        ]
+        .into_iter()
+        .map(|(id, updated_at)| SearchDocument {
+            published_at: updated_at,
+            updated_at,
+            ..doc(id, "Tokio notes", "tokio")
+        })
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+        let query = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("tokio".to_string())),
+            ..Default::default()
+        };
+
+        let smart = SearchOptions {
+            limit: 2,
+            sort: SearchSort::Smart,
+            ..Default::default()
+        };
+        let result = index.search(&query, &smart).unwrap();
+        assert_eq!(result.hits[0].url, "https://example.com/posts/doc-new");
+
+        for boosted in ["doc-old", "doc-new"] {
+            let url = format!("https://example.com/posts/{boosted}");
+            let popular = SearchOptions {
+                limit: 2,
+                sort: SearchSort::Popular,
+                popularity: Some(std::sync::Arc::new(PopularityScores::from([(
+                    url.clone(),
+                    1.0,
+                )]))),
+                ..Default::default()
+            };
+            let result = index.search(&query, &popular).unwrap();
+            assert_eq!(result.hits[0].url, url);
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sources_filter_weight_and_group_documents() {
+        let dir = temp_dir("inkstone-search-sources");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = [("doc-blog", "blog"), ("doc-notes", "notes"), ("doc-talk", "talks")]
+            .map(|(id, source)| SearchDocument {
+                source: source.to_string(),
+                ..doc(id, "Tokio notes", "tokio")
+            })
+            .to_vec();
+        index.upsert_documents(&docs).unwrap();
+        let options = SearchOptions {
+            limit: 10,
+            ..Default::default()
+        };
+
+        let only_notes = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("tokio".to_string())),
+            source: Some("notes".to_string()),
+            ..Default::default()
+        };
+        let result = index.search(&only_notes, &options).unwrap();
+        assert_eq!(result.total, 1);
+        assert_eq!(result.hits[0].source, "notes");
+
+        let without_talks = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("tokio".to_string())),
+            excluded_sources: vec!["talks".to_string()],
+            ..Default::default()
+        };
+        let result = index.search(&without_talks, &options).unwrap();
+        assert_eq!(result.total, 2);
+        assert!(result.hits.iter().all(|hit| hit.source != "talks"));
+
+        let query = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("tokio".to_string())),
+            ..Default::default()
+        };
+        for favoured in ["blog", "talks"] {
+            let weighted = SearchOptions {
+                limit: 10,
+                source_weights: Some(std::sync::Arc::new(SourceWeights::from([(
+                    favoured.to_string(),
+                    3.0,
+                )]))),
+                ..Default::default()
+            };
+            let result = index.search(&query, &weighted).unwrap();
+            assert_eq!(result.hits[0].source, favoured);
+        }
+
+        let ids = index.list_ids_by_source().unwrap();
+        assert_eq!(ids.len(), 3);
+        assert!(ids["notes"].contains("doc-notes"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sections_leave_post_scores_unchanged() {
+        let query = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("tokio".to_string())),
+            ..Default::default()
+        };
+        let options = SearchOptions {
+            limit: 1,
+            explain: true,
+            ..Default::default()
+        };
+        let score = |sections: Vec<SearchSection>| {
+            let dir = temp_dir("inkstone-search-section-scores");
+            let index = SearchIndex::open_or_create(&dir).unwrap();
+            let post = SearchDocument {
+                sections,
+                ..doc("doc-1", "Tokio notes", "tokio runtime internals")
+            };
+            index.upsert_documents(&[post, doc("doc-2", "Rust notes", "ownership")]).unwrap();
+            let result = index.search(&query, &options).unwrap();
+            let _ = fs::remove_dir_all(&dir);
+            result.hits[0].explanation.as_ref().unwrap()["value"].as_f64().unwrap()
+        };
+
+        let sections = ["Tokio", "Tokio tasks", "Tokio timers"]
+            .map(|heading| SearchSection {
+                anchor: heading.to_lowercase().replace(' ', "-"),
+                heading: heading.to_string(),
+                content: "tokio tokio".to_string(),
+            })
+            .to_vec();
+        assert_eq!(score(Vec::new()), score(sections));
+    }
+
+    #[test]
+    fn sections_are_grouped_under_their_post_hits() {
+        let dir = temp_dir("inkstone-search-sections");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let section = |anchor: &str, heading: &str, content: &str| SearchSection {
+            anchor: anchor.to_string(),
+            heading: heading.to_string(),
+            content: content.to_string(),
+        };
+        let docs = [
+            (
+                "doc-guide",
+                vec![
+                    section("setup", "Setup", "install the runtime"),
+                    section("cancellation", "Cancellation", "tokio tasks stop on drop"),
+                    section("timeouts", "Timeouts", "wrap tokio futures in a timeout"),
+                ],
+            ),
+            ("doc-other", vec![section("misc", "Misc", "nothing relevant")]),
+        ]
+        .into_iter()
+        .map(|(id, sections)| {
+            let content = sections
+                .iter()
+                .map(|section| format!("{} {}", section.heading, section.content))
+                .collect::<Vec<_>>()
+                .join(" ");
+            SearchDocument {
+                url: format!("https://example.com/posts/{id}/"),
+                sections,
+                ..doc(id, "Runtime guide", &content)
+            }
+        })
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+        let query = SearchQuery {
+            expr: Some(KeywordExpr::and(vec![
+                KeywordExpr::Keyword("tokio".to_string()),
+                KeywordExpr::Keyword("cancellation".to_string()),
+            ])),
+            ..Default::default()
+        };
+        let options = SearchOptions {
+            limit: 10,
+            ..Default::default()
+        };
+
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.total, 1);
+        let sections = &result.hits[0].sections;
+        let anchors = sections.iter().map(|section| section.anchor.as_str()).collect::<Vec<_>>();
+        assert_eq!(anchors, vec!["cancellation", "timeouts"]);
+        assert_eq!(sections[0].url, "https://example.com/posts/doc-guide/#cancellation");
+        assert_eq!(sections[0].heading, "Cancellation");
+        assert!(sections[0].content.as_deref().unwrap().contains("<b>tokio</b>"));
+
+        let ranges = SearchOptions {
+            limit: 10,
+            highlight: HighlightMode::Ranges,
+            ..Default::default()
+        };
+        let result = index.search(&query, &ranges).unwrap();
+        let first = &result.hits[0].sections[0];
+        assert_eq!(first.content.as_deref(), Some("tokio tasks stop on drop"));
+        assert_eq!(first.highlights[0].ranges, vec![[0, 5]]);
+
+        let filters_only = SearchQuery {
+            source: Some("blog".to_string()),
+            ..Default::default()
+        };
+        let result = index.search(&filters_only, &options).unwrap();
+        assert_eq!(result.total, 2);
+        assert!(result.hits.iter().all(|hit| hit.sections.is_empty()));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn field_boosts_rank_title_matches_and_explain_scores() {
+        let dir = temp_dir("inkstone-search-boosts");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = [
+            ("doc-title", "Tokio notes", "runtime internals"),
+            ("doc-content", "Runtime notes", "tokio tokio tokio runtime"),
+        ]
+        .into_iter()
+        .map(|(id, title, content)| doc(id, title, content))
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+        let query = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("tokio".to_string())),
+            ..Default::default()
+        };
+
+        let options = SearchOptions {
+            limit: 2,
+            explain: true,
+            ..Default::default()
+        };
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.hits[0].url, "https://example.com/posts/doc-title");
+        let explanation = result.hits[0].explanation.as_ref().unwrap();
+        assert!(explanation["value"].as_f64().unwrap() > 0.0);
+
+        let options = SearchOptions {
+            limit: 2,
+            boosts: FieldBoosts {
+                title: 0.1,
+                content: 10.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.hits[0].url, "https://example.com/posts/doc-content");
+        assert!(result.hits[0].explanation.is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cursor_pages_through_stable_snapshot() {
+        let dir = temp_dir("inkstone-search-cursor");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let now = chrono::Utc::now();
+        let make_doc = |n: i64| SearchDocument {
+            published_at: now - chrono::Duration::days(n % 3),
+            updated_at: now - chrono::Duration::days(n % 3),
+            ..doc(&format!("doc-{n}"), "Tokio notes", "tokio")
+        };
+        index
+            .upsert_documents(&(0..5).map(make_doc).collect::<Vec<_>>())
+            .unwrap();
+        let query = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("tokio".to_string())),
+            ..Default::default()
+        };
+
+        for sort in [SearchSort::Relevance, SearchSort::Latest] {
+            let all = SearchOptions {
+                limit: 10,
+                sort,
+                ..Default::default()
+            };
+            let all = index.search(&query, &all).unwrap();
+            assert!(all.next_cursor.is_none());
+            let expected = all.hits.iter().map(|hit| hit.url.clone()).collect::<Vec<_>>();
+
+            let mut urls = Vec::new();
+            let mut cursor = None;
+            loop {
+                let options = SearchOptions {
+                    limit: 2,
+                    sort,
+                    cursor,
+                    ..Default::default()
+                };
+                let page = index.search(&query, &options).unwrap();
+                urls.extend(page.hits.into_iter().map(|hit| hit.url));
+                if urls.len() == 2 {
+                    // Cursors keep reading the snapshot they started from.
+                    index.upsert_documents(&[make_doc(5)]).unwrap();
+                }
+                match page.next_cursor {
+                    Some(next) => cursor = Some(SearchCursor::decode(&next).unwrap()),
+                    None => break,
+                }
+            }
+            assert_eq!(urls, expected);
+            index.delete_documents(&["doc-5".to_string()]).unwrap();
+        }
+
+        // After a restart the cursor continues right after its hit, ties included.
+        let reopened = SearchIndex::open_or_create(&dir).unwrap();
+        for sort in [SearchSort::Relevance, SearchSort::Latest] {
+            let options = SearchOptions {
+                limit: 2,
+                sort,
+                ..Default::default()
+            };
+            let first = index.search(&query, &options).unwrap();
+            let cursor = SearchCursor::decode(first.next_cursor.as_deref().unwrap()).unwrap();
+            let options = SearchOptions {
+                limit: 10,
+                cursor: Some(cursor),
+                ..options
+            };
+            let rest = reopened.search(&query, &options).unwrap();
+            let urls = first
+                .hits
+                .iter()
+                .chain(&rest.hits)
+                .map(|hit| hit.url.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(urls.len(), 5);
+            assert_eq!(urls.iter().collect::<HashSet<_>>().len(), 5);
+        }
+
+        let cursor = SearchCursor {
+            index_id: index.instance_id,
+            generation: u64::MAX,
+            key: CursorKey::Score(1.0),
+            segment_ord: 0,
+            doc_id: 0,
+            id: "doc-0".to_string(),
+        };
+        let options = SearchOptions {
+            limit: 10,
+            cursor: Some(cursor.clone()),
+            ..Default::default()
+        };
+        // A released snapshot continues on the current one after the cursor's hit.
+        let all = SearchOptions {
+            cursor: None,
+            ..options.clone()
+        };
+        let all = index.search(&query, &all).unwrap();
+        let all = all.hits.into_iter().map(|hit| hit.url).collect::<Vec<_>>();
+        let after = all.iter().position(|url| url.ends_with("/doc-0")).unwrap() + 1;
+        let page = index.search(&query, &options).unwrap();
+        let urls = page.hits.into_iter().map(|hit| hit.url).collect::<Vec<_>>();
+        assert_eq!(urls, all[after..]);
+        let deleted = SearchCursor {
+            id: "missing".to_string(),
+            ..cursor
+        };
+        let options = SearchOptions {
+            cursor: Some(deleted),
+            ..options
+        };
+        assert!(matches!(
+            index.search(&query, &options),
+            Err(SearchIndexError::CursorExpired)
+        ));
+        let options = SearchOptions {
+            sort: SearchSort::Smart,
+            ..options
+        };
+        assert!(matches!(
+            index.search(&query, &options),
+            Err(SearchIndexError::InvalidCursor(_))
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn highlight_ranges_return_plain_fragments_with_utf16_offsets() {
+        let dir = temp_dir("inkstone-search-highlight");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let filler = "lorem ipsum dolor sit amet ".repeat(12);
+        let content = format!("tokio starts here. {filler} and tokio ends here.");
+        index
+            .upsert_documents(&[doc("doc-1", "深入 tokio <笔记>", &content)])
+            .unwrap();
+        let query = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("tokio".to_string())),
+            ..Default::default()
+        };
+        let options = SearchOptions {
+            limit: 1,
+            highlight: HighlightMode::Ranges,
+            snippets: SnippetOptions {
+                max_chars: 40,
+                fragments: 2,
+            },
+            ..Default::default()
+        };
+        let result = index.search(&query, &options).unwrap();
+        let hit = &result.hits[0];
+        assert_eq!(hit.title, "深入 tokio <笔记>");
+        let highlights = hit.highlights.as_ref().unwrap();
+        assert_eq!(highlights.title[0].ranges, vec![[3, 8]]);
+        assert_eq!(highlights.content.len(), 2);
+        for fragment in &highlights.content {
+            let units = fragment.text.encode_utf16().collect::<Vec<_>>();
+            for [start, end] in &fragment.ranges {
+                assert_eq!(String::from_utf16(&units[*start..*end]).unwrap(), "tokio");
+            }
+        }
+        assert!(highlights.content[0].text.starts_with("tokio starts"));
+        assert!(highlights.content[1].text.contains("tokio ends"));
+        assert!(!hit.content.as_deref().unwrap().contains("<b>"));
+
+        let options = SearchOptions {
+            highlight: HighlightMode::Html,
+            ..options
+        };
+        let result = index.search(&query, &options).unwrap();
+        let hit = &result.hits[0];
+        assert!(hit.highlights.is_none());
+        assert_eq!(hit.content.as_deref().unwrap().matches("<b>tokio</b>").count(), 2);
+        assert!(hit.title.contains("&lt;笔记&gt;"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn synonyms_expand_keywords_and_exclusions() {
+        let dir = temp_dir("inkstone-search-synonyms");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = [
+            ("doc-k8s", "Kubernetes operators", "writing a controller"),
+            ("doc-db", "数据库 索引", "b-tree pages"),
+        ]
+        .into_iter()
+        .map(|(id, title, content)| doc(id, title, content))
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+        let options = SearchOptions {
+            limit: 10,
+            synonyms: Some(std::sync::Arc::new(SynonymMap::parse(
+                "k8s, Kubernetes\nDB, 数据库\n",
+            ))),
+            ..Default::default()
+        };
+
+        let query = crate::search::parse_query("k8s").unwrap();
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.total, 1);
+        assert!(result.hits[0].title.contains("<b>Kubernetes</b>"));
+        let without = SearchOptions {
+            synonyms: None,
+            ..options.clone()
+        };
+        assert_eq!(index.search(&query, &without).unwrap().total, 0);
+
+        let query = crate::search::parse_query("db").unwrap();
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.hits[0].url, "https://example.com/posts/doc-db");
+
+        let query = crate::search::parse_query("operators OR 索引 -db").unwrap();
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.total, 1);
+        assert_eq!(result.hits[0].url, "https://example.com/posts/doc-k8s");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn pinyin_keywords_match_titles_and_tags_below_direct_matches() {
+        let dir = temp_dir("inkstone-search-pinyin");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = [
+            ("doc-db", "数据库索引", vec![]),
+            ("doc-tag", "周末随笔", vec!["摄影".to_string()]),
+            ("doc-sjk", "sjk 命令行工具", vec![]),
+        ]
+        .into_iter()
+        .map(|(id, title, tags)| SearchDocument {
+            tags,
+            ..doc(id, title, "正文")
+        })
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+        let options = SearchOptions {
+            limit: 10,
+            ..Default::default()
+        };
+
+        let query = crate::search::parse_query("shuju").unwrap();
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.total, 1);
+        assert_eq!(result.hits[0].url, "https://example.com/posts/doc-db");
+
+        let query = crate::search::parse_query("SheYing").unwrap();
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.total, 1);
+        assert_eq!(result.hits[0].url, "https://example.com/posts/doc-tag");
+
+        let query = crate::search::parse_query("sjk").unwrap();
+        let result = index.search(&query, &options).unwrap();
+        assert_eq!(result.total, 2);
+        assert_eq!(result.hits[0].url, "https://example.com/posts/doc-sjk");
+        assert_eq!(result.hits[1].url, "https://example.com/posts/doc-db");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn fuzzy_fallback_only_runs_when_exact_matching_finds_nothing() {
+        let dir = temp_dir("inkstone-search-fuzzy");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = [
+            ("doc-tantivy", "Tantivy internals", "segments and postings"),
+            ("doc-postgres", "Postgres tuning", "vacuum and indexes"),
+        ]
+        .into_iter()
+        .map(|(id, title, content)| doc(id, title, content))
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+        let options = SearchOptions {
+            limit: 10,
+            ..Default::default()
+        };
+
+        for (keyword, url) in [
+            ("tantvy", "https://example.com/posts/doc-tantivy"),
+            ("postgress", "https://example.com/posts/doc-postgres"),
+        ] {
+            let query = crate::search::parse_query(keyword).unwrap();
+            let result = index.search(&query, &options).unwrap();
+            assert!(result.fuzzy, "keyword {keyword}");
+            assert_eq!(result.total, 1);
+            assert_eq!(result.hits[0].url, url);
+        }
+
+        let query = crate::search::parse_query("tantivy").unwrap();
+        let result = index.search(&query, &options).unwrap();
+        assert!(!result.fuzzy);
+        assert_eq!(result.total, 1);
+
+        let query = crate::search::parse_query("vacum").unwrap();
+        assert!(index.search(&query, &options).unwrap().fuzzy);
+        let query = crate::search::parse_query("sql").unwrap();
+        let result = index.search(&query, &options).unwrap();
+        assert!(!result.fuzzy);
+        assert_eq!(result.total, 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn spelling_neighbour_prefers_closest_then_most_common_term() {
+        let dir = temp_dir("inkstone-search-spelling");
+        fs::create_dir_all(&dir).unwrap();
+        let index = SearchIndex::open_or_create(&dir).unwrap();
+        let docs = [
+            ("doc-1", "Tantivy internals", "segments and postings"),
+            ("doc-2", "Tantivy merges", "segment merges"),
+            ("doc-3", "Tantive", "a rarer neighbour"),
+        ]
+        .into_iter()
+        .map(|(id, title, content)| doc(id, title, content))
+        .collect::<Vec<_>>();
+        index.upsert_documents(&docs).unwrap();
+
+        assert_eq!(index.spelling_neighbour("Tantivi").unwrap().as_deref(), Some("tantivy"));
+        assert_eq!(index.spelling_neighbour("tantvy").unwrap().as_deref(), Some("tantivy"));
+        assert_eq!(index.spelling_neighbour("tantivy").unwrap(), None);
+        assert_eq!(index.spelling_neighbour("zzzzzz").unwrap(), None);
+        assert_eq!(index.spelling_neighbour("sgm").unwrap(), None);
+
+        let query = crate::search::parse_query("tantvy").unwrap();
+        assert_eq!(index.count(&query, &SearchOptions::default()).unwrap(), 0);
+        let query = crate::search::parse_query("tantivy").unwrap();
+        assert_eq!(index.count(&query, &SearchOptions::default()).unwrap(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn phrase_query_requires_adjacent_terms() -> Result<(), SearchIndexError> {
+        let schema = build_schema();
+        let title = schema.get_field("title")?;
+        let content = schema.get_field("content")?;
+        let index = Index::create_in_ram(schema);
+        register_tokenizers(&index, &SearchTokenizer::default());
+
+        let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
+        writer.add_document(doc!(
+            title => "Runtime notes",
+            content => "We love rust async runtimes"
+        ))?;
+        writer.add_document(doc!(
+            title => "Other notes",
+            content => "async code in rust"
+        ))?;
+        writer.commit()?;
+
+        let fields = SearchFields::from_schema(&index.schema())?;
+        let searcher = index.reader()?.searcher();
+        let search_query = SearchQuery {
+            expr: Some(KeywordExpr::Phrase("rust async".to_string())),
+            ..Default::default()
+        };
+        let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
+        let top_docs = searcher.search(&built.query, &TopDocs::with_limit(5))?;
+        assert_eq!(top_docs.len(), 1);
+        let doc: TantivyDocument = searcher.doc(top_docs[0].1)?;
+
+        let keyword_query = built.keyword.as_ref().expect("keyword query");
+        let content_snippet = SnippetGenerator::create(&searcher, &**keyword_query, content)?;
+        let html = content_snippet.snippet_from_doc(&doc).to_html();
+        assert!(html.contains("<b>rust</b>"));
+        assert!(html.contains("<b>async</b>"));
+        Ok(())
+    }
+
+    #[test]
+    fn excluded_terms_filter_matches() -> Result<(), SearchIndexError> {
+        let schema = build_schema();
+        let title = schema.get_field("title")?;
+        let content = schema.get_field("content")?;
+        let tags = schema.get_field("tags")?;
+        let index = Index::create_in_ram(schema);
+        register_tokenizers(&index, &SearchTokenizer::default());
+
+        let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
+        writer.add_document(doc!(
+            title => "Tokio runtime",
+            content => "tokio scheduling notes",
+            tags => "rust"
+        ))?;
+        writer.add_document(doc!(
+            title => "Tokio with actix",
+            content => "tokio under actix",
+            tags => "rust"
+        ))?;
+        writer.add_document(doc!(
+            title => "Tokio in the browser",
+            content => "tokio and wasm",
+            tags => "wasm"
+        ))?;
+        writer.commit()?;
+
+        let fields = SearchFields::from_schema(&index.schema())?;
+        let searcher = index.reader()?.searcher();
+        let search_query = SearchQuery {
+            expr: Some(KeywordExpr::Keyword("tokio".to_string())),
+            excluded_keywords: vec!["actix".to_string()],
+            excluded_tags: vec!["wasm".to_string()],
+            ..Default::default()
+        };
+        let built = build_query(&index, &fields, &search_query, &SearchOptions::default())?;
+        let top_docs = searcher.search(&built.query, &TopDocs::with_limit(5))?;
+        assert_eq!(top_docs.len(), 1);
+        let doc: TantivyDocument = searcher.doc(top_docs[0].1)?;
+        assert_eq!(get_string(&doc, title).as_deref(), Some("Tokio runtime"));
+        Ok(())
+    }
+
+    #[test]
+    fn keywords_require_all_terms_unless_or() -> Result<(), SearchIndexError> {
+        let schema = build_schema();
+        let title = schema.get_field("title")?;
+        let index = Index::create_in_ram(schema);
+        register_tokenizers(&index, &SearchTokenizer::default());
+
+        let mut writer = index.writer::<TantivyDocument>(50_000_000)?;
+        writer.add_document(doc!(title => "rust tantivy"))?;
+        writer.add_document(doc!(title => "rust only"))?;
+        writer.add_document(doc!(title => "tantivy only"))?;
+        writer.commit()?;
+
+        let fields = SearchFields::from_schema(&index.schema())?;
+        let searcher = index.reader()?.searcher();
+        let and_query = crate::search::parse_query("rust tantivy").unwrap();
+        let built = build_query(&index, &fields, &and_query, &SearchOptions::default())?;
+        assert_eq!(searcher.search(&built.query, &Count)?, 1);
+
+        let or_query = crate::search::parse_query("rust OR tantivy").unwrap();
+        let built = build_query(&index, &fields, &or_query, &SearchOptions::default())?;
+        assert_eq!(searcher.search(&built.query, &Count)?, 3);
+        Ok(())
+    }
 }
@@ -0,0 +1,416 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use pinyin::ToPinyin;
+use sha2::{Digest, Sha256};
+use tantivy::tokenizer::{
+    LowerCaser, RemoveLongFilter, Stemmer, StopWordFilter, TextAnalyzer, Token, TokenStream,
+    Tokenizer,
+};
+use tantivy_jieba::jieba_rs::{Jieba, TokenizeMode};
+use thiserror::Error;
+
+// Bump when the analyzer chain changes so existing indexes are rebuilt.
+const TOKENIZER_VERSION: &str = "jieba-v3";
+
+static DEFAULT_JIEBA: LazyLock<Arc<Jieba>> = LazyLock::new(|| Arc::new(Jieba::new()));
+
+#[derive(Debug, Error)]
+pub enum TokenizerError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid user dictionary entry on line {line}: {message}")]
+    InvalidDictEntry { line: usize, message: String },
+}
+
+// The "jieba" analyzer shared by every index this process opens. The user
+// dictionary uses the jieba format, one `word [freq] [tag]` entry per line;
+// entries without a frequency get one high enough to keep the word whole.
+// Stop words are listed one per line and matched after lowercasing.
+#[derive(Debug, Clone)]
+pub struct SearchTokenizer {
+    jieba: Arc<Jieba>,
+    stop_words: Vec<String>,
+    fingerprint: String,
+}
+
+impl Default for SearchTokenizer {
+    fn default() -> Self {
+        Self {
+            jieba: DEFAULT_JIEBA.clone(),
+            stop_words: Vec::new(),
+            fingerprint: fingerprint(&[], &[]),
+        }
+    }
+}
+
+impl SearchTokenizer {
+    pub fn load(
+        user_dict: Option<&Path>,
+        stop_words: Option<&Path>,
+    ) -> Result<Self, TokenizerError> {
+        let user_dict = user_dict.map(std::fs::read_to_string).transpose()?;
+        let stop_words = stop_words.map(std::fs::read_to_string).transpose()?;
+        Self::parse(user_dict.as_deref(), stop_words.as_deref())
+    }
+
+    pub fn parse(
+        user_dict: Option<&str>,
+        stop_words: Option<&str>,
+    ) -> Result<Self, TokenizerError> {
+        let entries = user_dict
+            .map(parse_user_dict)
+            .transpose()?
+            .unwrap_or_default();
+        let stop_words = stop_words
+            .map(parse_stop_words)
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let jieba = if entries.is_empty() {
+            DEFAULT_JIEBA.clone()
+        } else {
+            let mut jieba = Jieba::new();
+            for entry in &entries {
+                jieba.add_word(&entry.word, entry.freq, entry.tag.as_deref());
+            }
+            Arc::new(jieba)
+        };
+        let fingerprint = fingerprint(&entries, &stop_words);
+        Ok(Self {
+            jieba,
+            stop_words,
+            fingerprint,
+        })
+    }
+
+    // Stable digest of the analyzer chain, dictionary entries and stop words.
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    // Terms `text` is indexed under, e.g. to compare query words with highlighted spans.
+    pub fn terms(&self, text: &str) -> Vec<String> {
+        let mut analyzer = self.analyzer();
+        let mut stream = analyzer.token_stream(text);
+        let mut terms = Vec::new();
+        while stream.advance() {
+            terms.push(stream.token().text.clone());
+        }
+        terms
+    }
+
+    pub(crate) fn analyzer(&self) -> TextAnalyzer {
+        TextAnalyzer::builder(SharedJiebaTokenizer {
+            jieba: self.jieba.clone(),
+        })
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .filter(StopWordFilter::remove(self.stop_words.clone()))
+        .filter(Stemmer::default())
+        .build()
+    }
+
+    // Lowercased words without stemming or stop words, for typo-tolerant matching.
+    pub(crate) fn plain_analyzer(&self) -> TextAnalyzer {
+        TextAnalyzer::builder(SharedJiebaTokenizer {
+            jieba: self.jieba.clone(),
+        })
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .build()
+    }
+
+    // Pinyin of each hanzi token (`数据库` -> `shujuku` / `sjk`); other tokens are dropped.
+    pub(crate) fn pinyin_analyzer(&self, style: PinyinStyle) -> TextAnalyzer {
+        TextAnalyzer::from(PinyinTokenizer {
+            jieba: SharedJiebaTokenizer {
+                jieba: self.jieba.clone(),
+            },
+            style,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PinyinStyle {
+    Full,
+    Initials,
+}
+
+#[derive(Debug, PartialEq)]
+struct DictEntry {
+    word: String,
+    freq: Option<usize>,
+    tag: Option<String>,
+}
+
+fn parse_user_dict(contents: &str) -> Result<Vec<DictEntry>, TokenizerError> {
+    let mut entries = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let Some(word) = parts.next() else {
+            continue;
+        };
+        let freq = parts
+            .next()
+            .map(|raw| {
+                raw.parse::<usize>()
+                    .map_err(|_| TokenizerError::InvalidDictEntry {
+                        line: idx + 1,
+                        message: format!("invalid frequency `{raw}`"),
+                    })
+            })
+            .transpose()?;
+        entries.push(DictEntry {
+            word: word.to_string(),
+            freq,
+            tag: parts.next().map(str::to_string),
+        });
+    }
+    Ok(entries)
+}
+
+fn parse_stop_words(contents: &str) -> BTreeSet<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn fingerprint(entries: &[DictEntry], stop_words: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(TOKENIZER_VERSION.as_bytes());
+    for entry in entries {
+        let freq = entry.freq.map(|freq| freq.to_string()).unwrap_or_default();
+        let tag = entry.tag.as_deref().unwrap_or_default();
+        hasher.update(format!("\ndict\t{}\t{freq}\t{tag}", entry.word).as_bytes());
+    }
+    for word in stop_words {
+        hasher.update(format!("\nstop\t{word}").as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+// Same segmentation as `tantivy_jieba::JiebaTokenizer`, but sharing one
+// dictionary across analyzer clones instead of copying it per clone. Text is
+// folded to Simplified Chinese and half-width ASCII before segmentation, since
+// jieba splits Traditional text differently (`臺灣` vs `台湾`); offsets still
+// point into the original text so snippets highlight what the reader wrote.
+#[derive(Clone)]
+struct SharedJiebaTokenizer {
+    jieba: Arc<Jieba>,
+}
+
+struct SharedJiebaTokenStream {
+    tokens: std::vec::IntoIter<Token>,
+    token: Token,
+}
+
+impl Tokenizer for SharedJiebaTokenizer {
+    type TokenStream<'a> = SharedJiebaTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> SharedJiebaTokenStream {
+        let mut offsets = text.char_indices().map(|(idx, _)| idx).collect::<Vec<_>>();
+        offsets.push(text.len());
+        let folded = fold_text(text);
+        // Folding maps every char to exactly one char, so jieba's char positions
+        // index `offsets` directly.
+        let segmented = if folded.chars().count() + 1 == offsets.len() {
+            folded.as_str()
+        } else {
+            text
+        };
+        let tokens = self
+            .jieba
+            .tokenize(segmented, TokenizeMode::Search, true)
+            .into_iter()
+            .map(|token| Token {
+                offset_from: offsets[token.start],
+                offset_to: offsets[token.end],
+                position: token.start,
+                text: token.word.to_string(),
+                position_length: token.end - token.start,
+            })
+            .collect::<Vec<_>>();
+        SharedJiebaTokenStream {
+            tokens: tokens.into_iter(),
+            token: Token::default(),
+        }
+    }
+}
+
+impl TokenStream for SharedJiebaTokenStream {
+    fn advance(&mut self) -> bool {
+        match self.tokens.next() {
+            Some(token) => {
+                self.token = token;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[derive(Clone)]
+struct PinyinTokenizer {
+    jieba: SharedJiebaTokenizer,
+    style: PinyinStyle,
+}
+
+struct PinyinTokenStream {
+    tail: SharedJiebaTokenStream,
+    style: PinyinStyle,
+}
+
+impl Tokenizer for PinyinTokenizer {
+    type TokenStream<'a> = PinyinTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> PinyinTokenStream {
+        PinyinTokenStream {
+            tail: self.jieba.token_stream(text),
+            style: self.style,
+        }
+    }
+}
+
+impl TokenStream for PinyinTokenStream {
+    fn advance(&mut self) -> bool {
+        while self.tail.advance() {
+            if let Some(pinyin) = to_pinyin(&self.tail.token().text, self.style) {
+                self.tail.token_mut().text = pinyin;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
+// None unless every char of `text` is a hanzi with a known reading.
+fn to_pinyin(text: &str, style: PinyinStyle) -> Option<String> {
+    let mut output = String::new();
+    for pinyin in text.to_pinyin() {
+        let pinyin = pinyin?;
+        output.push_str(match style {
+            PinyinStyle::Full => pinyin.plain(),
+            PinyinStyle::Initials => pinyin.first_letter(),
+        });
+    }
+    (!output.is_empty()).then_some(output)
+}
+
+// Traditional -> Simplified and full-width -> half-width, char for char.
+fn fold_text(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+    fast2s::convert(text).chars().map(fold_width).collect()
+}
+
+fn fold_width(ch: char) -> char {
+    match ch {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(ch as u32 - 0xFEE0).unwrap_or(ch),
+        _ => ch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PinyinStyle, SearchTokenizer, TokenizerError};
+    use tantivy::tokenizer::TokenStream;
+
+    #[test]
+    fn user_dict_keeps_terms_whole_and_stop_words_are_removed() {
+        let stock = SearchTokenizer::default();
+        assert!(!stock.terms("墨石博客").contains(&"墨石博客".to_string()));
+
+        let custom =
+            SearchTokenizer::parse(Some("# terms\n墨石博客\n"), Some("的\nThe\n")).unwrap();
+        let custom_tokens = custom.terms("The 墨石博客的文章");
+        assert!(custom_tokens.contains(&"墨石博客".to_string()));
+        assert!(!custom_tokens.contains(&"的".to_string()));
+        assert!(!custom_tokens.contains(&"the".to_string()));
+    }
+
+    #[test]
+    fn traditional_and_full_width_text_is_folded_with_original_offsets() {
+        let tokenizer = SearchTokenizer::default();
+        let mut analyzer = tokenizer.analyzer();
+        let text = "臺灣的ＲＵＳＴ";
+        let mut stream = analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+        while stream.advance() {
+            let token = stream.token();
+            tokens.push((
+                token.text.clone(),
+                &text[token.offset_from..token.offset_to],
+            ));
+        }
+        assert!(tokens.contains(&("台湾".to_string(), "臺灣")));
+        assert!(tokens.contains(&("rust".to_string(), "ＲＵＳＴ")));
+    }
+
+    #[test]
+    fn pinyin_analyzer_emits_full_and_initials_for_hanzi_tokens() {
+        let tokenizer = SearchTokenizer::default();
+        for (style, expected) in [
+            (PinyinStyle::Full, "shujuku"),
+            (PinyinStyle::Initials, "sjk"),
+        ] {
+            let mut analyzer = tokenizer.pinyin_analyzer(style);
+            let mut stream = analyzer.token_stream("Rust 数据库");
+            let mut tokens = Vec::new();
+            while stream.advance() {
+                tokens.push(stream.token().text.clone());
+            }
+            assert!(tokens.contains(&expected.to_string()), "{tokens:?}");
+            assert!(!tokens.iter().any(|token| token.contains("rust")));
+        }
+    }
+
+    #[test]
+    fn fingerprint_tracks_configuration() {
+        let stock = SearchTokenizer::default();
+        let empty = SearchTokenizer::parse(Some("# nothing yet\n"), Some("")).unwrap();
+        assert_eq!(stock.fingerprint(), empty.fingerprint());
+
+        let dict = SearchTokenizer::parse(Some("墨石博客 1000 nz\n"), None).unwrap();
+        let stop = SearchTokenizer::parse(None, Some("的\n")).unwrap();
+        assert_ne!(dict.fingerprint(), stock.fingerprint());
+        assert_ne!(stop.fingerprint(), stock.fingerprint());
+        assert_ne!(dict.fingerprint(), stop.fingerprint());
+    }
+
+    #[test]
+    fn user_dict_rejects_invalid_frequency() {
+        let err = SearchTokenizer::parse(Some("墨石博客 lots\n"), None).unwrap_err();
+        assert!(matches!(
+            err,
+            TokenizerError::InvalidDictEntry { line: 1, .. }
+        ));
+    }
+}
@@ -0,0 +1,2 @@
+ALTER TABLE search_events
+    ADD COLUMN IF NOT EXISTS suggestion TEXT;
@@ -0,0 +1,6 @@
+ALTER TABLE search_events
+    ADD COLUMN IF NOT EXISTS clicked_url TEXT,
+    ADD COLUMN IF NOT EXISTS click_rank INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_search_events_user_hash_ts
+    ON search_events (search_user_hash, ts);
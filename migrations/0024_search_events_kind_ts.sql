@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS idx_search_events_kind_ts
+    ON search_events (kind, ts);